@@ -0,0 +1,92 @@
+//! Compares the cost of accessing an initialized lazy value
+//!
+//! Run with `cargo bench`. Every value is initialized before it is measured, so this only covers
+//! the hot path taken after initialization. The `deref` group measures accesses that can be
+//! inlined into the caller, and the `probe` group measures accesses through a function that is
+//! never inlined, like an access from code that the optimizer can't see into.
+
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate lazy_static;
+extern crate lazy_mut;
+extern crate once_cell;
+
+use criterion::{black_box, Criterion};
+use lazy_mut::{LazyMut, SyncLazy};
+use std::sync::LazyLock;
+
+static SYNC_LAZY: SyncLazy<u64> = SyncLazy::new(|| 42);
+static ONCE_CELL: once_cell::sync::Lazy<u64> = once_cell::sync::Lazy::new(|| 42);
+static LAZY_LOCK: LazyLock<u64> = LazyLock::new(|| 42);
+
+lazy_static! {
+    static ref LAZY_STATIC: u64 = 42;
+}
+
+#[inline(never)]
+fn probe_lazy_mut(lazy: &LazyMut<u64>) -> u64 {
+    **black_box(lazy)
+}
+
+#[inline(never)]
+fn probe_sync_lazy() -> u64 {
+    **black_box(&SYNC_LAZY)
+}
+
+#[inline(never)]
+fn probe_once_cell() -> u64 {
+    **black_box(&ONCE_CELL)
+}
+
+#[inline(never)]
+fn probe_lazy_lock() -> u64 {
+    **black_box(&LAZY_LOCK)
+}
+
+#[inline(never)]
+fn probe_lazy_static() -> u64 {
+    **black_box(&LAZY_STATIC)
+}
+
+fn lazy_mut() -> LazyMut<u64> {
+    let mut lazy: LazyMut<u64> = LazyMut::Init(|| 42);
+    lazy.init();
+    lazy
+}
+
+fn init_statics() {
+    SYNC_LAZY.init();
+    once_cell::sync::Lazy::force(&ONCE_CELL);
+    LazyLock::force(&LAZY_LOCK);
+    lazy_static::initialize(&LAZY_STATIC);
+}
+
+fn deref(c: &mut Criterion) {
+    let lazy_mut = lazy_mut();
+    init_statics();
+
+    let mut group = c.benchmark_group("deref");
+    group.bench_function("LazyMut", |b| b.iter(|| **black_box(&lazy_mut)));
+    group.bench_function("SyncLazy", |b| b.iter(|| **black_box(&SYNC_LAZY)));
+    group.bench_function("once_cell::sync::Lazy", |b| b.iter(|| **black_box(&ONCE_CELL)));
+    group.bench_function("std::sync::LazyLock", |b| b.iter(|| **black_box(&LAZY_LOCK)));
+    group.bench_function("lazy_static", |b| b.iter(|| **black_box(&LAZY_STATIC)));
+    group.finish();
+}
+
+fn probe(c: &mut Criterion) {
+    let lazy_mut = lazy_mut();
+    init_statics();
+
+    let mut group = c.benchmark_group("probe");
+    group.bench_function("LazyMut", |b| b.iter(|| probe_lazy_mut(&lazy_mut)));
+    group.bench_function("SyncLazy", |b| b.iter(probe_sync_lazy));
+    group.bench_function("once_cell::sync::Lazy", |b| b.iter(probe_once_cell));
+    group.bench_function("std::sync::LazyLock", |b| b.iter(probe_lazy_lock));
+    group.bench_function("lazy_static", |b| b.iter(probe_lazy_static));
+    group.finish();
+}
+
+criterion_group!(benches, deref, probe);
+criterion_main!(benches);
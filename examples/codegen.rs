@@ -0,0 +1,51 @@
+//! Accesses to lazy values whose generated code is checked by `tests/codegen.rs`
+//!
+//! Each probe is exported under its own name so that it can be found in the assembly. The probes
+//! are run once from `main`, so this also works as an ordinary example.
+
+extern crate lazy_mut;
+
+use lazy_mut::{CompactLazy, LazyMut, SyncLazy};
+use std::ops::DerefMut;
+
+static SYNC_LAZY: SyncLazy<u64> = SyncLazy::new(|| 42);
+
+/// Reads an initialized `LazyMut`, which should be a check of the discriminant and a load
+#[no_mangle]
+#[inline(never)]
+pub fn probe_lazy_mut_deref(lazy: &LazyMut<u64>) -> u64 {
+    **lazy
+}
+
+/// Initializes a `LazyMut` if needed, which should only call out to run the initializer
+#[no_mangle]
+#[inline(never)]
+pub fn probe_lazy_mut_init(lazy: &mut LazyMut<Vec<u64>>) -> usize {
+    lazy.init().len()
+}
+
+/// Reads a `SyncLazy` static, which should be a single load of the state and a branch
+#[no_mangle]
+#[inline(never)]
+pub fn probe_sync_lazy_deref() -> u64 {
+    *SYNC_LAZY
+}
+
+/// Reads a `CompactLazy` through `DerefMut`, initializing it if needed
+#[no_mangle]
+#[inline(never)]
+pub fn probe_compact_lazy_deref_mut(lazy: &mut CompactLazy<u64>) -> u64 {
+    *DerefMut::deref_mut(lazy)
+}
+
+fn main() {
+    let mut lazy_mut: LazyMut<u64> = LazyMut::Init(|| 42);
+    lazy_mut.init();
+    let mut vec: LazyMut<Vec<u64>> = LazyMut::Init(|| vec![1, 2, 3]);
+    let mut compact: CompactLazy<u64> = CompactLazy::new(|| 42);
+
+    assert_eq!(probe_lazy_mut_deref(&lazy_mut), 42);
+    assert_eq!(probe_lazy_mut_init(&mut vec), 3);
+    assert_eq!(probe_sync_lazy_deref(), 42);
+    assert_eq!(probe_compact_lazy_deref_mut(&mut compact), 42);
+}
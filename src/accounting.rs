@@ -0,0 +1,141 @@
+//! Memory allocated by the initializers of registered lazy statics
+//!
+//! With the `alloc-accounting` feature enabled, installing a `CountingAllocator` as the global
+//! allocator lets the registry count the bytes that each registered lazy static allocates while
+//! its initializer runs. The bytes that the initializer didn't free again are usually what the
+//! value keeps, so this finds the lazy statics that take up the most memory:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::accounting::CountingAllocator;
+//! use std::alloc::System;
+//!
+//! #[global_allocator]
+//! static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+//!
+//! lazy_mut! {
+//!     static TABLE: Vec<u64> = {
+//!         let scratch = vec![0u8; 4096];
+//!         drop(scratch);
+//!         vec![0; 1024]
+//!     };
+//! }
+//!
+//! # fn main() {
+//! TABLE.init();
+//!
+//! let entry = lazy_mut::registry::entries().find(|entry| entry.name() == "TABLE").unwrap();
+//! let allocation = entry.allocation().unwrap();
+//! assert!(allocation.allocated() >= 4096 + 8 * 1024);
+//! assert!(allocation.retained() >= 8 * 1024);
+//! assert!(allocation.retained() < 4096 + 8 * 1024);
+//! # }
+//! ```
+//!
+//! Only allocations made on the thread running the initializer are counted, including those of
+//! any lazy statics that it initializes in turn. Without a `CountingAllocator`, nothing is counted
+//! and `Entry::allocation` always returns `None`.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::alloc::System;
+
+/// A global allocator that counts the bytes allocated and freed by each thread, wrapping another
+/// allocator
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Creates a counting allocator that allocates with `inner`
+    pub const fn new(inner: A) -> CountingAllocator<A> {
+        CountingAllocator { inner }
+    }
+}
+
+std::thread_local! {
+    /// The bytes allocated and freed by this thread so far
+    static COUNTS: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+/// Whether a `CountingAllocator` has been used, so that counts of zero aren't reported otherwise
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+fn count(allocated: usize, freed: usize) {
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+    // The counts are gone while the thread is being torn down, and nothing is measured then
+    let _ = COUNTS.try_with(|counts| {
+        let (a, f) = counts.get();
+        counts.set((a.wrapping_add(allocated), f.wrapping_add(freed)));
+    });
+}
+
+unsafe impl<A> GlobalAlloc for CountingAllocator<A> where A: GlobalAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size(), 0);
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size(), 0);
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(0, layout.size());
+        self.inner.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size, layout.size());
+        self.inner.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Returns the bytes allocated and freed by this thread so far, or `None` if no
+/// `CountingAllocator` is in use
+pub(crate) fn counts() -> Option<(usize, usize)> {
+    if INSTALLED.load(Ordering::Relaxed) {
+        COUNTS.try_with(Cell::get).ok()
+    } else {
+        None
+    }
+}
+
+/// The memory allocated while a lazy static was being initialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitAllocation {
+    allocated: usize,
+    freed: usize,
+}
+
+impl InitAllocation {
+    pub(crate) fn between(start: (usize, usize), end: (usize, usize)) -> InitAllocation {
+        InitAllocation {
+            allocated: end.0.wrapping_sub(start.0),
+            freed: end.1.wrapping_sub(start.1),
+        }
+    }
+
+    /// Returns the total number of bytes allocated by the initializer
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+
+    /// Returns the number of bytes freed by the initializer
+    pub fn freed(&self) -> usize {
+        self.freed
+    }
+
+    /// Returns the number of bytes allocated by the initializer that it didn't free again
+    ///
+    /// This is zero if the initializer freed more than it allocated.
+    pub fn retained(&self) -> usize {
+        self.allocated.saturating_sub(self.freed)
+    }
+}
@@ -0,0 +1,87 @@
+use std::string::{String, ToString};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+use std::vec::Vec;
+
+/// The thread that initialized each `static mut` declared with the `lazy_mut` macro
+struct Owner {
+    address: *const (),
+    name: &'static str,
+    thread: ThreadId,
+    thread_name: String,
+}
+
+// The addresses are only compared, never dereferenced
+unsafe impl Send for Owner {}
+
+static OWNERS: Mutex<Vec<Owner>> = Mutex::new(Vec::new());
+
+/// Whether any value has been claimed, so that accesses don't lock `OWNERS` until then
+static CLAIMED: AtomicBool = AtomicBool::new(false);
+
+/// Records the current thread as the owner of a static, or checks that it already is
+#[doc(hidden)]
+#[track_caller]
+pub fn claim<T>(lazy: *const T, name: &'static str) {
+    if cfg!(debug_assertions) {
+        let address = lazy as *const ();
+        let mut owners = lock();
+        if !owners.iter().any(|owner| owner.address == address) {
+            let thread = thread::current();
+            owners.push(Owner {
+                address,
+                name,
+                thread: thread.id(),
+                thread_name: thread_name(&thread),
+            });
+            CLAIMED.store(true, Ordering::Release);
+        }
+        drop(owners);
+        check(lazy);
+    }
+}
+
+/// Panics if a static was initialized by a thread other than the current one
+#[inline]
+#[track_caller]
+pub fn check<T>(lazy: *const T) {
+    if cfg!(debug_assertions) && CLAIMED.load(Ordering::Acquire) {
+        check_owner(lazy as *const ());
+    }
+}
+
+#[track_caller]
+fn check_owner(address: *const ()) {
+    let owners = lock();
+    let owner = match owners.iter().find(|owner| owner.address == address) {
+        Some(owner) => owner,
+        None => return,
+    };
+    let thread = thread::current();
+    if owner.thread != thread.id() {
+        let (name, owner_name) = (owner.name, owner.thread_name.clone());
+        drop(owners);
+        panic!("lazy static `{}` was initialized on thread `{}`, but accessed on thread `{}`",
+            name, owner_name, thread_name(&thread));
+    }
+}
+
+fn lock() -> ::std::sync::MutexGuard<'static, Vec<Owner>> {
+    match OWNERS.lock() {
+        Ok(owners) => owners,
+        Err(err) => err.into_inner(),
+    }
+}
+
+fn thread_name(thread: &thread::Thread) -> String {
+    thread.name().unwrap_or("<unnamed>").to_string()
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_claim {
+    ($N:ident) => {
+        $crate::__claim($crate::__addr_of!($N), stringify!($N))
+    };
+}
@@ -0,0 +1,162 @@
+//! Zero-copy archiving of lazy values with `rkyv`
+
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+use core::ops::Deref;
+use rkyv::{Archive, Deserialize, Place, Portable, Serialize};
+use rkyv::bytecheck::CheckBytes;
+use rkyv::rancor::{Fallible, Source};
+use LazyMut;
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+use SyncLazy;
+
+/// The archived form of a lazy value, which is the archived form of its value
+///
+/// With the `rkyv` feature, an initialized `LazyMut<T>` or `SyncLazy<T>` can be archived, and its
+/// archived form dereferences to the archived form of `T` without any copying. Deserializing it
+/// gives back a lazy value that is already initialized, so a table that is expensive to build can
+/// be built once, saved, and mapped back in on the next run:
+///
+/// ```
+/// # #[cfg(feature = "rkyv")]
+/// extern crate rkyv;
+/// # extern crate lazy_mut;
+///
+/// # #[cfg(feature = "rkyv")]
+/// # fn main() {
+/// use lazy_mut::LazyMut;
+/// use rkyv::rancor::Error;
+///
+/// let mut squares = LazyMut::Init(|| (0..16u32).map(|n| n * n).collect::<Vec<_>>());
+/// squares.init();
+///
+/// let bytes = rkyv::to_bytes::<Error>(&squares).unwrap();
+/// let archived = rkyv::access::<rkyv::Archived<LazyMut<Vec<u32>>>, Error>(&bytes).unwrap();
+/// assert_eq!(archived[3], 9);
+///
+/// let restored: LazyMut<Vec<u32>> = rkyv::deserialize::<_, Error>(archived).unwrap();
+/// assert!(restored.is_initialized());
+/// assert_eq!(*restored, *squares);
+/// # }
+/// # #[cfg(not(feature = "rkyv"))]
+/// # fn main() {}
+/// ```
+///
+/// Archiving a value that isn't initialized fails with an `UninitializedError`.
+///
+/// Only available with the `rkyv` feature.
+#[repr(transparent)]
+pub struct ArchivedLazy<A>(A);
+
+// The archived value is the only field
+unsafe impl<A> Portable for ArchivedLazy<A> where A: Portable {}
+
+unsafe impl<A, C> CheckBytes<C> for ArchivedLazy<A> where A: CheckBytes<C>, C: Fallible + ?Sized {
+    unsafe fn check_bytes(value: *const ArchivedLazy<A>, context: &mut C) -> Result<(), C::Error> {
+        A::check_bytes(value as *const A, context)
+    }
+}
+
+impl<A> Deref for ArchivedLazy<A> {
+    type Target = A;
+    #[inline]
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A> Debug for ArchivedLazy<A> where A: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ArchivedLazy").field(&self.0).finish()
+    }
+}
+
+/// Archives the value of a lazy value, after the caller has checked that it is initialized
+fn resolve<T>(val: Option<&T>, resolver: T::Resolver, out: Place<ArchivedLazy<T::Archived>>)
+    where T: Archive
+{
+    match val {
+        // The archived form has the same layout as the archived value
+        Some(val) => val.resolve(resolver, unsafe { out.cast_unchecked() }),
+        None => unreachable!(),
+    }
+}
+
+/// Serializes the value of a lazy value, failing if it is uninitialized
+fn serialize<T, S>(val: Option<&T>, serializer: &mut S) -> Result<T::Resolver, S::Error>
+    where T: Serialize<S>, S: Fallible + ?Sized, S::Error: Source
+{
+    match val {
+        Some(val) => val.serialize(serializer),
+        None => Err(S::Error::new(UninitializedError)),
+    }
+}
+
+impl<T> Archive for LazyMut<T> where T: Archive {
+    type Archived = ArchivedLazy<T::Archived>;
+    type Resolver = T::Resolver;
+
+    fn resolve(&self, resolver: T::Resolver, out: Place<ArchivedLazy<T::Archived>>) {
+        resolve(LazyMut::get(self), resolver, out)
+    }
+}
+
+impl<T, S> Serialize<S> for LazyMut<T>
+    where T: Serialize<S>, S: Fallible + ?Sized, S::Error: Source
+{
+    fn serialize(&self, serializer: &mut S) -> Result<T::Resolver, S::Error> {
+        serialize(LazyMut::get(self), serializer)
+    }
+}
+
+impl<T, D> Deserialize<LazyMut<T>, D> for ArchivedLazy<T::Archived>
+    where T: Archive, T::Archived: Deserialize<T, D>, D: Fallible + ?Sized
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<LazyMut<T>, D::Error> {
+        self.0.deserialize(deserializer).map(LazyMut::Value)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+impl<T, F> Archive for SyncLazy<T, F> where T: Archive {
+    type Archived = ArchivedLazy<T::Archived>;
+    type Resolver = T::Resolver;
+
+    fn resolve(&self, resolver: T::Resolver, out: Place<ArchivedLazy<T::Archived>>) {
+        resolve(SyncLazy::get(self), resolver, out)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+impl<T, F, S> Serialize<S> for SyncLazy<T, F>
+    where T: Serialize<S>, S: Fallible + ?Sized, S::Error: Source
+{
+    fn serialize(&self, serializer: &mut S) -> Result<T::Resolver, S::Error> {
+        serialize(SyncLazy::get(self), serializer)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+impl<T, F, D> Deserialize<SyncLazy<T, F>, D> for ArchivedLazy<T::Archived>
+    where T: Archive, T::Archived: Deserialize<T, D>, D: Fallible + ?Sized
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<SyncLazy<T, F>, D::Error> {
+        self.0.deserialize(deserializer).map(SyncLazy::with_value)
+    }
+}
+
+/// An error from archiving a lazy value that isn't initialized
+///
+/// Only initialized values can be archived, since there is no way to archive an initializer.
+///
+/// Only available with the `rkyv` feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UninitializedError;
+
+impl Display for UninitializedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot archive an uninitialized lazy value")
+    }
+}
+
+impl Error for UninitializedError {}
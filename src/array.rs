@@ -0,0 +1,141 @@
+use core::mem::MaybeUninit;
+use core::fmt::{self, Debug};
+use core::ptr;
+
+/// An array of `N` lazy values that share a single initializer
+///
+/// The initializer is called with the index of an element the first time that element is
+/// accessed through `LazyArray::get` or `LazyArray::get_mut`. Only one function pointer is stored
+/// for the whole array, along with a flag for each element recording whether it is initialized,
+/// so the array is about as big as `[T; N]` itself. This is useful for lookup tables where only a
+/// few entries are ever used.
+///
+/// ```
+/// use lazy_mut::LazyArray;
+///
+/// fn square(i: usize) -> u64 {
+///     (i * i) as u64
+/// }
+///
+/// let mut squares: LazyArray<u64, 1024> = LazyArray::new(square);
+///
+/// assert_eq!(*squares.get(12), 144);
+/// *squares.get_mut(3) += 1;
+///
+/// assert_eq!(squares.try_get(3), Some(&10));
+/// assert_eq!(squares.try_get(4), None);
+/// assert_eq!(squares.iter().collect::<Vec<_>>(), [(3, &10), (12, &144)]);
+/// ```
+pub struct LazyArray<T, const N: usize> {
+    init: fn(usize) -> T,
+    ready: [bool; N],
+    values: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> LazyArray<T, N> {
+    /// Creates a new array of uninitialized values with an initializer
+    pub const fn new(init: fn(usize) -> T) -> LazyArray<T, N> {
+        LazyArray {
+            init,
+            ready: [false; N],
+            values: [const { MaybeUninit::uninit() }; N],
+        }
+    }
+
+    /// Returns a reference to the element at an index, initializing it if needed
+    ///
+    /// Panics if the index is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn get(&mut self, index: usize) -> &T {
+        self.get_mut(index)
+    }
+
+    /// Returns a mutable reference to the element at an index, initializing it if needed
+    ///
+    /// Panics if the index is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn get_mut(&mut self, index: usize) -> &mut T {
+        if !self.ready[index] {
+            self.initialize(index);
+        }
+        unsafe { &mut *self.values[index].as_mut_ptr() }
+    }
+
+    /// Tries to get a reference to the element at an index, returns `None` if it is uninitialized
+    ///
+    /// Panics if the index is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn try_get(&self, index: usize) -> Option<&T> {
+        match self.ready[index] {
+            true => Some(unsafe { &*self.values[index].as_ptr() }),
+            false => None,
+        }
+    }
+
+    /// Tries to get a mutable reference to the element at an index, returns `None` if it is
+    /// uninitialized
+    ///
+    /// Panics if the index is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn try_get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match self.ready[index] {
+            true => Some(unsafe { &mut *self.values[index].as_mut_ptr() }),
+            false => None,
+        }
+    }
+
+    /// Returns `true` if the element at an index has been initialized
+    ///
+    /// Panics if the index is out of bounds.
+    #[inline]
+    #[track_caller]
+    pub fn is_initialized(&self, index: usize) -> bool {
+        self.ready[index]
+    }
+
+    /// Returns the number of elements in the array, including uninitialized ones
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns `true` if the array has no elements
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns an iterator over the indices and values of the initialized elements
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
+        (0..N).filter_map(move |index| self.try_get(index).map(|val| (index, val)))
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&mut self, index: usize) {
+        let init = self.init;
+        self.values[index] = MaybeUninit::new(::trace::init(|| init(index)));
+        self.ready[index] = true;
+    }
+}
+
+impl<T, const N: usize> Drop for LazyArray<T, N> {
+    fn drop(&mut self) {
+        for (ready, value) in self.ready.iter().zip(self.values.iter_mut()) {
+            if *ready {
+                unsafe { ptr::drop_in_place(value.as_mut_ptr()) }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Debug for LazyArray<T, N> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
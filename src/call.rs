@@ -0,0 +1,22 @@
+use core::marker::Tuple;
+use LazyMut;
+
+impl<F, Args: Tuple> FnOnce<Args> for LazyMut<F> where F: FnOnce<Args> {
+    type Output = F::Output;
+
+    extern "rust-call" fn call_once(self, args: Args) -> F::Output {
+        self.unwrap().call_once(args)
+    }
+}
+
+impl<F, Args: Tuple> FnMut<Args> for LazyMut<F> where F: FnMut<Args> {
+    extern "rust-call" fn call_mut(&mut self, args: Args) -> F::Output {
+        (**self).call_mut(args)
+    }
+}
+
+impl<F, Args: Tuple> Fn<Args> for LazyMut<F> where F: Fn<Args> {
+    extern "rust-call" fn call(&self, args: Args) -> F::Output {
+        (**self).call(args)
+    }
+}
@@ -0,0 +1,158 @@
+use core::fmt::{self, Debug};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::{Mutex, MutexGuard};
+
+/// A channel that is created the first time either of its ends is used
+///
+/// This holds one `Sender` that any thread can send through, or clone with
+/// `LazyChannel::sender`, and a `Receiver` that can be taken once by the thread that handles the
+/// messages:
+///
+/// ```
+/// use lazy_mut::LazyChannel;
+/// use std::thread;
+///
+/// static EVENTS: LazyChannel<String> = LazyChannel::new();
+///
+/// let receiver = EVENTS.take_receiver().unwrap();
+/// let handler = thread::spawn(move || receiver.iter().collect::<Vec<_>>());
+///
+/// EVENTS.send("started".to_string()).unwrap();
+/// EVENTS.sender().send("stopped".to_string()).unwrap();
+///
+/// EVENTS.close();
+/// assert_eq!(handler.join().unwrap(), ["started", "stopped"]);
+/// ```
+///
+/// Since the channel lives in a `static`, its sender is never dropped on its own, so a receiver
+/// iterating over it would wait forever. `LazyChannel::close` drops the sender, ending the
+/// iteration once every message (and every clone of the sender) is gone. Sending through the
+/// channel after it has been closed fails, just like sending after the receiver has been dropped.
+///
+/// Only available with the `std` feature.
+pub struct LazyChannel<T> {
+    state: Mutex<State<T>>,
+}
+
+struct State<T> {
+    created: bool,
+    sender: Option<Sender<T>>,
+    receiver: Option<Receiver<T>>,
+}
+
+impl<T> LazyChannel<T> {
+    /// Creates a new channel that doesn't exist yet
+    pub const fn new() -> LazyChannel<T> {
+        LazyChannel { state: Mutex::new(State { created: false, sender: None, receiver: None }) }
+    }
+
+    /// Sends a value through the channel, creating it if needed
+    ///
+    /// This returns the value back if the channel has been closed or its receiver has been
+    /// dropped.
+    pub fn send(&self, val: T) -> Result<(), SendError<T>> {
+        match self.lock().sender {
+            Some(ref sender) => sender.send(val),
+            None => Err(SendError(val)),
+        }
+    }
+
+    /// Returns a clone of the sender, creating the channel if needed
+    ///
+    /// If the channel has been closed, this returns a sender that can't send anything.
+    pub fn sender(&self) -> Sender<T> {
+        match self.lock().sender {
+            Some(ref sender) => sender.clone(),
+            None => mpsc::channel().0,
+        }
+    }
+
+    /// Takes the receiver, creating the channel if needed, or returns `None` if it has already
+    /// been taken
+    pub fn take_receiver(&self) -> Option<Receiver<T>> {
+        self.lock().receiver.take()
+    }
+
+    /// Drops the sender held by the channel, so that the receiver is disconnected once every
+    /// clone of it has been dropped as well
+    pub fn close(&self) {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(err) => err.into_inner(),
+        };
+        state.created = true;
+        state.sender = None;
+    }
+
+    /// Returns `true` if the channel has been created
+    pub fn is_initialized(&self) -> bool {
+        match self.state.lock() {
+            Ok(state) => state.created,
+            Err(err) => err.into_inner().created,
+        }
+    }
+
+    /// Locks the state, creating the channel if it doesn't exist yet
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(err) => err.into_inner(),
+        };
+        if !state.created {
+            let (sender, receiver) = mpsc::channel();
+            *state = State { created: true, sender: Some(sender), receiver: Some(receiver) };
+        }
+        state
+    }
+}
+
+impl<T> Default for LazyChannel<T> {
+    fn default() -> LazyChannel<T> {
+        LazyChannel::new()
+    }
+}
+
+impl<T> Debug for LazyChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            Err(err) => err.into_inner(),
+        };
+        match (state.created, state.sender.is_some()) {
+            (false, _) => write!(f, "LazyChannel({{uninitialized}})"),
+            (true, true) => write!(f, "LazyChannel({{open}})"),
+            (true, false) => write!(f, "LazyChannel({{closed}})"),
+        }
+    }
+}
+
+/// A macro that declares lazily created channels
+///
+/// Each `static NAME: TY;` declares a `LazyChannel<TY>`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// lazy_channel! {
+///     /// Log lines waiting to be written
+///     pub static LOG_LINES: String;
+/// }
+///
+/// # fn main() {
+/// LOG_LINES.send("hello".to_string()).unwrap();
+/// let receiver = LOG_LINES.take_receiver().unwrap();
+/// assert_eq!(receiver.recv().unwrap(), "hello");
+/// # }
+/// ```
+///
+/// Only available with the `std` feature.
+#[macro_export]
+macro_rules! lazy_channel {
+    (/* empty */) => {};
+    ($(#[$attr:meta])* $vis:vis static $N:ident: $T:ty; $($t:tt)*) => {
+        $(#[$attr])*
+        $vis static $N: $crate::LazyChannel<$T> = $crate::LazyChannel::new();
+        lazy_channel!($($t)*);
+    };
+}
@@ -0,0 +1,256 @@
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use core::panic::Location;
+use core::ptr;
+use {AlreadyInitialized, Initializer};
+use location::InitLocation;
+
+const UNINIT: u8 = 0;
+const READY: u8 = 1;
+const POISONED: u8 = 2;
+
+/// A mutable lazy value stored as the value itself and a single state byte
+///
+/// `LazyMut<T>` is as big as the larger of `T` and a function pointer, plus a discriminant padded
+/// out to the alignment of the function pointer. `CompactLazy<T, F>` instead stores the
+/// initializer separately, so when the initializer is zero-sized (such as a function item or a
+/// closure that doesn't capture anything), the only overhead in release builds is a single byte.
+/// Debug builds also store the location of the call that initialized the value (see
+/// `CompactLazy::initialized_at`).
+///
+/// Otherwise, this behaves just like `LazyMut`, including implicit initialization through
+/// `DerefMut`.
+///
+/// ```
+/// use lazy_mut::{CompactLazy, LazyMut};
+/// use std::mem::size_of_val;
+///
+/// let mut buffer = CompactLazy::new(|| [0u8; 4096]);
+/// let lazy_buffer: LazyMut<[u8; 4096]> = LazyMut::Init(|| [0u8; 4096]);
+///
+/// buffer[0] = 1;
+/// assert_eq!(buffer[..2], [1, 0]);
+///
+/// # if CompactLazy::initialized_at(&buffer).is_none() {
+/// assert_eq!(size_of_val(&buffer), 4097);
+/// # }
+/// assert_eq!(size_of_val(&lazy_buffer), 4104);
+/// ```
+pub struct CompactLazy<T, F = fn() -> T> {
+    state: u8,
+    init: MaybeUninit<F>,
+    value: MaybeUninit<T>,
+    location: InitLocation,
+}
+
+impl<T, F> CompactLazy<T, F> where F: Initializer<T> {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> CompactLazy<T, F> {
+        CompactLazy {
+            state: UNINIT,
+            init: MaybeUninit::new(init),
+            value: MaybeUninit::uninit(),
+            location: InitLocation::new(),
+        }
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        match CompactLazy::into_inner(self) {
+            Ok(val) => val,
+            Err(init) => ::trace::init(|| init.run()),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized
+    #[inline]
+    #[track_caller]
+    pub fn init(&mut self) -> &mut CompactLazy<T, F> {
+        if self.state == UNINIT {
+            self.initialize();
+        }
+        self
+    }
+
+    /// Initializes the wrapped value, panicking if it was already initialized
+    #[track_caller]
+    pub fn init_once(&mut self) -> &mut CompactLazy<T, F> {
+        match self.state {
+            UNINIT => self.init(),
+            _ => ::already_initialized(CompactLazy::initialized_at(self)),
+        }
+    }
+
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    #[track_caller]
+    pub fn try_init_once(&mut self) -> Result<&mut T, AlreadyInitialized> {
+        if self.state != UNINIT {
+            return Err(AlreadyInitialized { location: CompactLazy::initialized_at(self) });
+        }
+        self.initialize();
+        Ok(unsafe { &mut *self.value.as_mut_ptr() })
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&mut self) {
+        self.location.set();
+        self.state = POISONED;
+        let init = unsafe { ptr::read(self.init.as_ptr()) };
+        self.value = MaybeUninit::new(::trace::init(|| init.run()));
+        self.state = READY;
+    }
+}
+
+impl<T, F> CompactLazy<T, F> {
+    /// Creates a new value that is already initialized
+    pub const fn with_value(val: T) -> CompactLazy<T, F> {
+        CompactLazy {
+            state: READY,
+            init: MaybeUninit::uninit(),
+            value: MaybeUninit::new(val),
+            location: InitLocation::new(),
+        }
+    }
+
+    /// Returns the wrapped value, or the initializer if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`CompactLazy::into_inner(VAL)`)
+    #[track_caller]
+    pub fn into_inner(this: CompactLazy<T, F>) -> Result<T, F> {
+        let this = ManuallyDrop::new(this);
+        match this.state {
+            UNINIT => Err(unsafe { ptr::read(this.init.as_ptr()) }),
+            READY => Ok(unsafe { ptr::read(this.value.as_ptr()) }),
+            _ => poisoned(),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`CompactLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &CompactLazy<T, F>) -> Option<&T> {
+        match this.state {
+            READY => Some(unsafe { &*this.value.as_ptr() }),
+            _ => None,
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`CompactLazy::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut CompactLazy<T, F>) -> Option<&mut T> {
+        match this.state {
+            READY => Some(unsafe { &mut *this.value.as_mut_ptr() }),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state == READY
+    }
+
+    /// Returns the location of the call that initialized the value
+    ///
+    /// This is only tracked in debug builds, so it always returns `None` in release builds.
+    ///
+    /// Uses associated function syntax (`CompactLazy::initialized_at(&VAL)`)
+    pub fn initialized_at(this: &CompactLazy<T, F>) -> Option<&'static Location<'static>> {
+        this.location.get()
+    }
+}
+
+impl<T, F> Drop for CompactLazy<T, F> {
+    fn drop(&mut self) {
+        match self.state {
+            UNINIT => unsafe { ptr::drop_in_place(self.init.as_mut_ptr()) },
+            READY => unsafe { ptr::drop_in_place(self.value.as_mut_ptr()) },
+            _ => {}
+        }
+    }
+}
+
+impl<T, F> Deref for CompactLazy<T, F> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        match self.state {
+            UNINIT => ::uninitialized::<T>(None),
+            READY => unsafe { &*self.value.as_ptr() },
+            _ => poisoned(),
+        }
+    }
+}
+
+impl<T, F> DerefMut for CompactLazy<T, F> where F: Initializer<T> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        if self.state == UNINIT {
+            ::trace::implicit_init::<T>();
+        }
+        self.init();
+        match CompactLazy::get_mut(self) {
+            Some(val) => val,
+            None => poisoned(),
+        }
+    }
+}
+
+impl<T, F> Clone for CompactLazy<T, F> where T: Clone, F: Clone {
+    fn clone(&self) -> CompactLazy<T, F> {
+        match self.state {
+            UNINIT => CompactLazy {
+                state: UNINIT,
+                init: MaybeUninit::new(unsafe { &*self.init.as_ptr() }.clone()),
+                value: MaybeUninit::uninit(),
+                location: self.location,
+            },
+            READY => CompactLazy {
+                state: READY,
+                init: MaybeUninit::uninit(),
+                value: MaybeUninit::new(unsafe { &*self.value.as_ptr() }.clone()),
+                location: self.location,
+            },
+            state => CompactLazy {
+                state,
+                init: MaybeUninit::uninit(),
+                value: MaybeUninit::uninit(),
+                location: self.location,
+            },
+        }
+    }
+}
+
+impl<T, F> Debug for CompactLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match CompactLazy::get(self) {
+            Some(val) => f.debug_tuple("CompactLazy").field(val).finish(),
+            None => write!(f, "CompactLazy({{uninitialized}})"),
+        }
+    }
+}
+
+impl<T, F> Display for CompactLazy<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match CompactLazy::get(self) {
+            Some(val) => val.fmt(f),
+            None => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn poisoned() -> ! {
+    panic!("initializer of lazy value previously panicked")
+}
@@ -0,0 +1,183 @@
+use core::any::{self, Any, TypeId};
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::vec::Vec;
+
+/// Values provided to the initializers of `ContextLazy` values
+///
+/// A context holds at most one value of each type, such as the configuration or handles that
+/// are set up at startup. Passing them to initializers through a context, instead of having
+/// initializers read other lazy statics, makes the dependencies of each lazy value explicit, and
+/// lets tests initialize it with a different context.
+///
+/// Only available with the `std` feature.
+#[derive(Default)]
+pub struct InitContext {
+    values: HashMap<TypeId, (&'static str, Box<dyn Any + Send + Sync>)>,
+}
+
+/// The context installed with `InitContext::install`
+static GLOBAL: OnceLock<InitContext> = OnceLock::new();
+
+impl InitContext {
+    /// Creates a new empty context
+    pub fn new() -> InitContext {
+        InitContext { values: HashMap::new() }
+    }
+
+    /// Adds a value to the context, replacing any value of the same type
+    pub fn with<T>(mut self, val: T) -> InitContext where T: Any + Send + Sync {
+        self.insert(val);
+        self
+    }
+
+    /// Adds a value to the context, returning the value of the same type it replaced if there was
+    /// one
+    pub fn insert<T>(&mut self, val: T) -> Option<T> where T: Any + Send + Sync {
+        let entry = (any::type_name::<T>(), Box::new(val) as Box<dyn Any + Send + Sync>);
+        let previous = self.values.insert(TypeId::of::<T>(), entry);
+        previous.and_then(|(_, val)| val.downcast().ok()).map(|val| *val)
+    }
+
+    /// Returns a reference to the value of a type, panicking if the context doesn't have one
+    #[track_caller]
+    pub fn get<T>(&self) -> &T where T: Any {
+        match self.try_get() {
+            Some(val) => val,
+            None => panic!("init context has no value of type `{}`", any::type_name::<T>()),
+        }
+    }
+
+    /// Returns a reference to the value of a type, or `None` if the context doesn't have one
+    pub fn try_get<T>(&self) -> Option<&T> where T: Any {
+        self.values.get(&TypeId::of::<T>()).and_then(|(_, val)| val.downcast_ref())
+    }
+
+    /// Installs this as the context used to initialize every `ContextLazy`, returning it back if a
+    /// context has already been installed
+    pub fn install(self) -> Result<(), InitContext> {
+        GLOBAL.set(self)
+    }
+
+    /// Returns the context installed with `InitContext::install`, or `None` if there isn't one
+    pub fn global() -> Option<&'static InitContext> {
+        GLOBAL.get()
+    }
+}
+
+impl Debug for InitContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("InitContext")
+            .field(&self.values.values().map(|&(name, _)| name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A thread-safe lazy value whose initializer receives an `InitContext`
+///
+/// On first access, the initializer is called with the context installed with
+/// `InitContext::install`, so that it only depends on the values it asks the context for:
+///
+/// ```
+/// use lazy_mut::{ContextLazy, InitContext};
+///
+/// struct Config {
+///     database_url: String,
+/// }
+///
+/// static POOL: ContextLazy<String> = ContextLazy::new(|context| {
+///     format!("pool for {}", context.get::<Config>().database_url)
+/// });
+///
+/// InitContext::new()
+///     .with(Config { database_url: "postgres://localhost".to_string() })
+///     .install()
+///     .unwrap();
+///
+/// assert_eq!(*POOL, "pool for postgres://localhost");
+/// ```
+///
+/// Tests can use `ContextLazy::init_with` to initialize a value with a context of their own
+/// instead:
+///
+/// ```
+/// use lazy_mut::{ContextLazy, InitContext};
+///
+/// let doubled = ContextLazy::new(|context| context.get::<u32>() * 2);
+/// let context = InitContext::new().with(21u32);
+///
+/// assert_eq!(*doubled.init_with(&context), 42);
+/// ```
+///
+/// Accessing the value before a context has been installed panics, and leaves the value
+/// uninitialized.
+///
+/// Only available with the `std` feature.
+pub struct ContextLazy<T> {
+    init: fn(&InitContext) -> T,
+    value: OnceLock<T>,
+}
+
+impl<T> ContextLazy<T> {
+    /// Creates a new uninitialized value with an initializer that receives a context
+    pub const fn new(init: fn(&InitContext) -> T) -> ContextLazy<T> {
+        ContextLazy { init, value: OnceLock::new() }
+    }
+
+    /// Initializes the wrapped value with the installed context if it is uninitialized, returning
+    /// a reference to it
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish.
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        if let Some(val) = self.value.get() {
+            return val;
+        }
+        match InitContext::global() {
+            Some(context) => self.init_with(context),
+            None => panic!("lazy value accessed before an `InitContext` was installed"),
+        }
+    }
+
+    /// Initializes the wrapped value with a context if it is uninitialized, returning a reference
+    /// to it
+    #[track_caller]
+    pub fn init_with(&self, context: &InitContext) -> &T {
+        self.value.get_or_init(|| ::trace::init(|| (self.init)(context)))
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`ContextLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &ContextLazy<T>) -> Option<&T> {
+        this.value.get()
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Deref for ContextLazy<T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T> Debug for ContextLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ContextLazy::get(self) {
+            Some(val) => f.debug_tuple("ContextLazy").field(val).finish(),
+            None => write!(f, "ContextLazy({{uninitialized}})"),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+
+/// A value that borrows a `'static` default until it is first accessed mutably
+///
+/// Reading a `CowLazy<T>` reads the default without running anything. The initializer only runs
+/// on the first mutable access, to build an owned copy of the default that can then be modified.
+/// When most values are never modified, this avoids building (and often allocating) a copy of the
+/// default for each of them:
+///
+/// ```
+/// use lazy_mut::CowLazy;
+///
+/// static NO_HEADERS: Vec<(&str, &str)> = Vec::new();
+///
+/// struct Request {
+///     headers: CowLazy<Vec<(&'static str, &'static str)>>,
+/// }
+///
+/// impl Request {
+///     fn new() -> Request {
+///         Request { headers: CowLazy::new(&NO_HEADERS, |_| Vec::with_capacity(8)) }
+///     }
+/// }
+///
+/// let plain = Request::new();
+/// assert!(plain.headers.is_empty());
+/// assert!(!plain.headers.is_owned());
+///
+/// let mut custom = Request::new();
+/// custom.headers.push(("user-agent", "lazy_mut"));
+/// assert_eq!(*custom.headers, [("user-agent", "lazy_mut")]);
+/// assert!(custom.headers.is_owned());
+/// ```
+///
+/// With `CowLazy::cloned`, the owned value is a clone of the default:
+///
+/// ```
+/// use lazy_mut::CowLazy;
+///
+/// static PRIMES: [u32; 3] = [2, 3, 5];
+///
+/// let mut primes = CowLazy::cloned(&PRIMES);
+/// primes[0] = 7;
+///
+/// assert_eq!(*primes, [7, 3, 5]);
+/// assert_eq!(PRIMES, [2, 3, 5]);
+/// ```
+///
+/// The initializer is given the default, and isn't consumed, so `CowLazy::reset` can go back to
+/// borrowing the default and the initializer will run again on the next mutable access.
+#[derive(Clone)]
+pub struct CowLazy<T: 'static, F = fn(&T) -> T> {
+    default: &'static T,
+    init: F,
+    owned: Option<T>,
+}
+
+impl<T, F> CowLazy<T, F> where F: Fn(&T) -> T {
+    /// Creates a new value that borrows `default`, with an initializer for the owned value
+    pub const fn new(default: &'static T, init: F) -> CowLazy<T, F> {
+        CowLazy { default, init, owned: None }
+    }
+
+    /// Returns a mutable reference to the owned value, running the initializer if needed
+    ///
+    /// Uses associated function syntax (`CowLazy::to_mut(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn to_mut(this: &mut CowLazy<T, F>) -> &mut T {
+        if this.owned.is_none() {
+            this.initialize();
+        }
+        match this.owned {
+            Some(ref mut val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&mut self) {
+        let default = self.default;
+        self.owned = Some(::trace::init(|| (self.init)(default)));
+    }
+
+    /// Returns the owned value, running the initializer if needed
+    #[track_caller]
+    pub fn into_owned(mut self) -> T {
+        match self.owned.take() {
+            Some(val) => val,
+            None => ::trace::init(|| (self.init)(self.default)),
+        }
+    }
+}
+
+impl<T> CowLazy<T> where T: Clone {
+    /// Creates a new value that borrows `default`, cloning it on the first mutable access
+    pub const fn cloned(default: &'static T) -> CowLazy<T> {
+        CowLazy { default, init: T::clone, owned: None }
+    }
+}
+
+impl<T, F> CowLazy<T, F> {
+    /// Returns the default that is borrowed until the value is accessed mutably
+    ///
+    /// Uses associated function syntax (`CowLazy::default_value(&VAL)`)
+    #[inline]
+    pub fn default_value(this: &CowLazy<T, F>) -> &'static T {
+        this.default
+    }
+
+    /// Tries to get a mutable reference to the owned value, returns `None` if the value is still
+    /// borrowing the default
+    ///
+    /// Uses associated function syntax (`CowLazy::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut CowLazy<T, F>) -> Option<&mut T> {
+        this.owned.as_mut()
+    }
+
+    /// Drops the owned value, going back to borrowing the default, and returns the owned value if
+    /// there was one
+    #[inline]
+    pub fn reset(&mut self) -> Option<T> {
+        self.owned.take()
+    }
+
+    /// Returns `true` if the value owns a copy of the default
+    #[inline]
+    pub fn is_owned(&self) -> bool {
+        self.owned.is_some()
+    }
+}
+
+impl<T, F> Deref for CowLazy<T, F> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        match self.owned {
+            Some(ref val) => val,
+            None => self.default,
+        }
+    }
+}
+
+impl<T, F> DerefMut for CowLazy<T, F> where F: Fn(&T) -> T {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        CowLazy::to_mut(self)
+    }
+}
+
+impl<T, F> Debug for CowLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.owned {
+            Some(ref val) => f.debug_tuple("Owned").field(val).finish(),
+            None => f.debug_tuple("Borrowed").field(self.default).finish(),
+        }
+    }
+}
+
+impl<T, F> Display for CowLazy<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,197 @@
+use core::ops::Deref;
+use core::fmt::{self, Debug, Display};
+use core::str::FromStr;
+use std::env::{self, VarError};
+use std::error::Error;
+use std::sync::OnceLock;
+use InitError;
+
+/// A thread-safe lazy value read from an environment variable
+///
+/// The variable is read and parsed with `FromStr` on first access, and the result is cached. If
+/// the variable isn't set, the default is used if there is one. A missing or invalid variable is
+/// reported through `LazyEnvVar::try_init` as `InitError::Failed`, like with `TryLazyMut`, and
+/// leaves the value uninitialized so that it can be retried after the variable is fixed.
+///
+/// Dereferencing initializes the value, panicking with the error if it can't be read.
+///
+/// ```
+/// use lazy_mut::LazyEnvVar;
+/// use std::env;
+///
+/// static THREADS: LazyEnvVar<usize> = LazyEnvVar::new("EXAMPLE_THREADS");
+/// static PORT: LazyEnvVar<u16> = LazyEnvVar::with_default("EXAMPLE_PORT", || 8080);
+///
+/// env::set_var("EXAMPLE_THREADS", "4");
+///
+/// assert_eq!(*THREADS, 4);
+/// assert_eq!(*PORT, 8080);
+/// ```
+///
+/// Errors leave the value uninitialized:
+///
+/// ```
+/// use lazy_mut::{EnvVarError, InitError, LazyEnvVar};
+/// use std::env::{self, VarError};
+///
+/// static LEVEL: LazyEnvVar<u8> = LazyEnvVar::new("EXAMPLE_LEVEL");
+///
+/// match LEVEL.try_init() {
+///     Err(InitError::Failed(EnvVarError::Var(VarError::NotPresent))) => {}
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+///
+/// env::set_var("EXAMPLE_LEVEL", "high");
+/// match LEVEL.try_init() {
+///     Err(InitError::Failed(EnvVarError::Parse(_))) => {}
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+///
+/// env::set_var("EXAMPLE_LEVEL", "3");
+/// assert_eq!(LEVEL.try_init().ok(), Some(&3));
+///
+/// env::set_var("EXAMPLE_LEVEL", "5");
+/// assert_eq!(*LEVEL, 3);
+/// ```
+///
+/// Only available with the `std` feature.
+pub struct LazyEnvVar<T> {
+    name: &'static str,
+    default: Option<fn() -> T>,
+    value: OnceLock<T>,
+}
+
+impl<T> LazyEnvVar<T> where T: FromStr {
+    /// Creates a new uninitialized value read from an environment variable
+    pub const fn new(name: &'static str) -> LazyEnvVar<T> {
+        LazyEnvVar {
+            name,
+            default: None,
+            value: OnceLock::new(),
+        }
+    }
+
+    /// Creates a new uninitialized value read from an environment variable, using a default if
+    /// the variable isn't set
+    ///
+    /// The default isn't used if the variable is set but can't be parsed.
+    pub const fn with_default(name: &'static str, default: fn() -> T) -> LazyEnvVar<T> {
+        LazyEnvVar {
+            name,
+            default: Some(default),
+            value: OnceLock::new(),
+        }
+    }
+
+    /// Initializes the value if it is uninitialized, returning an error if the variable can't be
+    /// read or parsed
+    ///
+    /// If multiple threads initialize the value at once, each of them may read the variable, but
+    /// only the first value is kept.
+    pub fn try_init(&self) -> Result<&T, InitError<EnvVarError<T::Err>>> {
+        if let Some(val) = self.value.get() {
+            return Ok(val);
+        }
+        let val = ::trace::init(|| self.read()).map_err(InitError::Failed)?;
+        Ok(self.value.get_or_init(|| val))
+    }
+
+    fn read(&self) -> Result<T, EnvVarError<T::Err>> {
+        match (env::var(self.name), self.default) {
+            (Ok(var), _) => var.parse().map_err(EnvVarError::Parse),
+            (Err(VarError::NotPresent), Some(default)) => Ok(default()),
+            (Err(err), _) => Err(EnvVarError::Var(err)),
+        }
+    }
+}
+
+impl<T> LazyEnvVar<T> {
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyEnvVar::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &LazyEnvVar<T>) -> Option<&T> {
+        this.value.get()
+    }
+
+    /// Returns the name of the environment variable
+    ///
+    /// Uses associated function syntax (`LazyEnvVar::name(&VAL)`)
+    #[inline]
+    pub fn name(this: &LazyEnvVar<T>) -> &'static str {
+        this.name
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Deref for LazyEnvVar<T> where T: FromStr, T::Err: Display {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        match self.try_init() {
+            Ok(val) => val,
+            Err(err) => invalid(self.name, err),
+        }
+    }
+}
+
+impl<T> Debug for LazyEnvVar<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match LazyEnvVar::get(self) {
+            Some(val) => f.debug_tuple("LazyEnvVar").field(val).finish(),
+            None => write!(f, "LazyEnvVar({{uninitialized}})"),
+        }
+    }
+}
+
+impl<T> Display for LazyEnvVar<T> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match LazyEnvVar::get(self) {
+            Some(val) => val.fmt(f),
+            None => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn invalid<E>(name: &str, err: InitError<EnvVarError<E>>) -> ! where E: Display {
+    match err {
+        InitError::Failed(err) => panic!("cannot read environment variable `{}`: {}", name, err),
+        InitError::Panicked(panic) => panic.resume(),
+    }
+}
+
+/// An error from reading an environment variable for a `LazyEnvVar`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EnvVarError<E> {
+    /// The variable isn't set or isn't valid unicode
+    Var(VarError),
+    /// The variable couldn't be parsed
+    Parse(E),
+}
+
+impl<E> Display for EnvVarError<E> where E: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EnvVarError::Var(ref err) => Display::fmt(err, f),
+            EnvVarError::Parse(ref err) => write!(f, "invalid value: {}", err),
+        }
+    }
+}
+
+impl<E> Error for EnvVarError<E> where E: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            EnvVarError::Var(ref err) => Some(err),
+            EnvVarError::Parse(ref err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use core::fmt::{self, Debug};
+use std::time::{Duration, Instant};
+
+/// A lazy value that expires after a fixed amount of time
+///
+/// The initializer runs on first access like any other lazy value, but once the value is older than
+/// its time to live it is considered stale, and the next call to `force` runs the initializer again
+/// to replace it. This is useful for caching values like authentication tokens that are only valid
+/// for a limited time.
+///
+/// ```
+/// use lazy_mut::ExpiringLazy;
+/// use std::time::Duration;
+///
+/// let mut token = ExpiringLazy::new(|| "secret".to_string(), Duration::from_secs(60));
+///
+/// assert_eq!(token.peek_stale(), None);
+/// assert_eq!(token.force(), "secret");
+/// assert!(token.is_fresh());
+/// ```
+///
+/// `ExpiringLazy::force_fresh` always runs the initializer, such as when a token is rejected
+/// before it expires, and `ExpiringLazy::peek_stale` returns the value without checking whether it
+/// has expired:
+///
+/// ```
+/// use lazy_mut::ExpiringLazy;
+/// use std::cell::Cell;
+/// use std::time::Duration;
+///
+/// let runs = Cell::new(0);
+/// let mut count = ExpiringLazy::new(|| { runs.set(runs.get() + 1); runs.get() }, Duration::ZERO);
+///
+/// assert_eq!(*count.force(), 1);
+/// assert!(!count.is_fresh());
+/// assert_eq!(count.peek_stale(), Some(&1));
+/// assert_eq!(*count.force(), 2);
+/// assert_eq!(*count.force_fresh(), 3);
+/// ```
+pub struct ExpiringLazy<T, F = fn() -> T> {
+    init: F,
+    ttl: Duration,
+    value: Option<(T, Instant)>,
+}
+
+impl<T, F> ExpiringLazy<T, F> where F: Fn() -> T {
+    /// Creates a new uninitialized value with an initializer and a time to live
+    pub const fn new(init: F, ttl: Duration) -> ExpiringLazy<T, F> {
+        ExpiringLazy {
+            init,
+            ttl,
+            value: None,
+        }
+    }
+
+    /// Returns a mutable reference to the value, running the initializer if it is uninitialized
+    /// or stale
+    #[track_caller]
+    pub fn force(&mut self) -> &mut T {
+        if !self.is_fresh() {
+            self.refresh();
+        }
+        match self.value {
+            Some((ref mut val, _)) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Runs the initializer to replace the value, even if it hasn't expired yet
+    #[track_caller]
+    pub fn force_fresh(&mut self) -> &mut T {
+        self.refresh();
+        match self.value {
+            Some((ref mut val, _)) => val,
+            None => unreachable!(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn refresh(&mut self) {
+        self.value = None;
+        let val = ::trace::init(&self.init);
+        self.value = Some((val, Instant::now()));
+    }
+}
+
+impl<T, F> ExpiringLazy<T, F> {
+    /// Returns a reference to the value without checking whether it is stale, returns `None` if the
+    /// value is uninitialized
+    #[inline]
+    pub fn peek_stale(&self) -> Option<&T> {
+        self.value.as_ref().map(|(val, _)| val)
+    }
+
+    /// Returns `true` if the value has been initialized and hasn't expired yet
+    pub fn is_fresh(&self) -> bool {
+        match self.value {
+            Some((_, created)) => created.elapsed() < self.ttl,
+            None => false,
+        }
+    }
+
+    /// Returns the time to live of the value
+    #[inline]
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the time when the current value expires, returns `None` if the value is
+    /// uninitialized or the time to live is too long to represent
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.value.as_ref().and_then(|&(_, created)| created.checked_add(self.ttl))
+    }
+}
+
+impl<T, F> Debug for ExpiringLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some((ref val, _)) if self.is_fresh() => f.debug_tuple("Fresh").field(val).finish(),
+            Some((ref val, _)) => f.debug_tuple("Stale").field(val).finish(),
+            None => write!(f, "Init(..)"),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+#[cfg(not(feature = "catch-panic"))]
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "catch-panic")]
+use std::any::Any;
+#[cfg(feature = "catch-panic")]
+use std::boxed::Box;
+#[cfg(feature = "catch-panic")]
+use std::string::String;
+
+/// A mutable lazy value with an initializer that may fail
+///
+/// Unlike `LazyMut`, this is never initialized implicitly, since initialization can fail. A failed
+/// initialization leaves the value uninitialized, so it can be retried later.
+#[derive(Clone, Debug)]
+pub enum TryLazyMut<T, E> {
+    /// An initializer that will be run to obtain the first value
+    Init(fn() -> Result<T, E>),
+    /// The value from the initializer
+    Value(T),
+}
+
+impl<T, E> TryLazyMut<T, E> {
+    /// Initializes the wrapped value if it is uninitialized, returning any error from the
+    /// initializer
+    ///
+    /// With the `catch-panic` feature enabled, a panic in the initializer is caught and returned
+    /// as `InitError::Panicked` instead of unwinding through the caller.
+    pub fn try_init(&mut self) -> Result<&mut T, InitError<E>> {
+        use self::TryLazyMut::*;
+        if let Init(init) = *self {
+            *self = Value(run(init)?);
+        }
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => Ok(val),
+        }
+    }
+
+    /// Initializes every value in a group, or none of them if any initializer fails
+    ///
+    /// The values are initialized in order. If one of them fails, the values that this call
+    /// already initialized are dropped in reverse order and put back in their uninitialized state,
+    /// and the error is returned. Values that were initialized before the call are left alone.
+    /// This keeps a program from being left half-initialized when one of its dependencies is
+    /// unavailable:
+    ///
+    /// ```
+    /// use lazy_mut::{InitError, TryLazyMut};
+    ///
+    /// fn connect_cache() -> Result<String, &'static str> { Ok("cache".to_string()) }
+    /// fn connect_database() -> Result<String, &'static str> { Err("database is down") }
+    ///
+    /// let mut group = [
+    ///     TryLazyMut::Init(connect_cache),
+    ///     TryLazyMut::Init(connect_database),
+    /// ];
+    ///
+    /// match TryLazyMut::init_group(&mut group) {
+    ///     Err(InitError::Failed(err)) => assert_eq!(err, "database is down"),
+    ///     other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    /// }
+    /// assert!(!group[0].is_initialized());
+    /// ```
+    ///
+    /// Only available with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn init_group(group: &mut [TryLazyMut<T, E>]) -> Result<(), InitError<E>> {
+        // The initializers of the values this call initializes, so that they can be put back
+        let mut inits = Vec::with_capacity(group.len());
+        for i in 0..group.len() {
+            inits.push(match group[i] {
+                TryLazyMut::Init(init) => Some(init),
+                TryLazyMut::Value(_) => None,
+            });
+            if let Err(err) = group[i].try_init() {
+                for (lazy, init) in group[..i].iter_mut().zip(inits).rev() {
+                    if let Some(init) = init {
+                        *lazy = TryLazyMut::Init(init);
+                    }
+                }
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets a mutable reference to the value, initializing it with `f` if it is uninitialized
+    ///
+    /// The stored initializer is not run. If `f` returns an error, the value is left
+    /// uninitialized and the error is returned.
+    pub fn get_or_try_init<F>(&mut self, f: F) -> Result<&mut T, E>
+        where F: FnOnce() -> Result<T, E>
+    {
+        use self::TryLazyMut::*;
+        if let Init(_) = *self {
+            *self = Value(f()?);
+        }
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => Ok(val),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`TryLazyMut::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &TryLazyMut<T, E>) -> Option<&T> {
+        use self::TryLazyMut::*;
+        match *this {
+            Init(_) => None,
+            Value(ref val) => Some(val),
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`TryLazyMut::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut TryLazyMut<T, E>) -> Option<&mut T> {
+        use self::TryLazyMut::*;
+        match *this {
+            Init(_) => None,
+            Value(ref mut val) => Some(val),
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        use self::TryLazyMut::*;
+        match *self {
+            Init(_) => false,
+            Value(_) => true,
+        }
+    }
+}
+
+#[cfg(not(feature = "catch-panic"))]
+fn run<T, E>(init: fn() -> Result<T, E>) -> Result<T, InitError<E>> {
+    ::trace::init(init).map_err(InitError::Failed)
+}
+
+#[cfg(feature = "catch-panic")]
+fn run<T, E>(init: fn() -> Result<T, E>) -> Result<T, InitError<E>> {
+    match ::std::panic::catch_unwind(|| ::trace::init(init)) {
+        Ok(result) => result.map_err(InitError::Failed),
+        Err(payload) => Err(InitError::Panicked(InitPanic { payload })),
+    }
+}
+
+impl<T, E> Deref for TryLazyMut<T, E> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        use self::TryLazyMut::*;
+        match *self {
+            Init(_) => ::uninitialized::<T>(None),
+            Value(ref val) => val,
+        }
+    }
+}
+
+impl<T, E> DerefMut for TryLazyMut<T, E> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        use self::TryLazyMut::*;
+        match *self {
+            Init(_) => ::uninitialized::<T>(None),
+            Value(ref mut val) => val,
+        }
+    }
+}
+
+impl<T, E> Display for TryLazyMut<T, E> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::TryLazyMut::*;
+        match *self {
+            Init(_) => write!(f, "{{uninitialized}}"),
+            Value(ref val) => val.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-logging")]
+impl<T, E> ::defmt::Format for TryLazyMut<T, E> where T: ::defmt::Format {
+    fn format(&self, f: ::defmt::Formatter) {
+        use self::TryLazyMut::*;
+        match *self {
+            Init(_) => ::defmt::write!(f, "{{uninitialized}}"),
+            Value(ref val) => val.format(f),
+        }
+    }
+}
+
+/// An error from running the initializer of a `TryLazyMut`
+#[derive(Debug)]
+pub enum InitError<E> {
+    /// The initializer returned an error
+    Failed(E),
+    /// The initializer panicked
+    ///
+    /// This is only returned with the `catch-panic` feature, since panics aren't caught otherwise.
+    Panicked(InitPanic),
+}
+
+impl<E> Display for InitError<E> where E: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InitError::Failed(ref err) => write!(f, "initializer failed: {}", err),
+            InitError::Panicked(ref panic) => match panic.message() {
+                Some(msg) => write!(f, "initializer panicked: {}", msg),
+                None => write!(f, "initializer panicked"),
+            },
+        }
+    }
+}
+
+/// A panic caught while running the initializer of a `TryLazyMut`
+///
+/// Without the `catch-panic` feature, panics aren't caught, so this is never created.
+///
+/// ```
+/// # #[cfg(feature = "catch-panic")]
+/// # fn main() {
+/// use lazy_mut::{InitError, TryLazyMut};
+///
+/// fn init() -> Result<u32, ()> { panic!("missing config") }
+/// let mut config: TryLazyMut<u32, ()> = TryLazyMut::Init(init);
+///
+/// match config.try_init() {
+///     Err(InitError::Panicked(panic)) => assert_eq!(panic.message(), Some("missing config")),
+///     _ => unreachable!(),
+/// }
+/// # }
+/// # #[cfg(not(feature = "catch-panic"))]
+/// # fn main() {}
+/// ```
+pub struct InitPanic {
+    #[cfg(feature = "catch-panic")]
+    payload: Box<dyn Any + Send>,
+    #[cfg(not(feature = "catch-panic"))]
+    never: Infallible,
+}
+
+impl InitPanic {
+    /// Returns the message of the panic, if it was a string
+    pub fn message(&self) -> Option<&str> {
+        #[cfg(feature = "catch-panic")]
+        return match self.payload.downcast_ref::<&str>() {
+            Some(msg) => Some(msg),
+            None => self.payload.downcast_ref::<String>().map(|msg| &msg[..]),
+        };
+        #[cfg(not(feature = "catch-panic"))]
+        match self.never {}
+    }
+
+    /// Returns the payload of the panic, as passed to `std::panic::resume_unwind`
+    #[cfg(feature = "catch-panic")]
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Continues unwinding from the panic
+    #[cfg(feature = "std")]
+    pub(crate) fn resume(self) -> ! {
+        #[cfg(feature = "catch-panic")]
+        ::std::panic::resume_unwind(self.payload);
+        #[cfg(not(feature = "catch-panic"))]
+        match self.never {}
+    }
+}
+
+impl Debug for InitPanic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InitPanic").field("message", &self.message()).finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E> Error for InitError<E> where E: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            InitError::Failed(ref err) => Some(err),
+            InitError::Panicked(_) => None,
+        }
+    }
+}
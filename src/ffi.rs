@@ -0,0 +1,119 @@
+//! Support for driving lazy globals from C
+//!
+//! The `lazy_mut_ffi` macro declares thread-safe lazy statics like the `lazy_mut` macro, along
+//! with a pair of `#[no_mangle] extern "C"` functions for each one. These can be declared in a C
+//! header as:
+//!
+//! ```c
+//! int config_init(void);
+//! int config_is_ready(void);
+//! ```
+//!
+//! The init function returns one of the status codes defined in this module, and never unwinds
+//! into the caller. The is-ready function returns `1` if the value is initialized and `0`
+//! otherwise.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut_ffi! {
+//!     static CONFIG: Vec<u32> = vec![1, 2, 3];
+//!     extern fn config_init, config_is_ready;
+//! }
+//!
+//! # fn main() {
+//! use lazy_mut::ffi::{INITIALIZED, ALREADY_INITIALIZED};
+//!
+//! assert_eq!(config_is_ready(), 0);
+//! assert_eq!(config_init(), INITIALIZED);
+//! assert_eq!(config_init(), ALREADY_INITIALIZED);
+//! assert_eq!(config_is_ready(), 1);
+//! # }
+//! ```
+//!
+//! When several threads call the init function at the same time, only the one that ran the
+//! initializer gets `INITIALIZED`:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut_ffi! {
+//!     static TABLE: Vec<u32> = (0..1000).collect();
+//!     extern fn table_init, table_is_ready;
+//! }
+//!
+//! # fn main() {
+//! use lazy_mut::ffi::INITIALIZED;
+//! use std::thread;
+//!
+//! let threads: Vec<_> = (0..8).map(|_| thread::spawn(|| table_init())).collect();
+//! let codes: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+//! assert_eq!(codes.iter().filter(|&&code| code == INITIALIZED).count(), 1);
+//! # }
+//! ```
+
+use std::panic::{self, AssertUnwindSafe};
+use {Initializer, SyncLazy};
+
+pub use libc::c_int;
+
+/// The value was initialized by this call
+pub const INITIALIZED: c_int = 0;
+/// The value was already initialized before this call
+pub const ALREADY_INITIALIZED: c_int = 1;
+/// The initializer panicked, either during this call or a previous one
+pub const FAILED: c_int = -1;
+
+/// Declares thread-safe lazy statics with C functions to initialize and observe them
+///
+/// See the `ffi` module for more information on usage.
+///
+/// # Usage
+///
+/// ```ignore
+/// lazy_mut_ffi! {
+///     [pub [(VIS)]] static NAME: TY = EXPR;
+///     extern fn INIT_NAME, IS_READY_NAME;
+/// }
+/// ```
+#[macro_export]
+macro_rules! lazy_mut_ffi {
+    (/* empty */) => {};
+    ($(#[$attr:meta])* $vis:vis static $N:ident: $T:ty = $e:expr;
+        extern fn $init:ident, $is_ready:ident; $($t:tt)*) => {
+        $(#[$attr])*
+        $vis static $N: $crate::SyncLazy<$T> =
+            $crate::SyncLazy::named(stringify!($N), || -> $T {
+                $crate::__lazy_mut_name!($N);
+                $e
+            });
+        $crate::__lazy_mut_register!($N);
+        #[no_mangle]
+        pub extern "C" fn $init() -> $crate::ffi::c_int {
+            $crate::ffi::init(&$N)
+        }
+        #[no_mangle]
+        pub extern "C" fn $is_ready() -> $crate::ffi::c_int {
+            $crate::ffi::is_ready(&$N)
+        }
+        lazy_mut_ffi!($($t)*);
+    };
+}
+
+#[doc(hidden)]
+pub fn init<T, F>(lazy: &SyncLazy<T, F>) -> c_int where F: Initializer<T> {
+    // Only the call that actually runs the initializer reports `INITIALIZED`, even when several
+    // threads race to initialize the value
+    match panic::catch_unwind(AssertUnwindSafe(|| lazy.try_init_once().is_ok())) {
+        Ok(true) => INITIALIZED,
+        Ok(false) => ALREADY_INITIALIZED,
+        Err(_) => FAILED,
+    }
+}
+
+#[doc(hidden)]
+pub fn is_ready<T, F>(lazy: &SyncLazy<T, F>) -> c_int {
+    lazy.is_initialized() as c_int
+}
@@ -0,0 +1,149 @@
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use {CompactLazy, Initializer};
+
+/// A lazy value meant to be stored as a field of a struct
+///
+/// `LazyField<T>` is a `CompactLazy<T>` with accessors that fit methods taking `&mut self`. Mutable
+/// dereferences initialize the value as expected of a field, so `self.cache.push(x)` works whether
+/// or not `cache` was used before. Immutable dereferences still panic if the value is
+/// uninitialized, so use `LazyField::force` in methods that only read.
+///
+/// When `T: Default`, `LazyField<T>` implements `Default` with `T::default` as its initializer.
+/// This lets it be used in structs that `#[derive(Default)]`, and in fields marked with
+/// `#[serde(skip)]`, which are filled in with `Default` when deserializing.
+///
+/// ```
+/// use lazy_mut::LazyField;
+///
+/// #[derive(Default)]
+/// struct Client {
+///     name: String,
+///     history: LazyField<Vec<String>>,
+/// }
+///
+/// impl Client {
+///     fn send(&mut self, request: &str) {
+///         self.history.push(request.to_string());
+///     }
+///
+///     fn history(&mut self) -> &[String] {
+///         LazyField::force(&mut self.history)
+///     }
+/// }
+///
+/// let mut client = Client { name: "test".to_string(), ..Client::default() };
+/// assert_eq!(LazyField::get(&client.history), None);
+/// assert!(client.history().is_empty());
+///
+/// client.send("GET /");
+/// assert_eq!(client.history(), ["GET /"]);
+/// ```
+#[derive(Clone)]
+pub struct LazyField<T, F = fn() -> T> {
+    lazy: CompactLazy<T, F>,
+}
+
+impl<T, F> LazyField<T, F> where F: Initializer<T> {
+    /// Creates a new uninitialized field with an initializer
+    pub const fn new(init: F) -> LazyField<T, F> {
+        LazyField { lazy: CompactLazy::new(init) }
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        self.lazy.unwrap()
+    }
+
+    /// Initializes the field if needed and returns a mutable reference to its value
+    ///
+    /// Uses associated function syntax (`LazyField::force(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn force(this: &mut LazyField<T, F>) -> &mut T {
+        match CompactLazy::get_mut(this.lazy.init()) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T, F> LazyField<T, F> {
+    /// Creates a new field that is already initialized
+    pub const fn with_value(val: T) -> LazyField<T, F> {
+        LazyField { lazy: CompactLazy::with_value(val) }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyField::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &LazyField<T, F>) -> Option<&T> {
+        CompactLazy::get(&this.lazy)
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyField::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut LazyField<T, F>) -> Option<&mut T> {
+        CompactLazy::get_mut(&mut this.lazy)
+    }
+
+    /// Sets the value of the field, returning the previous value if it was initialized
+    ///
+    /// The initializer is dropped if it hasn't been run yet.
+    ///
+    /// Uses associated function syntax (`LazyField::set(&mut VAL, value)`)
+    #[track_caller]
+    pub fn set(this: &mut LazyField<T, F>, val: T) -> Option<T> {
+        let old = mem::replace(&mut this.lazy, CompactLazy::with_value(val));
+        CompactLazy::into_inner(old).ok()
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.lazy.is_initialized()
+    }
+}
+
+impl<T> Default for LazyField<T> where T: Default {
+    fn default() -> LazyField<T> {
+        LazyField::new(T::default)
+    }
+}
+
+impl<T, F> Deref for LazyField<T, F> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        &self.lazy
+    }
+}
+
+impl<T, F> DerefMut for LazyField<T, F> where F: Initializer<T> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        LazyField::force(self)
+    }
+}
+
+impl<T, F> Debug for LazyField<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match LazyField::get(self) {
+            Some(val) => f.debug_tuple("LazyField").field(val).finish(),
+            None => write!(f, "LazyField({{uninitialized}})"),
+        }
+    }
+}
+
+impl<T, F> Display for LazyField<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.lazy.fmt(f)
+    }
+}
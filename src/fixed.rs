@@ -0,0 +1,208 @@
+use core::cell::UnsafeCell;
+use core::fmt::{self, Debug};
+use core::mem::{self, MaybeUninit};
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::{Acquire, Release};
+
+/// A thread-safe lazy value with a fixed `#[repr(C)]` layout and no initializer
+///
+/// The layout is a `u32` state word followed by the value, aligned as it would be in a C struct:
+///
+/// | State | Meaning                                        |
+/// |-------|------------------------------------------------|
+/// | `0`   | Uninitialized, the value bytes are unspecified |
+/// | `1`   | Currently being initialized                    |
+/// | `2`   | Initialized                                    |
+/// | `3`   | The initializer panicked                       |
+///
+/// Since the state of an uninitialized value is zero, zeroed memory is an uninitialized
+/// `FixedLazy`, so it can be placed in memory that is mapped from a file or shared with another
+/// process or language, which can check the state word and read the value without knowing about
+/// this crate. The initializer isn't stored, since a function pointer means nothing in another
+/// process, and is instead passed to `FixedLazy::init_with`:
+///
+/// ```
+/// use lazy_mut::FixedLazy;
+///
+/// #[repr(C)]
+/// struct Shared {
+///     version: u32,
+///     table: FixedLazy<[u16; 4]>,
+/// }
+///
+/// let shared = Shared { version: 1, table: FixedLazy::new() };
+///
+/// assert_eq!(*shared.table.init_with(|| [1, 2, 4, 8]), [1, 2, 4, 8]);
+/// assert_eq!(FixedLazy::get(&shared.table), Some(&[1, 2, 4, 8]));
+/// ```
+///
+/// With the `bytemuck` feature, this implements `bytemuck::Zeroable`. It can't implement `Pod`,
+/// since it has interior mutability and may contain uninitialized bytes.
+#[repr(C)]
+pub struct FixedLazy<T> {
+    state: AtomicU32,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T> Sync for FixedLazy<T> where T: Send + Sync {}
+unsafe impl<T> Send for FixedLazy<T> where T: Send {}
+
+impl<T> FixedLazy<T> {
+    /// The state of a value that hasn't been initialized
+    pub const UNINIT: u32 = 0;
+    /// The state of a value that is currently being initialized
+    pub const RUNNING: u32 = 1;
+    /// The state of a value that has been initialized
+    pub const READY: u32 = 2;
+    /// The state of a value whose initializer panicked
+    pub const POISONED: u32 = 3;
+
+    /// Creates a new uninitialized value
+    pub const fn new() -> FixedLazy<T> {
+        FixedLazy {
+            state: AtomicU32::new(FixedLazy::<T>::UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Creates a new value that is already initialized
+    pub const fn with_value(val: T) -> FixedLazy<T> {
+        FixedLazy {
+            state: AtomicU32::new(FixedLazy::<T>::READY),
+            value: UnsafeCell::new(MaybeUninit::new(val)),
+        }
+    }
+
+    /// Initializes the value with `init` if it is uninitialized, returning a reference to it
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish. This
+    /// panics if a previous initializer panicked, or if the state word isn't one of the states
+    /// above.
+    #[track_caller]
+    pub fn init_with<F>(&self, init: F) -> &T where F: FnOnce() -> T {
+        if self.state.load(Acquire) != FixedLazy::<T>::READY {
+            self.initialize(init);
+        }
+        unsafe { &*(*self.value.get()).as_ptr() }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize<F>(&self, init: F) where F: FnOnce() -> T {
+        loop {
+            match self.state.compare_exchange_weak(FixedLazy::<T>::UNINIT,
+                FixedLazy::<T>::RUNNING, Acquire, Acquire)
+            {
+                Ok(_) => break,
+                Err(FixedLazy::<T>::UNINIT) => {}
+                Err(FixedLazy::<T>::RUNNING) => wait(),
+                Err(FixedLazy::<T>::READY) => return,
+                Err(FixedLazy::<T>::POISONED) => {
+                    panic!("initializer of lazy value previously panicked")
+                }
+                Err(state) => panic!("invalid lazy value state {}", state),
+            }
+        }
+        let poison = Poison(&self.state);
+        let val = ::trace::init(init);
+        unsafe { (*self.value.get()).as_mut_ptr().write(val) };
+        mem::forget(poison);
+        self.state.store(FixedLazy::<T>::READY, Release);
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`FixedLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &FixedLazy<T>) -> Option<&T> {
+        if this.state.load(Acquire) == FixedLazy::<T>::READY {
+            Some(unsafe { &*(*this.value.get()).as_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`FixedLazy::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut FixedLazy<T>) -> Option<&mut T> {
+        if *this.state.get_mut() == FixedLazy::<T>::READY {
+            Some(unsafe { &mut *(*this.value.get()).as_mut_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the wrapped value, or `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`FixedLazy::into_inner(VAL)`)
+    pub fn into_inner(this: FixedLazy<T>) -> Option<T> {
+        let mut this = mem::ManuallyDrop::new(this);
+        if *this.state.get_mut() == FixedLazy::<T>::READY {
+            Some(unsafe { (*this.value.get()).as_ptr().read() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the state word, which is one of the states above unless the memory was written by
+    /// something else
+    #[inline]
+    pub fn state(&self) -> u32 {
+        self.state.load(Acquire)
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Acquire) == FixedLazy::<T>::READY
+    }
+}
+
+/// Marks a value as poisoned if its initializer unwinds
+struct Poison<'a>(&'a AtomicU32);
+
+impl<'a> Drop for Poison<'a> {
+    fn drop(&mut self) {
+        self.0.store(FixedLazy::<()>::POISONED, Release);
+    }
+}
+
+#[cfg(feature = "std")]
+fn wait() {
+    ::std::thread::yield_now();
+}
+
+#[cfg(not(feature = "std"))]
+fn wait() {
+    core::hint::spin_loop();
+}
+
+impl<T> Drop for FixedLazy<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == FixedLazy::<T>::READY {
+            unsafe { (*self.value.get()).as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+impl<T> Default for FixedLazy<T> {
+    fn default() -> FixedLazy<T> {
+        FixedLazy::new()
+    }
+}
+
+impl<T> Debug for FixedLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match FixedLazy::get(self) {
+            Some(val) => f.debug_tuple("FixedLazy").field(val).finish(),
+            None => write!(f, "FixedLazy({{uninitialized}})"),
+        }
+    }
+}
+
+// The state of an all-zero value is `UNINIT`, and the value bytes aren't read in that state
+#[cfg(feature = "bytemuck")]
+unsafe impl<T> ::bytemuck::Zeroable for FixedLazy<T> {}
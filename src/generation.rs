@@ -0,0 +1,331 @@
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+/// A thread-safe lazy value that can be invalidated to run its initializer again
+///
+/// `GenLazy::invalidate` drops the value so that the initializer runs again on next access, and
+/// increments the generation of the value. Code that computes something from the value can store
+/// the generation it saw, and compare it with `GenLazy::generation` later to detect that the value
+/// has changed since. The generation is only incremented while holding the lock, so the generation
+/// returned by `GenRef::generation` always matches the value it dereferences to.
+///
+/// The initializer runs while holding a write lock, so it must not access the value itself. A
+/// panic in the initializer leaves the value uninitialized, so it runs again on next access.
+///
+/// ```
+/// use lazy_mut::GenLazy;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+///
+/// static VERSION: AtomicU32 = AtomicU32::new(1);
+/// static CONFIG: GenLazy<String> = GenLazy::new(|| {
+///     format!("config v{}", VERSION.load(Ordering::SeqCst))
+/// });
+///
+/// assert_eq!(*CONFIG.read(), "config v1");
+/// assert_eq!(CONFIG.generation(), 0);
+///
+/// VERSION.store(2, Ordering::SeqCst);
+/// assert_eq!(*CONFIG.read(), "config v1");
+///
+/// CONFIG.invalidate();
+/// assert_eq!(CONFIG.generation(), 1);
+/// assert!(!CONFIG.is_initialized());
+/// assert_eq!(*CONFIG.read(), "config v2");
+/// ```
+///
+/// Other lazy values can be derived from a `GenLazy` with `derive_from`, so that they are computed
+/// again whenever it is invalidated.
+///
+/// Only available with the `std` feature.
+pub struct GenLazy<T, F = fn() -> T> {
+    init: F,
+    value: RwLock<Option<T>>,
+    generation: AtomicU64,
+}
+
+impl<T, F> GenLazy<T, F> where F: Fn() -> T {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> GenLazy<T, F> {
+        GenLazy {
+            init,
+            value: RwLock::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Locks the value for reading, initializing it if needed
+    ///
+    /// This blocks while another thread is initializing or invalidating the value.
+    #[track_caller]
+    pub fn read(&self) -> GenRef<'_, T> {
+        loop {
+            let guard = read(&self.value);
+            if guard.is_some() {
+                return GenRef {
+                    generation: self.generation.load(Ordering::Acquire),
+                    guard,
+                };
+            }
+            drop(guard);
+            self.initialize();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) {
+        let mut guard = write(&self.value);
+        if guard.is_none() {
+            *guard = Some(::trace::init(&self.init));
+        }
+    }
+}
+
+impl<T, F> GenLazy<T, F> {
+    /// Drops the value so that it will be initialized again on next access, returning it if it
+    /// was initialized
+    ///
+    /// The generation is incremented even if the value was uninitialized.
+    pub fn invalidate(&self) -> Option<T> {
+        let mut guard = write(&self.value);
+        self.generation.fetch_add(1, Ordering::Release);
+        guard.take()
+    }
+
+    /// Returns the number of times the value has been invalidated
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Tries to get a mutable reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`GenLazy::get_mut(&mut VAL)`)
+    pub fn get_mut(this: &mut GenLazy<T, F>) -> Option<&mut T> {
+        match this.value.get_mut() {
+            Ok(val) => val.as_mut(),
+            Err(err) => err.into_inner().as_mut(),
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    ///
+    /// This blocks while another thread is initializing or invalidating the value.
+    pub fn is_initialized(&self) -> bool {
+        read(&self.value).is_some()
+    }
+}
+
+impl<T, F> Debug for GenLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let generation = self.generation();
+        match self.value.try_read() {
+            Ok(guard) => debug_value(f, generation, &guard),
+            Err(TryLockError::Poisoned(err)) => debug_value(f, generation, &err.into_inner()),
+            Err(TryLockError::WouldBlock) => write!(f, "GenLazy({{locked}})"),
+        }
+    }
+}
+
+fn debug_value<T>(f: &mut fmt::Formatter, generation: u64, val: &Option<T>) -> fmt::Result
+    where T: Debug
+{
+    match *val {
+        Some(ref val) => f.debug_struct("GenLazy")
+            .field("generation", &generation)
+            .field("value", val)
+            .finish(),
+        None => write!(f, "GenLazy({{uninitialized}})"),
+    }
+}
+
+impl<T, F> GenSource for GenLazy<T, F> where F: Fn() -> T {
+    type Value = T;
+
+    fn read(&self) -> GenRef<'_, T> {
+        GenLazy::read(self)
+    }
+
+    fn generation(&self) -> u64 {
+        GenLazy::generation(self)
+    }
+}
+
+/// A lazy value with a generation that is incremented whenever the value changes
+///
+/// This is implemented by `GenLazy` and `Derived`, so that derived values can be chained.
+pub trait GenSource {
+    /// The type of the value
+    type Value;
+
+    /// Locks the value for reading, initializing it if needed
+    fn read(&self) -> GenRef<'_, Self::Value>;
+
+    /// Returns the generation that the value would have if it were read now
+    fn generation(&self) -> u64;
+}
+
+/// Creates a lazy value computed from another one, which is computed again whenever the source
+/// value changes
+///
+/// The function doesn't run until the derived value is read. When it is read after the generation
+/// of the source has advanced, the function runs again with the new source value, which is locked
+/// for reading while it runs. The generation of the derived value is the generation of the source
+/// value it was computed from, so derived values can be derived from in turn.
+///
+/// ```
+/// use lazy_mut::{derive_from, Derived, GenLazy, GenRef};
+/// use std::sync::Mutex;
+///
+/// static PATH: Mutex<&str> = Mutex::new("/usr/local/bin");
+/// static CONFIG: GenLazy<String> = GenLazy::new(|| PATH.lock().unwrap().to_string());
+/// static DEPTH: Derived<GenLazy<String>, usize> = derive_from(&CONFIG, |path| {
+///     path.matches('/').count()
+/// });
+/// static DEEP: Derived<Derived<GenLazy<String>, usize>, bool> = derive_from(&DEPTH, |&d| d > 2);
+///
+/// assert_eq!(*DEPTH.read(), 3);
+/// assert!(*DEEP.read());
+///
+/// *PATH.lock().unwrap() = "/bin";
+/// CONFIG.invalidate();
+/// assert!(DEPTH.is_stale());
+///
+/// let deep = DEEP.read();
+/// assert!(!*deep);
+/// assert_eq!(GenRef::generation(&deep), 1);
+/// assert_eq!(*DEPTH.read(), 1);
+/// ```
+pub const fn derive_from<S, T>(source: &S, func: fn(&S::Value) -> T) -> Derived<'_, S, T>
+    where S: GenSource
+{
+    Derived {
+        source,
+        func,
+        value: RwLock::new(None),
+        seen: AtomicU64::new(0),
+    }
+}
+
+/// A lazy value computed from another one, created by `derive_from`
+///
+/// Only available with the `std` feature.
+pub struct Derived<'a, S: 'a, T> where S: GenSource {
+    source: &'a S,
+    func: fn(&S::Value) -> T,
+    value: RwLock<Option<T>>,
+    seen: AtomicU64,
+}
+
+impl<'a, S, T> Derived<'a, S, T> where S: GenSource {
+    /// Locks the value for reading, computing it if it is uninitialized or the source has changed
+    ///
+    /// This locks the source for reading as well, so it must not be called while holding a guard
+    /// for the source value.
+    #[track_caller]
+    pub fn read(&self) -> GenRef<'_, T> {
+        loop {
+            let source = self.source.read();
+            let generation = GenRef::generation(&source);
+            let guard = read(&self.value);
+            if guard.is_some() && self.seen.load(Ordering::Acquire) == generation {
+                return GenRef { generation, guard };
+            }
+            drop(guard);
+            self.recompute(&source);
+        }
+    }
+
+    /// Returns `true` if the value is uninitialized or the source has changed since it was
+    /// computed
+    pub fn is_stale(&self) -> bool {
+        let guard = read(&self.value);
+        guard.is_none() || self.seen.load(Ordering::Acquire) != self.source.generation()
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn recompute(&self, source: &GenRef<S::Value>) {
+        let generation = GenRef::generation(source);
+        let mut guard = write(&self.value);
+        if guard.is_none() || self.seen.load(Ordering::Acquire) != generation {
+            *guard = None;
+            *guard = Some(::trace::init(|| (self.func)(source)));
+            self.seen.store(generation, Ordering::Release);
+        }
+    }
+}
+
+impl<'a, S, T> GenSource for Derived<'a, S, T> where S: GenSource {
+    type Value = T;
+
+    fn read(&self) -> GenRef<'_, T> {
+        Derived::read(self)
+    }
+
+    fn generation(&self) -> u64 {
+        self.source.generation()
+    }
+}
+
+impl<'a, S, T> Debug for Derived<'a, S, T> where S: GenSource, T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value.try_read() {
+            Ok(guard) => debug_derived(f, &guard),
+            Err(TryLockError::Poisoned(err)) => debug_derived(f, &err.into_inner()),
+            Err(TryLockError::WouldBlock) => write!(f, "Derived({{locked}})"),
+        }
+    }
+}
+
+fn debug_derived<T>(f: &mut fmt::Formatter, val: &Option<T>) -> fmt::Result where T: Debug {
+    match *val {
+        Some(ref val) => f.debug_tuple("Derived").field(val).finish(),
+        None => write!(f, "Derived({{uninitialized}})"),
+    }
+}
+
+/// A read guard for the value of a `GenLazy` or `Derived`, along with its generation
+pub struct GenRef<'a, T> {
+    generation: u64,
+    guard: RwLockReadGuard<'a, Option<T>>,
+}
+
+impl<'a, T> GenRef<'a, T> {
+    /// Returns the generation of the value
+    ///
+    /// Uses associated function syntax (`GenRef::generation(&VAL)`)
+    #[inline]
+    pub fn generation(this: &GenRef<'a, T>) -> u64 {
+        this.generation
+    }
+}
+
+impl<'a, T> Deref for GenRef<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        match *self.guard {
+            Some(ref val) => val,
+            None => unreachable!(),
+        }
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    match lock.read() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    }
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    match lock.write() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    }
+}
@@ -0,0 +1,98 @@
+use core::ops::Deref;
+use core::fmt::{self, Debug, Display};
+use {LazyState, LazyValue, SyncLazy};
+
+/// One of several lazy statics that share an initializer, declared with the `lazy_group` macro
+///
+/// Every member of a group refers to the same `SyncLazy` holding a tuple of all of their values,
+/// so accessing any member runs the initializer once and initializes all of them. The second type
+/// parameter is the type of that tuple.
+pub struct GroupLazy<T: 'static, G: 'static> {
+    name: &'static str,
+    group: fn() -> &'static SyncLazy<G>,
+    project: fn(&G) -> &T,
+}
+
+impl<T, G> GroupLazy<T, G> {
+    #[doc(hidden)]
+    pub const fn __new(name: &'static str, group: fn() -> &'static SyncLazy<G>,
+        project: fn(&G) -> &T) -> GroupLazy<T, G>
+    {
+        GroupLazy { name, group, project }
+    }
+
+    #[doc(hidden)]
+    pub fn __group(this: &GroupLazy<T, G>) -> &'static SyncLazy<G> {
+        (this.group)()
+    }
+
+    /// Initializes every value in the group if they are uninitialized, returning a reference to
+    /// this one
+    #[inline]
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        (self.project)((self.group)().init())
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the group is uninitialized
+    ///
+    /// Uses associated function syntax (`GroupLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &GroupLazy<T, G>) -> Option<&T> {
+        SyncLazy::get((this.group)()).map(this.project)
+    }
+
+    /// Returns the name of the value
+    ///
+    /// Uses associated function syntax (`GroupLazy::name(&VAL)`)
+    pub fn name(this: &GroupLazy<T, G>) -> &'static str {
+        this.name
+    }
+
+    /// Returns `true` if the group has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        (self.group)().is_initialized()
+    }
+}
+
+impl<T, G> Deref for GroupLazy<T, G> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T, G> Debug for GroupLazy<T, G> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match GroupLazy::get(self) {
+            Some(val) => f.debug_tuple(self.name).field(val).finish(),
+            None => write!(f, "{}({{uninitialized}})", self.name),
+        }
+    }
+}
+
+impl<T, G> Display for GroupLazy<T, G> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match GroupLazy::get(self) {
+            Some(val) => val.fmt(f),
+            None => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+impl<T, G> LazyValue for GroupLazy<T, G> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        (self.group)().state()
+    }
+}
@@ -0,0 +1,261 @@
+//! Functions that are called when lazy values are initialized or replaced
+//!
+//! Hooks are stored in a global table keyed by the address of the lazy value they belong to,
+//! which is why they can only be registered on values that live for `'static`.
+
+use core::panic::Location;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::vec::Vec;
+
+/// A hook that has had the type of its argument erased
+pub type Hook = Arc<dyn Fn(*const ()) + Send + Sync>;
+
+/// A function that supplies a value for an uninitialized lazy value, with its type erased
+pub type Fallback = Arc<dyn Fn() -> *const () + Send + Sync>;
+
+struct Hooks {
+    /// Every registered hook, along with the address of its lazy value
+    hooks: Vec<(usize, Hook)>,
+    /// The addresses of the lazy values whose hooks have already been notified of initialization
+    initialized: Vec<usize>,
+    /// Every registered fallback, along with the address and the type name of its lazy value
+    fallbacks: Vec<(usize, &'static str, Fallback)>,
+    /// The handler set with `set_uninit_handler`
+    uninit: Option<fn(&UninitAccess)>,
+    /// How many times each place accessed an uninitialized value
+    uninit_counts: Vec<(UninitAccess, usize)>,
+    /// The threshold set with `set_uninit_threshold`
+    uninit_threshold: Option<usize>,
+}
+
+static HOOKS: Mutex<Hooks> = Mutex::new(Hooks {
+    hooks: Vec::new(),
+    initialized: Vec::new(),
+    fallbacks: Vec::new(),
+    uninit: None,
+    uninit_counts: Vec::new(),
+    uninit_threshold: None,
+});
+
+fn lock() -> MutexGuard<'static, Hooks> {
+    match HOOKS.lock() {
+        Ok(hooks) => hooks,
+        Err(err) => err.into_inner(),
+    }
+}
+
+/// Returns the key of a lazy value in the table
+pub fn key<L>(lazy: &L) -> usize {
+    lazy as *const L as usize
+}
+
+/// Erases the type of the argument of a hook
+///
+/// # Safety
+///
+/// The hook may only be called with pointers to valid values of type `T` that live for `'static`.
+pub unsafe fn erase<T, H>(hook: H) -> Hook
+    where T: 'static, H: Fn(&'static T) + Send + Sync + 'static
+{
+    Arc::new(move |val: *const ()| hook(&*(val as *const T)))
+}
+
+/// Registers a hook for the lazy value at `key`, returning `true` if it has already been
+/// initialized, in which case the caller should call the hook itself
+pub fn register(key: usize, hook: Hook) -> bool {
+    let mut hooks = lock();
+    hooks.hooks.push((key, hook));
+    hooks.initialized.contains(&key)
+}
+
+/// Calls every hook registered for the lazy value at `key` after it has been initialized
+///
+/// # Safety
+///
+/// `val` must point to the value that hooks for `key` expect.
+pub unsafe fn initialized(key: usize, val: *const ()) {
+    let matching = {
+        let mut hooks = lock();
+        hooks.initialized.push(key);
+        matching(&hooks, key)
+    };
+    for hook in matching {
+        hook(val);
+    }
+}
+
+/// Calls every hook registered for the lazy value at `key` after its value has been replaced
+///
+/// # Safety
+///
+/// `val` must point to the value that hooks for `key` expect.
+pub unsafe fn replaced(key: usize, val: *const ()) {
+    let matching = matching(&lock(), key);
+    for hook in matching {
+        hook(val);
+    }
+}
+
+/// Forgets that the lazy value at `key` was initialized, after it has been reset
+#[cfg(feature = "test-support")]
+pub fn reset(key: usize) {
+    lock().initialized.retain(|&initialized| initialized != key);
+}
+
+/// Clones the hooks for `key`, so they can be called without holding the lock
+fn matching(hooks: &Hooks, key: usize) -> Vec<Hook> {
+    hooks.hooks.iter().filter(|hook| hook.0 == key).map(|hook| hook.1.clone()).collect()
+}
+
+/// Registers a fallback for the lazy value at `key`, replacing any previous one
+pub fn register_fallback(key: usize, name: &'static str, fallback: Fallback) {
+    let mut hooks = lock();
+    hooks.fallbacks.retain(|&(other, other_name, _)| (other, other_name) != (key, name));
+    hooks.fallbacks.push((key, name, fallback));
+}
+
+/// Calls the fallback registered for the lazy value at `key`, if there is one
+///
+/// The name of the type of the lazy value is checked as well, since a lazy value can have the same
+/// address as a lazy value stored in it. The access is counted if there is a fallback, and panics
+/// if that puts it over the threshold set with `set_uninit_threshold`.
+#[track_caller]
+pub fn fallback(key: usize, name: &'static str) -> Option<*const ()> {
+    let access = UninitAccess { type_name: name, location: Location::caller() };
+    let (fallback, count) = {
+        let mut hooks = lock();
+        let fallback = hooks.fallbacks.iter()
+            .find(|&&(other, other_name, _)| (other, other_name) == (key, name))
+            .map(|fallback| fallback.2.clone())?;
+        (fallback, count(&mut hooks, access))
+    };
+    if let Some(count) = count {
+        panic!("uninitialized value of type `{}` was dereferenced {} times at {}", name, count,
+            access.location);
+    }
+    Some(fallback())
+}
+
+/// Counts an access to an uninitialized value, returning the count if it is over the threshold
+fn count(hooks: &mut Hooks, access: UninitAccess) -> Option<usize> {
+    let count = match hooks.uninit_counts.iter_mut().find(|count| count.0 == access) {
+        Some(count) => {
+            count.1 += 1;
+            count.1
+        }
+        None => {
+            hooks.uninit_counts.push((access, 1));
+            1
+        }
+    };
+    match hooks.uninit_threshold {
+        Some(threshold) if count > threshold => Some(count),
+        _ => None,
+    }
+}
+
+/// Information about an immutable access to an uninitialized lazy value, which is passed to the
+/// handler set with `set_uninit_handler`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UninitAccess {
+    type_name: &'static str,
+    location: &'static Location<'static>,
+}
+
+impl UninitAccess {
+    /// Returns the name of the type of the value that was accessed
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the location of the access
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+/// Sets a function to be called whenever an uninitialized lazy value is dereferenced immutably,
+/// replacing any previous one
+///
+/// The handler runs before the usual panic, so it can log the access, run code that should happen
+/// instead of crashing (such as loading default settings), or panic or abort with a message of its
+/// own. If it returns, the access still panics, since there is no value to return a reference to.
+/// To supply a value instead, register one for a specific value with `LazyMut::on_uninit`.
+///
+/// ```
+/// use lazy_mut::{LazyMut, UninitAccess};
+/// use std::panic;
+///
+/// fn report(access: &UninitAccess) {
+///     panic!("`{}` accessed too early at {}", access.type_name(), access.location());
+/// }
+///
+/// lazy_mut::set_uninit_handler(report);
+///
+/// let lazy = LazyMut::Init(|| 5);
+/// let err = panic::catch_unwind(|| *lazy).unwrap_err();
+/// assert!(err.downcast_ref::<String>().unwrap().starts_with("`i32` accessed too early"));
+/// ```
+///
+/// Only available with the `hooks` feature.
+pub fn set_uninit_handler(handler: fn(&UninitAccess)) {
+    lock().uninit = Some(handler);
+}
+
+/// Counts an access to an uninitialized value that is about to panic, and calls the handler set
+/// with `set_uninit_handler`, if there is one
+#[track_caller]
+pub fn uninit(type_name: &'static str) {
+    let access = UninitAccess { type_name, location: Location::caller() };
+    let handler = {
+        let mut hooks = lock();
+        count(&mut hooks, access);
+        hooks.uninit
+    };
+    if let Some(handler) = handler {
+        handler(&access);
+    }
+}
+
+/// Returns how many times each place in the program has dereferenced an uninitialized lazy value
+///
+/// Every immutable access to an uninitialized value is counted, whether it panicked or was given
+/// a value by a fallback registered with `LazyMut::on_uninit`. An access that only works because
+/// of a fallback, or because something else happened to initialize the value first in tests,
+/// points to code that depends on initialization order, so these counts help find such code before
+/// it breaks:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+/// use std::panic;
+///
+/// let theme: &'static LazyMut<&str> = Box::leak(Box::new(LazyMut::Init(|| "dark")));
+/// theme.on_uninit(|| &"light");
+///
+/// let render = || theme.len();
+/// assert_eq!(render(), 5);
+/// assert_eq!(render(), 5);
+///
+/// let counts = lazy_mut::uninit_counts();
+/// assert_eq!(counts.len(), 1);
+/// assert_eq!(counts[0].0.type_name(), "&str");
+/// assert_eq!(counts[0].1, 2);
+///
+/// // From here on, the access in `render` panics, even though there is a fallback
+/// lazy_mut::set_uninit_threshold(Some(2));
+/// assert!(panic::catch_unwind(render).is_err());
+/// ```
+///
+/// Only available with the `hooks` feature.
+pub fn uninit_counts() -> Vec<(UninitAccess, usize)> {
+    lock().uninit_counts.clone()
+}
+
+/// Sets how many times each place in the program may dereference an uninitialized lazy value
+/// before it panics, even if a fallback could have given it a value, or `None` to never panic
+/// because of the count
+///
+/// See `uninit_counts` for how accesses are counted. Only available with the `hooks` feature.
+pub fn set_uninit_threshold(threshold: Option<usize>) {
+    lock().uninit_threshold = threshold;
+}
@@ -0,0 +1,147 @@
+use core::fmt::{self, Debug};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A thread-safe lazy value that can be replaced without blocking readers
+///
+/// The value is stored in one of two slots. Reading clones an `Arc` out of the active slot, so
+/// readers always see a complete value and can keep using it for as long as they need. Replacing
+/// the value writes it into the other slot and then flips which slot is active, so readers never
+/// wait for a new value to be built. The value is initialized with the initializer on first access,
+/// and `HotSwap::reload` runs the initializer again to replace it, which covers reloading a
+/// configuration file while requests are being served.
+///
+/// ```
+/// use lazy_mut::HotSwap;
+/// use std::sync::Mutex;
+///
+/// static SOURCE: Mutex<&str> = Mutex::new("workers = 4");
+/// static CONFIG: HotSwap<String> = HotSwap::new(|| SOURCE.lock().unwrap().to_string());
+///
+/// let config = CONFIG.load();
+/// assert_eq!(*config, "workers = 4");
+///
+/// *SOURCE.lock().unwrap() = "workers = 8";
+/// CONFIG.reload();
+///
+/// assert_eq!(*CONFIG.load(), "workers = 8");
+/// assert_eq!(*config, "workers = 4");
+/// ```
+///
+/// Only available with the `std` feature.
+pub struct HotSwap<T, F = fn() -> T> {
+    init: F,
+    ready: AtomicBool,
+    active: AtomicUsize,
+    slots: [RwLock<Option<Arc<T>>>; 2],
+    writer: Mutex<()>,
+}
+
+impl<T, F> HotSwap<T, F> where F: Fn() -> T {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> HotSwap<T, F> {
+        HotSwap {
+            init,
+            ready: AtomicBool::new(false),
+            active: AtomicUsize::new(0),
+            slots: [RwLock::new(None), RwLock::new(None)],
+            writer: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current value, initializing it if needed
+    ///
+    /// This only blocks while the value is being initialized for the first time.
+    #[track_caller]
+    pub fn load(&self) -> Arc<T> {
+        loop {
+            let slot = &self.slots[self.active.load(Ordering::Acquire)];
+            if let Some(ref val) = *read(slot) {
+                return val.clone();
+            }
+            if !self.is_initialized() {
+                self.initialize();
+            }
+        }
+    }
+
+    /// Runs the initializer again and replaces the value with its result, returning the
+    /// previous value if there was one
+    ///
+    /// The initializer runs before any locks are taken, so readers keep seeing the previous value
+    /// until the new one is ready.
+    #[track_caller]
+    pub fn reload(&self) -> Option<Arc<T>> {
+        self.store(::trace::init(&self.init))
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.is_initialized() {
+            let val = ::trace::init(&self.init);
+            *write(&self.slots[self.active.load(Ordering::Acquire)]) = Some(Arc::new(val));
+            self.ready.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl<T, F> HotSwap<T, F> {
+    /// Replaces the value, returning the previous value if there was one
+    pub fn store(&self, val: T) -> Option<Arc<T>> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let current = self.active.load(Ordering::Acquire);
+        if !self.is_initialized() {
+            *write(&self.slots[current]) = Some(Arc::new(val));
+            self.ready.store(true, Ordering::Release);
+            return None;
+        }
+        let next = 1 - current;
+        *write(&self.slots[next]) = Some(Arc::new(val));
+        self.active.store(next, Ordering::Release);
+        write(&self.slots[current]).take()
+    }
+
+    /// Returns the current value without initializing it, returns `None` if the value is
+    /// uninitialized
+    pub fn get(&self) -> Option<Arc<T>> {
+        while self.is_initialized() {
+            let slot = &self.slots[self.active.load(Ordering::Acquire)];
+            if let Some(ref val) = *read(slot) {
+                return Some(val.clone());
+            }
+        }
+        None
+    }
+
+    /// Returns `true` if the value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+}
+
+impl<T, F> Debug for HotSwap<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(val) => f.debug_tuple("HotSwap").field(&val).finish(),
+            None => write!(f, "HotSwap({{uninitialized}})"),
+        }
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    match lock.read() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    }
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    match lock.write() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    }
+}
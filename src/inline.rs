@@ -0,0 +1,253 @@
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use core::panic::Location;
+use core::ptr;
+use {AlreadyInitialized, Initializer};
+use location::InitLocation;
+
+/// A mutable lazy value with a closure initializer stored inline, without allocating
+///
+/// The closure is stored in a buffer of `N` bytes inside of the value itself, aligned to 16 bytes.
+/// Creating an `InlineLazy` from a closure that doesn't fit is a compile-time error. Otherwise,
+/// this behaves just like `LazyMut`, including implicit initialization through `DerefMut`. In
+/// debug builds, the location of the call that initialized the value is tracked (see
+/// `InlineLazy::initialized_at`).
+///
+/// ```
+/// use lazy_mut::InlineLazy;
+///
+/// let base = 40;
+/// let mut answer: InlineLazy<u32> = InlineLazy::new(move || base + 2);
+///
+/// answer.init();
+/// assert_eq!(*answer, 42);
+/// ```
+///
+/// Capturing more than fits in the buffer fails to compile:
+///
+/// ```compile_fail
+/// use lazy_mut::InlineLazy;
+///
+/// let table = [0u64; 8];
+/// let sum: InlineLazy<u64, 16> = InlineLazy::new(move || table.iter().sum());
+/// ```
+pub struct InlineLazy<T, const N: usize = 32> {
+    state: State<T, N>,
+    location: InitLocation,
+}
+
+enum State<T, const N: usize> {
+    Init(Closure<T, N>),
+    Value(T),
+    Poisoned,
+}
+
+impl<T, const N: usize> InlineLazy<T, N> {
+    /// Creates a new uninitialized value with a closure (or any other `Initializer<T>`)
+    ///
+    /// The closure must fit in `N` bytes and have an alignment of at most 16 bytes.
+    pub fn new<F>(init: F) -> InlineLazy<T, N> where F: Initializer<T> + Send + 'static {
+        InlineLazy {
+            state: State::Init(Closure::new(init)),
+            location: InitLocation::new(),
+        }
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        match self.state {
+            State::Init(init) => ::trace::init(|| init.call()),
+            State::Value(val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized
+    #[inline]
+    #[track_caller]
+    pub fn init(&mut self) -> &mut InlineLazy<T, N> {
+        if let State::Init(_) = self.state {
+            self.initialize();
+        }
+        self
+    }
+
+    /// Initializes the wrapped value, panicking if it was already initialized
+    #[track_caller]
+    pub fn init_once(&mut self) -> &mut InlineLazy<T, N> {
+        match self.state {
+            State::Init(_) => self.init(),
+            _ => ::already_initialized(InlineLazy::initialized_at(self)),
+        }
+    }
+
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    #[track_caller]
+    pub fn try_init_once(&mut self) -> Result<&mut T, AlreadyInitialized> {
+        match self.state {
+            State::Init(_) => self.initialize(),
+            _ => return Err(AlreadyInitialized { location: InlineLazy::initialized_at(self) }),
+        }
+        match InlineLazy::get_mut(self) {
+            Some(val) => Ok(val),
+            None => unreachable!(),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`InlineLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &InlineLazy<T, N>) -> Option<&T> {
+        match this.state {
+            State::Value(ref val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`InlineLazy::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut InlineLazy<T, N>) -> Option<&mut T> {
+        match this.state {
+            State::Value(ref mut val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        matches!(self.state, State::Value(_))
+    }
+
+    /// Returns the location of the call that initialized the value
+    ///
+    /// This is only tracked in debug builds, so it always returns `None` in release builds.
+    ///
+    /// Uses associated function syntax (`InlineLazy::initialized_at(&VAL)`)
+    pub fn initialized_at(this: &InlineLazy<T, N>) -> Option<&'static Location<'static>> {
+        this.location.get()
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&mut self) {
+        self.location.set();
+        if let State::Init(init) = mem::replace(&mut self.state, State::Poisoned) {
+            self.state = State::Value(::trace::init(|| init.call()));
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for InlineLazy<T, N> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        match self.state {
+            State::Init(_) => ::uninitialized::<T>(None),
+            State::Value(ref val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+}
+
+impl<T, const N: usize> DerefMut for InlineLazy<T, N> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        if let State::Init(_) = self.state {
+            ::trace::implicit_init::<T>();
+        }
+        self.init();
+        match self.state {
+            State::Init(_) => unreachable!(),
+            State::Value(ref mut val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+}
+
+impl<T, const N: usize> Debug for InlineLazy<T, N> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            State::Init(_) => write!(f, "Init(..)"),
+            State::Value(ref val) => f.debug_tuple("Value").field(val).finish(),
+            State::Poisoned => write!(f, "Poisoned"),
+        }
+    }
+}
+
+impl<T, const N: usize> Display for InlineLazy<T, N> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            State::Value(ref val) => val.fmt(f),
+            _ => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn poisoned() -> ! {
+    panic!("initializer of lazy value previously panicked")
+}
+
+/// A type-erased closure stored in a buffer of `N` bytes
+struct Closure<T, const N: usize> {
+    buffer: Buffer<N>,
+    call: unsafe fn(*mut u8) -> T,
+    drop: unsafe fn(*mut u8),
+}
+
+#[repr(C, align(16))]
+struct Buffer<const N: usize>([MaybeUninit<u8>; N]);
+
+/// Checks at compile time that a closure fits in a buffer of `N` bytes
+struct Fits<F, const N: usize>(PhantomData<F>);
+
+impl<F, const N: usize> Fits<F, N> {
+    const CHECK: () = {
+        assert!(mem::size_of::<F>() <= N, "closure is too big for `InlineLazy` buffer");
+        assert!(mem::align_of::<F>() <= 16, "closure alignment is too big for `InlineLazy`");
+    };
+}
+
+impl<T, const N: usize> Closure<T, N> {
+    fn new<F>(init: F) -> Closure<T, N> where F: Initializer<T> {
+        let () = Fits::<F, N>::CHECK;
+        let mut buffer = Buffer([MaybeUninit::uninit(); N]);
+        unsafe { ptr::write(buffer.0.as_mut_ptr() as *mut F, init) };
+        Closure {
+            buffer,
+            call: call::<F, T>,
+            drop: drop::<F>,
+        }
+    }
+
+    fn call(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { (this.call)(this.buffer.0.as_mut_ptr() as *mut u8) }
+    }
+}
+
+impl<T, const N: usize> Drop for Closure<T, N> {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.buffer.0.as_mut_ptr() as *mut u8) }
+    }
+}
+
+unsafe fn call<F, T>(ptr: *mut u8) -> T where F: Initializer<T> {
+    ptr::read(ptr as *mut F).run()
+}
+
+unsafe fn drop<F>(ptr: *mut u8) {
+    ptr::drop_in_place(ptr as *mut F)
+}
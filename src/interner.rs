@@ -0,0 +1,133 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug};
+use core::hash::{BuildHasher, Hash};
+use std::borrow::ToOwned;
+use std::boxed::Box;
+use std::collections::HashSet;
+use std::collections::hash_map::RandomState;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use SyncLazy;
+
+/// The number of shards, which must be a power of two
+const SHARDS: usize = 16;
+
+/// A thread-safe global interner that deduplicates values and hands out `'static` references
+///
+/// Interning a value returns a reference to the single copy of it stored in the interner, so equal
+/// values interned anywhere in the program share one address and can be compared with
+/// `ptr::eq`. The storage is created lazily on first use, so an interner can be a `static`:
+///
+/// ```
+/// use lazy_mut::LazyInterner;
+/// use std::ptr;
+///
+/// static SYMBOLS: LazyInterner<str> = LazyInterner::new();
+///
+/// let a = SYMBOLS.intern("main");
+/// let b = SYMBOLS.intern(&String::from("main"));
+/// assert!(ptr::eq(a, b));
+///
+/// assert_eq!(SYMBOLS.get("main"), Some(a));
+/// assert_eq!(SYMBOLS.get("other"), None);
+/// assert_eq!(SYMBOLS.len(), 1);
+/// ```
+///
+/// Values are split between several shards by their hash, each behind its own lock, so that
+/// threads interning different values rarely wait for each other. Interned values are never
+/// freed, so an interner should only be used for a bounded set of values.
+///
+/// Only available with the `std` feature.
+pub struct LazyInterner<T: ?Sized + 'static> {
+    shards: SyncLazy<Shards<T>>,
+}
+
+struct Shards<T: ?Sized + 'static> {
+    hasher: RandomState,
+    shards: [Mutex<HashSet<&'static T>>; SHARDS],
+}
+
+impl<T: ?Sized> Shards<T> {
+    fn new() -> Shards<T> {
+        Shards {
+            hasher: RandomState::new(),
+            shards: Default::default(),
+        }
+    }
+}
+
+impl<T: ?Sized> LazyInterner<T> where T: Eq + Hash {
+    /// Creates a new empty interner
+    pub const fn new() -> LazyInterner<T> {
+        LazyInterner { shards: SyncLazy::new(Shards::new) }
+    }
+
+    /// Interns a value, copying it into the interner if it isn't already there
+    pub fn intern(&self, val: &T) -> &'static T where T: ToOwned, T::Owned: Into<Box<T>> {
+        let mut shard = self.shard(val);
+        if let Some(&interned) = shard.get(val) {
+            return interned;
+        }
+        let interned: &'static T = Box::leak(val.to_owned().into());
+        shard.insert(interned);
+        interned
+    }
+
+    /// Interns an owned value, moving it into the interner if it isn't already there
+    pub fn intern_owned<O>(&self, val: O) -> &'static T where O: Borrow<T> + Into<Box<T>> {
+        let mut shard = self.shard(val.borrow());
+        if let Some(&interned) = shard.get(val.borrow()) {
+            return interned;
+        }
+        let interned: &'static T = Box::leak(val.into());
+        shard.insert(interned);
+        interned
+    }
+
+    /// Returns the interned copy of a value, or `None` if it hasn't been interned
+    pub fn get(&self, val: &T) -> Option<&'static T> {
+        self.shard(val).get(val).copied()
+    }
+
+    /// Returns the number of interned values
+    pub fn len(&self) -> usize {
+        match SyncLazy::get(&self.shards) {
+            Some(shards) => shards.shards.iter().map(|shard| lock(shard).len()).sum(),
+            None => 0,
+        }
+    }
+
+    /// Returns `true` if no values have been interned
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard(&self, val: &T) -> MutexGuard<'_, HashSet<&'static T>> {
+        let shards = self.shards.init();
+        let index = shards.hasher.hash_one(val) as usize & (SHARDS - 1);
+        lock(&shards.shards[index])
+    }
+}
+
+impl<T: ?Sized> Default for LazyInterner<T> where T: Eq + Hash {
+    fn default() -> LazyInterner<T> {
+        LazyInterner::new()
+    }
+}
+
+impl<T: ?Sized> Debug for LazyInterner<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shards = match SyncLazy::get(&self.shards) {
+            Some(shards) => shards,
+            None => return f.debug_set().finish(),
+        };
+        let mut set = f.debug_set();
+        for shard in shards.shards.iter() {
+            set.entries(lock(shard).iter());
+        }
+        set.finish()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
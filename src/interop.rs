@@ -0,0 +1,44 @@
+//! Conversions into the lazy types of the standard library
+//!
+//! The standard library can't move a value out of `LazyCell` or `LazyLock` on stable Rust, so
+//! conversions only go in one direction. The converted value keeps its state: an initialized value
+//! is returned right away on first access, and an uninitialized one runs its initializer then.
+
+use core::cell::LazyCell;
+#[cfg(feature = "std")]
+use std::sync::LazyLock;
+use alloc::boxed::Box;
+use {CompactLazy, InlineLazy, Initializer, LazyMut};
+#[cfg(feature = "std")]
+use SyncLazy;
+
+impl<'a, T: 'a> From<LazyMut<T>> for LazyCell<T, Box<dyn FnOnce() -> T + 'a>> {
+    fn from(lazy: LazyMut<T>) -> LazyCell<T, Box<dyn FnOnce() -> T + 'a>> {
+        LazyCell::new(Box::new(move || lazy.unwrap()))
+    }
+}
+
+impl<'a, T: 'a, F: 'a> From<CompactLazy<T, F>> for LazyCell<T, Box<dyn FnOnce() -> T + 'a>>
+    where F: Initializer<T>
+{
+    fn from(lazy: CompactLazy<T, F>) -> LazyCell<T, Box<dyn FnOnce() -> T + 'a>> {
+        LazyCell::new(Box::new(move || lazy.unwrap()))
+    }
+}
+
+impl<'a, T: 'a, const N: usize> From<InlineLazy<T, N>>
+    for LazyCell<T, Box<dyn FnOnce() -> T + 'a>>
+{
+    fn from(lazy: InlineLazy<T, N>) -> LazyCell<T, Box<dyn FnOnce() -> T + 'a>> {
+        LazyCell::new(Box::new(move || lazy.unwrap()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, T: 'a, F: 'a> From<SyncLazy<T, F>> for LazyLock<T, Box<dyn FnOnce() -> T + Send + 'a>>
+    where T: Send, F: Initializer<T> + Send
+{
+    fn from(lazy: SyncLazy<T, F>) -> LazyLock<T, Box<dyn FnOnce() -> T + Send + 'a>> {
+        LazyLock::new(Box::new(move || lazy.unwrap()))
+    }
+}
@@ -0,0 +1,208 @@
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use core::panic::Location;
+use alloc::boxed::Box;
+use {AlreadyInitialized, Initializer};
+use location::InitLocation;
+
+/// A mutable lazy value stored on the heap, which may be unsized
+///
+/// The initializer returns a `Box<T>`, so `T` can be a trait object or a slice, which can't be
+/// stored in any of the other lazy types. Only a single allocation is made, when the value is
+/// initialized. Otherwise, this behaves just like `LazyMut`, including implicit initialization
+/// through `DerefMut`. In debug builds, the location of the call that initialized the value is
+/// tracked (see `LazyBox::initialized_at`).
+///
+/// ```
+/// use lazy_mut::LazyBox;
+///
+/// trait Handler {
+///     fn handle(&self, request: &str) -> String;
+/// }
+///
+/// struct Echo;
+///
+/// impl Handler for Echo {
+///     fn handle(&self, request: &str) -> String {
+///         request.to_string()
+///     }
+/// }
+///
+/// let mut handler: LazyBox<dyn Handler> = LazyBox::new(|| Box::new(Echo));
+/// let mut buffer: LazyBox<[u8]> = LazyBox::new(|| vec![0; 16].into_boxed_slice());
+///
+/// handler.init();
+/// assert_eq!(handler.handle("ping"), "ping");
+///
+/// buffer[0] = 1;
+/// assert_eq!(buffer.len(), 16);
+/// ```
+pub struct LazyBox<T: ?Sized, F = fn() -> Box<T>> {
+    state: State<T, F>,
+    location: InitLocation,
+}
+
+enum State<T: ?Sized, F> {
+    Init(F),
+    Value(Box<T>),
+    Poisoned,
+}
+
+impl<T: ?Sized, F> LazyBox<T, F> where F: Initializer<Box<T>> {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> LazyBox<T, F> {
+        LazyBox { state: State::Init(init), location: InitLocation::new() }
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    #[track_caller]
+    pub fn unwrap(self) -> Box<T> {
+        match self.state {
+            State::Init(init) => ::trace::init(|| init.run()),
+            State::Value(val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized
+    #[inline]
+    #[track_caller]
+    pub fn init(&mut self) -> &mut LazyBox<T, F> {
+        if let State::Init(_) = self.state {
+            self.initialize();
+        }
+        self
+    }
+
+    /// Initializes the wrapped value, panicking if it was already initialized
+    #[track_caller]
+    pub fn init_once(&mut self) -> &mut LazyBox<T, F> {
+        match self.state {
+            State::Init(_) => self.init(),
+            _ => ::already_initialized(LazyBox::initialized_at(self)),
+        }
+    }
+
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    #[track_caller]
+    pub fn try_init_once(&mut self) -> Result<&mut T, AlreadyInitialized> {
+        match self.state {
+            State::Init(_) => self.initialize(),
+            _ => return Err(AlreadyInitialized { location: LazyBox::initialized_at(self) }),
+        }
+        match LazyBox::get_mut(self) {
+            Some(val) => Ok(val),
+            None => unreachable!(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&mut self) {
+        self.location.set();
+        if let State::Init(init) = mem::replace(&mut self.state, State::Poisoned) {
+            self.state = State::Value(::trace::init(|| init.run()));
+        }
+    }
+}
+
+impl<T: ?Sized, F> LazyBox<T, F> {
+    /// Creates a new value that is already initialized
+    pub const fn with_value(val: Box<T>) -> LazyBox<T, F> {
+        LazyBox { state: State::Value(val), location: InitLocation::new() }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyBox::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &LazyBox<T, F>) -> Option<&T> {
+        match this.state {
+            State::Value(ref val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyBox::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut LazyBox<T, F>) -> Option<&mut T> {
+        match this.state {
+            State::Value(ref mut val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        matches!(self.state, State::Value(_))
+    }
+
+    /// Returns the location of the call that initialized the value
+    ///
+    /// This is only tracked in debug builds, so it always returns `None` in release builds.
+    ///
+    /// Uses associated function syntax (`LazyBox::initialized_at(&VAL)`)
+    pub fn initialized_at(this: &LazyBox<T, F>) -> Option<&'static Location<'static>> {
+        this.location.get()
+    }
+}
+
+impl<T: ?Sized, F> Deref for LazyBox<T, F> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        match self.state {
+            State::Init(_) => ::uninitialized::<T>(None),
+            State::Value(ref val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+}
+
+impl<T: ?Sized, F> DerefMut for LazyBox<T, F> where F: Initializer<Box<T>> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        if let State::Init(_) = self.state {
+            ::trace::implicit_init::<T>();
+        }
+        self.init();
+        match self.state {
+            State::Init(_) => unreachable!(),
+            State::Value(ref mut val) => val,
+            State::Poisoned => poisoned(),
+        }
+    }
+}
+
+impl<T: ?Sized, F> Debug for LazyBox<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            State::Init(_) => write!(f, "Init(..)"),
+            State::Value(ref val) => f.debug_tuple("Value").field(&val).finish(),
+            State::Poisoned => write!(f, "Poisoned"),
+        }
+    }
+}
+
+impl<T: ?Sized, F> Display for LazyBox<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.state {
+            State::Value(ref val) => val.fmt(f),
+            _ => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn poisoned() -> ! {
+    panic!("initializer of lazy value previously panicked")
+}
@@ -0,0 +1,198 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use LazyMut;
+
+/// A borrowed `LazyMut` that can only be initialized and accessed, created by `LazyMut::by_ref`
+///
+/// This is a handle for helper functions that may need the value, but shouldn't be able to replace
+/// it, take it out, or reset it to an initializer. Just like the `LazyMut` it borrows, mutable
+/// dereferences initialize the value, which is stored back in the original:
+///
+/// ```
+/// use lazy_mut::{LazyMut, LazyRef};
+///
+/// fn record(log: &mut LazyRef<Vec<String>>, verbose: bool, message: &str) {
+///     if verbose {
+///         log.push(message.to_string());
+///     }
+/// }
+///
+/// let mut log = LazyMut::Init(Vec::new);
+///
+/// record(&mut LazyMut::by_ref(&mut log), false, "skipped");
+/// assert!(!log.is_initialized());
+///
+/// record(&mut LazyMut::by_ref(&mut log), true, "recorded");
+/// assert_eq!(*log, ["recorded"]);
+/// ```
+pub struct LazyRef<'a, T: 'a> {
+    lazy: &'a mut LazyMut<T>,
+}
+
+impl<'a, T> LazyRef<'a, T> {
+    /// Creates a handle that borrows a lazy value
+    pub fn new(lazy: &'a mut LazyMut<T>) -> LazyRef<'a, T> {
+        LazyRef { lazy }
+    }
+
+    /// Initializes the value if needed and returns a mutable reference to it
+    ///
+    /// Uses associated function syntax (`LazyRef::force(&mut VAL)`)
+    #[inline]
+    pub fn force<'b>(this: &'b mut LazyRef<'a, T>) -> &'b mut T {
+        match LazyMut::get_mut(this.lazy.init()) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyRef::get(&VAL)`)
+    #[inline]
+    pub fn get<'b>(this: &'b LazyRef<'a, T>) -> Option<&'b T> {
+        LazyMut::get(this.lazy)
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyRef::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut<'b>(this: &'b mut LazyRef<'a, T>) -> Option<&'b mut T> {
+        LazyMut::get_mut(this.lazy)
+    }
+
+    /// Returns `true` if the borrowed value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.lazy.is_initialized()
+    }
+}
+
+impl<'a, T> Deref for LazyRef<'a, T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.lazy
+    }
+}
+
+impl<'a, T> DerefMut for LazyRef<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        self.lazy
+    }
+}
+
+impl<'a, T> Debug for LazyRef<'a, T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match LazyMut::get(self.lazy) {
+            Some(val) => f.debug_tuple("LazyRef").field(val).finish(),
+            None => write!(f, "LazyRef({{uninitialized}})"),
+        }
+    }
+}
+
+impl<'a, T> Display for LazyRef<'a, T> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self.lazy, f)
+    }
+}
+
+/// A view of part of a `LazyMut`, created by `LazyMut::map_ref`
+///
+/// This dereferences to the part of the value that the projection picked out, such as a single
+/// field, so that it can be handed out without the rest of the value. If the value wasn't
+/// initialized when the view was made, the view stays uninitialized, since the value can't be
+/// initialized while it is borrowed:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+///
+/// struct Config {
+///     name: String,
+///     secret: String,
+/// }
+///
+/// let mut config = LazyMut::Init(|| Config {
+///     name: "server".to_string(),
+///     secret: "hunter2".to_string(),
+/// });
+///
+/// let name = LazyMut::map_ref(&config, |config| &config.name);
+/// assert!(!name.is_initialized());
+///
+/// config.init();
+/// let name = LazyMut::map_ref(&config, |config| &config.name);
+/// assert_eq!(*name, "server");
+/// # assert_eq!(config.secret, "hunter2");
+/// ```
+pub struct LazyRefProj<'a, U: 'a + ?Sized> {
+    value: Option<&'a U>,
+}
+
+impl<'a, U: ?Sized> LazyRefProj<'a, U> {
+    /// Creates a view of a value, projecting it with `f` if it is initialized
+    pub fn new<T>(lazy: &'a LazyMut<T>, f: fn(&T) -> &U) -> LazyRefProj<'a, U> {
+        LazyRefProj { value: LazyMut::get(lazy).map(f) }
+    }
+
+    /// Narrows the view further with another projection
+    pub fn map<V: ?Sized>(self, f: fn(&U) -> &V) -> LazyRefProj<'a, V> {
+        LazyRefProj { value: self.value.map(f) }
+    }
+
+    /// Tries to get a reference to the projected value, returns `None` if the value was
+    /// uninitialized
+    ///
+    /// Uses associated function syntax (`LazyRefProj::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &LazyRefProj<'a, U>) -> Option<&'a U> {
+        this.value
+    }
+
+    /// Returns `true` if the value was initialized when the view was made
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+impl<'a, U: ?Sized> Clone for LazyRefProj<'a, U> {
+    fn clone(&self) -> LazyRefProj<'a, U> {
+        *self
+    }
+}
+
+impl<'a, U: ?Sized> Copy for LazyRefProj<'a, U> {}
+
+impl<'a, U: ?Sized> Deref for LazyRefProj<'a, U> {
+    type Target = U;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &U {
+        match self.value {
+            Some(val) => val,
+            None => ::uninitialized::<U>(None),
+        }
+    }
+}
+
+impl<'a, U: ?Sized> Debug for LazyRefProj<'a, U> where U: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(val) => f.debug_tuple("LazyRefProj").field(&val).finish(),
+            None => write!(f, "LazyRefProj({{uninitialized}})"),
+        }
+    }
+}
+
+impl<'a, U: ?Sized> Display for LazyRefProj<'a, U> where U: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(val) => val.fmt(f),
+            None => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
@@ -46,7 +46,8 @@
 //!
 //! The `lazy_mut` macro also works for static variables:
 //!
-//! ```
+#![cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#![cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
 //! #[macro_use]
 //! extern crate lazy_mut;
 //!
@@ -85,10 +86,715 @@
 //! Note that with the direct definition the function `Vec::new` can be passed directly, making it
 //! simpler to write. `LazyMut` can be used to make simple initializers for types that require heap
 //! allocations at runtime, such as collections, strings, or boxed types.
+//!
+//! Every access to a `static mut` creates a new mutable reference to it, so a reference obtained
+//! through one access must not be used once another access has started. The examples here follow
+//! that rule and run clean under Miri with Stacked Borrows. To make overlapping references
+//! impossible to write by accident, access the static through a raw pointer from
+//! `ptr::addr_of_mut!`, reborrowing it only for as long as it is needed:
+//!
+#![cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#![cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::LazyMut;
+//! use std::ptr;
+//!
+//! lazy_mut! {
+//!     static mut COUNTS: Vec<u64> = vec![0; 4];
+//! }
+//!
+//! fn counts() -> *mut LazyMut<Vec<u64>> {
+//!     unsafe { ptr::addr_of_mut!(COUNTS) }
+//! }
+//!
+//! # fn main() {
+//! unsafe {
+//!     (*counts()).init();
+//!
+//!     let counts = &mut *counts();
+//!     counts[1] += 1;
+//!     assert_eq!(counts[..], [0, 1, 0, 0]);
+//! }
+//! # }
+//! ```
+//!
+//! The `static mut` forms of the macro still store a plain `LazyMut<T>` in a `static mut`, so that
+//! code written against them keeps working, and following this rule is left to the code that
+//! accesses them. Thread-safe statics (below) keep their state in an `UnsafeCell` and only hand
+//! out references derived from raw pointers, so they never need `static mut` at all. The tests in
+//! `tests/macros.rs` use every form of the macro this way, and are meant to be run under Miri.
+//!
+//! With the `strict-statics` feature enabled, every `static mut` form of the `lazy_mut` and
+//! `const_lazy` macros fails to compile, which keeps `static mut` out of a whole workspace once it
+//! has moved to thread-safe statics. The `LazyMut` type itself is still available.
+//!
+//! # Thread-Safe Static Variables
+//!
+//! Leaving out the `mut` makes the macro declare a `SyncLazy<T>` instead, which can be used
+//! without `unsafe`. It is initialized automatically on first access, and if multiple threads
+//! access it at once only one of them will run the initializer:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use std::collections::HashMap;
+//!
+//! lazy_mut! {
+//!     static PRIMES: HashMap<u32, bool> = (1..10).map(|n| (n, is_prime(n))).collect();
+//! }
+//!
+//! fn is_prime(n: u32) -> bool {
+//!     n > 1 && (2..n).all(|d| n % d != 0)
+//! }
+//!
+//! # fn main() {
+//! let threads: Vec<_> = (0..4).map(|_| std::thread::spawn(|| PRIMES[&7])).collect();
+//! for thread in threads {
+//!     assert!(thread.join().unwrap());
+//! }
+//! # }
+//! ```
+//!
+//! `SyncLazy` requires the `std` feature, or on `no_std` targets either the `spin` feature (with
+//! atomics) or the `critical-section` feature (for interrupt-safe statics on microcontrollers).
+//!
+//! A `SyncLazy` can't be modified once it has been initialized. For global state that changes over
+//! time, `SyncLazyMut<T>` stores the value behind a lock so that it can be replaced through a
+//! shared reference, either permanently or only until the end of a scope.
+//!
+//! Values that can be computed in a `const` context can be declared with `const_lazy` instead,
+//! which uses the same types but stores the value already initialized.
+//!
+//! With the `hooks` feature enabled, other parts of a program can register functions to be called
+//! when a lazy static is initialized (`SyncLazy::on_init`) or replaced (`SyncLazyMut::on_change`):
+//!
+//! ```
+//! # #[cfg(feature = "hooks")] {
+//! use lazy_mut::SyncLazy;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! static CONFIG: SyncLazy<String> = SyncLazy::new(|| "production".to_string());
+//! static LABELED: AtomicUsize = AtomicUsize::new(0);
+//!
+//! CONFIG.on_init(|config| {
+//!     assert_eq!(config, "production");
+//!     LABELED.fetch_add(1, Ordering::SeqCst);
+//! });
+//!
+//! assert_eq!(LABELED.load(Ordering::SeqCst), 0);
+//! CONFIG.init();
+//! assert_eq!(LABELED.load(Ordering::SeqCst), 1);
+//! # }
+//! ```
+//!
+//! The same feature lets a program handle immutable accesses to uninitialized values, either for
+//! every value with `set_uninit_handler` or by supplying a value for one static with
+//! `LazyMut::on_uninit`.
+//!
+//! # Fallible Initialization
+//!
+//! `TryLazyMut<T, E>` is used when the initializer can fail. It is never initialized implicitly,
+//! and a failed initialization leaves it uninitialized so that it can be retried:
+//!
+//! ```
+//! use lazy_mut::TryLazyMut;
+//!
+//! let mut port: TryLazyMut<u16, _> = {
+//!     fn init() -> Result<u16, std::num::ParseIntError> { "8080".parse() }
+//!     TryLazyMut::Init(init)
+//! };
+//!
+//! assert_eq!(*port.try_init().unwrap(), 8080);
+//! assert_eq!(*port, 8080);
+//! ```
+//!
+//! With the `catch-panic` feature enabled, a panic in the initializer is returned as
+//! `InitError::Panicked` instead of unwinding through the caller.
+//!
+//! # Other Initializers
+//!
+//! `SyncLazy` accepts any `Initializer<T>`, which includes closures and `extern "C" fn() -> T`
+//! callbacks:
+//!
+//! ```
+//! use lazy_mut::SyncLazy;
+//!
+//! extern "C" fn answer() -> u32 { 42 }
+//!
+//! static ANSWER: SyncLazy<u32, extern "C" fn() -> u32> = SyncLazy::new(answer);
+//!
+//! assert_eq!(*ANSWER, 42);
+//! ```
+//!
+//! Initializers that are `unsafe fn() -> T` can be stored in an `UnsafeLazyMut<T>`, where every
+//! method that may run the initializer is `unsafe` as well.
+//!
+//! An initializer can be wrapped in a `Validated` along with a function that checks its result, so
+//! that a value which fails the check poisons the lazy value instead of being served.
+//!
+//! Slow initializers can be stored in a `ProgressLazy<T>` as a `fn(&Progress) -> T`, which
+//! reports how far along it is so that other threads can show it while they wait.
+//!
+//! # Standard Library Lazy Types
+//!
+//! With the `alloc` feature enabled, the lazy types can be converted into a `LazyCell` from the
+//! standard library (and `SyncLazy` into a `LazyLock` with the `std` feature), so that code can
+//! be migrated piece by piece. The initializer is boxed, and an initialized value stays
+//! initialized:
+//!
+//! ```
+//! use lazy_mut::SyncLazy;
+//! use std::sync::LazyLock;
+//!
+//! let lazy = SyncLazy::new(|| vec![1, 2, 3]);
+//! let lock: LazyLock<Vec<u32>, _> = lazy.into();
+//!
+//! assert_eq!(*lock, [1, 2, 3]);
+//! ```
+//!
+//! # Migrating to Thread-Safe Statics
+//!
+//! A `LazyMut` can be converted into a `SyncLazy` with `LazyMut::upgrade` (or `into`), keeping
+//! its state, so code that owns lazy values can switch to the thread-safe type one value at a
+//! time. A `static mut` can't be moved out of, so the usual path for one is to declare the
+//! thread-safe static next to it, sharing the initializer, and to mark the old one as
+//! `#[deprecated]` so that the compiler points every remaining use at the replacement:
+//!
+#![cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#![cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::{LazyMut, SyncLazy};
+//!
+//! fn default_limits() -> Vec<u32> {
+//!     vec![10, 100]
+//! }
+//!
+//! lazy_mut! {
+//!     #[deprecated(note = "use `LIMITS`, which doesn't need `unsafe`")]
+//!     static mut LIMITS_MUT: Vec<u32> = default_limits();
+//!     static LIMITS: Vec<u32> = default_limits();
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(*LIMITS, [10, 100]);
+//!
+//! let mut lazy = LazyMut::Init(default_limits);
+//! lazy.init().push(1000);
+//! let lazy: SyncLazy<Vec<u32>> = LazyMut::upgrade(lazy);
+//! assert_eq!(SyncLazy::get(&lazy), Some(&vec![10, 100, 1000]));
+//! # }
+//! ```
+//!
+//! Once nothing uses the old static, it can be removed, and the `strict-statics` feature keeps new
+//! ones from being added.
+//!
+//! # `once_cell` Compatibility
+//!
+//! With the `once_cell-compat` feature enabled, the lazy types have the same associated functions
+//! as the `Lazy` types of the `once_cell` crate (`force`, `force_mut`, `get`, `get_or_init`, and
+//! `into_value`), and they can be converted to and from them. `SyncLazy` corresponds to
+//! `once_cell::sync::Lazy`, and `CompactLazy` and `LazyMut` correspond to
+//! `once_cell::unsync::Lazy`:
+//!
+//! ```
+//! # #[cfg(feature = "once_cell-compat")] {
+//! extern crate once_cell;
+//! # extern crate lazy_mut;
+//!
+//! use lazy_mut::SyncLazy;
+//!
+//! let lazy = once_cell::sync::Lazy::new(|| 2 + 3);
+//! once_cell::sync::Lazy::force(&lazy);
+//!
+//! let lazy: SyncLazy<u32, _> = lazy.into();
+//! assert_eq!(SyncLazy::get(&lazy), Some(&5));
+//! assert_eq!(SyncLazy::into_value(lazy).ok(), Some(5));
+//! # }
+//! ```
+//!
+//! # Serializing Lazy Fields
+//!
+//! With the `serde` feature enabled, a `LazyMut<T>` is serialized as its value and deserialized
+//! into an initialized value. A lazily computed cache usually shouldn't be serialized at all,
+//! since it can be computed again, and an uninitialized value can't be serialized. Skipping it
+//! while it is uninitialized and giving it a default with its initializer keeps it out of
+//! snapshots without losing it:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde;
+//! extern crate serde_json;
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::LazyMut;
+//!
+//! # #[cfg(feature = "serde")]
+//! #[derive(Serialize, Deserialize)]
+//! struct Document {
+//!     text: String,
+//!     #[serde(skip_serializing_if = "LazyMut::is_uninit", default = "Document::word_count")]
+//!     word_count: LazyMut<usize>,
+//! }
+//!
+//! # #[cfg(feature = "serde")]
+//! impl Document {
+//!     fn word_count() -> LazyMut<usize> {
+//!         LazyMut::Init(|| 0)
+//!     }
+//! }
+//!
+//! # fn main() {
+//! # #[cfg(feature = "serde")] {
+//! let doc = Document { text: "a lazy document".to_string(), word_count: Document::word_count() };
+//! let json = serde_json::to_string(&doc).unwrap();
+//! assert_eq!(json, r#"{"text":"a lazy document"}"#);
+//!
+//! let doc: Document = serde_json::from_str(&json).unwrap();
+//! assert!(!doc.word_count.is_initialized());
+//!
+//! let doc: Document = serde_json::from_str(r#"{"text":"","word_count":3}"#).unwrap();
+//! assert_eq!(*doc.word_count, 3);
+//! # }
+//! # }
+//! ```
+//!
+//! Since the initializer is a `fn() -> T`, it can't depend on the other fields. To skip a cache
+//! even once it has been initialized, use `#[serde(skip, default = "...")]` instead.
+//!
+//! # Aborting on Uninitialized Access
+//!
+//! Dereferencing an uninitialized value normally panics. With the `abort-on-uninit` feature
+//! enabled, it instead prints the type of the value to standard error and aborts the process.
+//! This is useful for libraries built with `panic = "abort"` or called through C frames, where
+//! unwinding is not an option.
+//!
+//! Either way, the message names the type of the value. With the `registry` feature enabled, it
+//! also names a `static mut` declared with the macro, so that statics of the same type can be told
+//! apart:
+//!
+#![cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#![cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     static mut WIDTH: u32 = 80;
+//! }
+//!
+//! # #[cfg(all(feature = "registry", not(feature = "abort-on-uninit")))]
+//! # fn main() {
+//! use std::panic;
+//! use std::ptr;
+//!
+//! let err = panic::catch_unwind(|| unsafe { **ptr::addr_of!(WIDTH) }).unwrap_err();
+//! let message = err.downcast::<String>().unwrap();
+//! assert_eq!(*message, "cannot dereference uninitialized value `WIDTH` of type `u32`");
+//! # }
+//! # #[cfg(not(all(feature = "registry", not(feature = "abort-on-uninit"))))]
+//! # fn main() {}
+//! ```
+//!
+//! # Embedded Logging
+//!
+//! With the `embedded-logging` feature enabled, the lazy types implement `defmt::Format` when
+//! their value does, so they can be logged with `defmt` just like with `Display`.
+//!
+//! # Tracing and Logging
+//!
+//! With the `tracing` feature enabled, every initializer runs inside of an `init` span from the
+//! `tracing` crate, recording the type of the value and, for variables declared with the macro,
+//! its name. Implicit initialization through `DerefMut` is also reported with a debug event.
+//!
+//! With the `log` feature enabled, a warning is logged with the `log` crate whenever a value is
+//! initialized implicitly through `DerefMut`, so that code relying on it can be found and changed
+//! to call `init` explicitly.
+//!
+//! # Registry
+//!
+//! With the `registry` feature enabled, every thread-safe static declared with the macro is
+//! registered so that they can all be initialized at once during startup, optionally in parallel
+//! with the `rayon` feature. Those that have been initialized can also be dropped at the end of
+//! `main` with `shutdown`, which statics otherwise never are. See the `registry` module for more
+//! information.
+//!
+//! With the `metrics` feature enabled, each thread-safe lazy value also records when it was first
+//! accessed and how long it took to initialize. See the `metrics` module for more information.
+//!
+//! With the `alloc-accounting` feature enabled, the memory that each registered lazy static
+//! allocates while it is initialized is counted as well. See the `accounting` module for more
+//! information.
+//!
+//! With the `test-support` feature enabled, registered lazy statics can be reset between tests so
+//! that tests don't depend on the order they run in. See the `test_support` module for more
+//! information.
+//!
+//! # Eager Initialization
+//!
+//! With the `ctor` feature enabled, a thread-safe static declared with the macro can be marked
+//! `#[eager]` to initialize it from a constructor function that runs before `main`, using the
+//! `ctor` crate. It is still a `SyncLazy<T>`, so code that uses it doesn't need to know whether it
+//! was initialized eagerly. Without the feature, `#[eager]` statics are initialized lazily as
+//! usual, so they can be made eager only in release builds:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     #[eager]
+//!     static ROUTES: Vec<&'static str> = vec!["/", "/about"];
+//! }
+//!
+//! # fn main() {
+//! # #[cfg(feature = "ctor")]
+//! assert!(ROUTES.is_initialized());
+//! assert_eq!(ROUTES.len(), 2);
+//! # }
+//! ```
+//!
+//! `#[eager]` must come before any other attributes. The initializers run in an unspecified order
+//! before the standard library is fully set up, so they shouldn't depend on each other or do much
+//! more than compute a value. A panic in one of them aborts the program.
+//!
+//! # Testing Initializers
+//!
+//! A static declared with the macro can be marked `#[test_init]` to generate a unit test that runs
+//! its initializer on its own and fails if it panics. This catches a broken initializer in CI even
+//! if no other test happens to use the static:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     #[test_init]
+//!     static PORT: u16 = "8080".parse().unwrap();
+//! }
+//! # fn main() {
+//! # assert_eq!(*PORT, 8080);
+//! # }
+//! ```
+//!
+//! The test is generated in a module with the same name as the static, so it shows up as
+//! `PORT::initializer_does_not_panic`. The expression is evaluated in that module, which imports
+//! everything from the module the static is declared in, so it can't use `self` or `super` paths.
+//! Like `#[eager]`, `#[test_init]` must come before any other attributes, including `#[eager]`, but
+//! it works with every static form of the macro.
+//!
+//! # Checking Thread Affinity
+//!
+//! A `static mut` is only sound if every access comes from the same thread (or is otherwise
+//! synchronized). With the `thread-affinity` feature enabled, debug builds record which thread
+//! initialized each `static mut` declared with the macro, and panic with the names of both
+//! threads if another thread accesses it later:
+//!
+#![cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#![cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use std::ptr;
+//! use std::thread;
+//!
+//! lazy_mut! {
+//!     static mut CACHE: Vec<u32> = Vec::new();
+//! }
+//!
+//! # fn main() {
+//! unsafe { (*ptr::addr_of_mut!(CACHE)).init().push(1) };
+//!
+//! let result = thread::Builder::new()
+//!     .name("worker".to_string())
+//!     .spawn(|| unsafe { (*ptr::addr_of!(CACHE)).len() })
+//!     .unwrap()
+//!     .join();
+//! # #[cfg(all(feature = "thread-affinity", debug_assertions))]
+//! assert!(result.is_err()); // initialized on thread `main`, but accessed on thread `worker`
+//! # }
+//! ```
+//!
+//! Every access then takes a lock, so this is meant for debugging. A value that is accessed by
+//! other threads before it is initialized isn't caught, and only one thread can ever use the
+//! value, even if accesses from different threads are synchronized some other way. Release builds
+//! don't check anything.
+//!
+//! # Caching Values in Files
+//!
+//! With the `persist` feature enabled, `persist::PersistLazy` stores the result of its initializer
+//! in a file with `serde`, so that later runs of the program can read it back instead of running
+//! the initializer again. See the `persist` module for more information.
+//!
+//! # Sharing Values Between Processes
+//!
+//! With the `shared-mem` feature enabled on Unix, `shared_mem::SharedMemLazy` stores its value in
+//! named shared memory, so that the initializer runs only once for every process that uses it.
+//! See the `shared_mem` module for more information.
+//!
+//! For memory that is mapped some other way, `FixedLazy<T>` has a fixed `#[repr(C)]` layout of a
+//! state word followed by the value, and is uninitialized when zeroed, so it can be embedded in
+//! structs that are shared with other processes or languages.
+//!
+//! # Reloading Files
+//!
+//! With the `watch` feature enabled, `watch::WatchLazy` reads a file on first access and reads it
+//! again whenever it changes, using the `notify` crate. See the `watch` module for more
+//! information.
+//!
+//! # C Interface
+//!
+//! With the `ffi` feature enabled, the `lazy_mut_ffi` macro declares thread-safe lazy statics
+//! along with `extern "C"` functions that let a C host initialize them and check whether they are
+//! ready. See the `ffi` module for more information.
+//!
+//! # Calling Lazy Functions
+//!
+//! With the `nightly` feature enabled on a nightly compiler, a `LazyMut<F>` can be called like the
+//! function or closure `F` that it wraps. Calling it mutably initializes it first, so a handler
+//! that is expensive to build can be created when it is first called:
+//!
+//! ```
+//! # #[cfg(feature = "nightly")] {
+//! use lazy_mut::LazyMut;
+//!
+//! fn build_handler() -> Box<dyn FnMut(u32) -> u32> {
+//!     let mut total = 0;
+//!     Box::new(move |event| {
+//!         total += event;
+//!         total
+//!     })
+//! }
+//!
+//! let mut handler = LazyMut::Init(build_handler);
+//! assert_eq!(handler(2), 2);
+//! assert_eq!(handler(3), 5);
+//! # }
+//! ```
+//!
+//! Calling it through a shared reference panics if it is uninitialized, just like dereferencing.
+//!
+//! # Archiving Values
+//!
+//! With the `rkyv` feature enabled, initialized `LazyMut` and `SyncLazy` values can be archived
+//! with `rkyv`, so that a value built lazily can be saved and accessed on the next run without
+//! copying. See `ArchivedLazy` for more information.
+//!
+//! # `no_std` Support
+//!
+//! The crate is `no_std` when its default `std` feature is disabled. `LazyMut`, `TryLazyMut`, and
+//! both forms of the `lazy_mut` macro only depend on `core`. Features that need an allocator are
+//! enabled by the `alloc` feature, and features that need the standard library (such as
+//! `catch-panic` and `abort-on-uninit`) enable `std` automatically.
 #![deny(missing_docs)]
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(fn_traits, tuple_trait, unboxed_closures))]
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate std;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "critical-section")]
+extern crate critical_section;
+#[cfg(feature = "embedded-logging")]
+extern crate defmt;
+#[cfg(any(feature = "ffi", all(feature = "shared-mem", unix),
+    all(feature = "std", any(target_os = "linux", target_os = "android"))))]
+extern crate libc;
+#[cfg(feature = "registry")]
+#[doc(hidden)]
+pub extern crate inventory as __inventory;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub extern crate tracing as __tracing;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "once_cell-compat")]
+extern crate once_cell;
+#[cfg(feature = "watch")]
+extern crate notify;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "persist")]
+extern crate serde_json;
+#[cfg(feature = "ctor")]
+#[doc(hidden)]
+pub extern crate ctor as __ctor;
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
 
-use std::ops::{Deref, DerefMut};
-use std::fmt::{self, Display};
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Display};
+use core::panic::Location;
+use core::pin::Pin;
+use core::ptr;
+#[cfg(feature = "alloc")]
+use alloc::borrow::{Cow, ToOwned};
+#[cfg(feature = "hooks")]
+use std::sync::Arc;
+
+#[cfg(feature = "alloc-accounting")]
+pub mod accounting;
+#[cfg(feature = "thread-affinity")]
+mod affinity;
+mod array;
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "nightly")]
+mod call;
+#[cfg(feature = "std")]
+mod channel;
+mod compact;
+#[cfg(feature = "std")]
+mod context;
+mod cow;
+#[cfg(feature = "std")]
+mod env;
+#[cfg(feature = "std")]
+mod expiring;
+mod fallible;
+mod field;
+#[cfg(target_has_atomic = "32")]
+mod fixed;
+#[cfg(feature = "std")]
+mod generation;
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+mod group;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "hooks")]
+mod hooks;
+#[cfg(feature = "std")]
+mod hot_swap;
+mod inline;
+#[cfg(feature = "std")]
+mod interner;
+#[cfg(feature = "alloc")]
+mod interop;
+#[cfg(feature = "alloc")]
+mod lazy_box;
+mod lazy_ref;
+mod location;
+#[cfg(feature = "std")]
+pub mod map;
+#[cfg(feature = "std")]
+mod memo;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "once_cell-compat")]
+mod once_cell_compat;
+mod padded;
+#[cfg(feature = "std")]
+mod per_variant;
+#[cfg(feature = "persist")]
+pub mod persist;
+#[cfg(feature = "std")]
+mod pool;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+mod realtime;
+#[cfg(feature = "std")]
+pub mod regen;
+#[cfg(feature = "registry")]
+pub mod registry;
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+mod sync;
+#[cfg(all(feature = "shared-mem", unix))]
+pub mod shared_mem;
+mod slot;
+#[cfg(feature = "std")]
+mod sync_mut;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+mod trace;
+#[cfg(feature = "std")]
+mod two_phase;
+#[cfg(feature = "std")]
+mod type_map;
+mod typestate;
+mod unsafe_lazy;
+mod validated;
+mod value;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+#[cfg(feature = "rkyv")]
+pub use archive::{ArchivedLazy, UninitializedError};
+pub use array::LazyArray;
+#[cfg(feature = "std")]
+pub use channel::LazyChannel;
+pub use compact::CompactLazy;
+#[cfg(feature = "std")]
+pub use context::{ContextLazy, InitContext};
+pub use cow::CowLazy;
+#[cfg(feature = "std")]
+pub use env::{EnvVarError, LazyEnvVar};
+#[cfg(feature = "std")]
+pub use expiring::ExpiringLazy;
+pub use fallible::{TryLazyMut, InitError, InitPanic};
+pub use field::LazyField;
+#[cfg(target_has_atomic = "32")]
+pub use fixed::FixedLazy;
+#[cfg(feature = "std")]
+pub use generation::{derive_from, Derived, GenLazy, GenRef, GenSource};
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+pub use group::GroupLazy;
+#[cfg(feature = "hooks")]
+pub use hooks::{set_uninit_handler, set_uninit_threshold, uninit_counts, UninitAccess};
+#[cfg(feature = "std")]
+pub use hot_swap::HotSwap;
+pub use inline::InlineLazy;
+#[cfg(feature = "std")]
+pub use interner::LazyInterner;
+#[cfg(feature = "alloc")]
+pub use lazy_box::LazyBox;
+pub use lazy_ref::{LazyRef, LazyRefProj};
+#[cfg(feature = "std")]
+pub use map::LazyMap;
+#[cfg(feature = "std")]
+pub use memo::{Memo, SyncMemo};
+pub use padded::CachePadded;
+#[cfg(feature = "std")]
+pub use per_variant::{EnumArray, LazyPerVariant};
+#[cfg(feature = "std")]
+pub use pool::LazyPool;
+#[cfg(feature = "std")]
+pub use progress::{Progress, ProgressLazy};
+#[cfg(feature = "std")]
+pub use realtime::RealtimeLazy;
+#[cfg(feature = "registry")]
+pub use registry::shutdown;
+pub use slot::{Slot, SplitInit};
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+pub use sync::{InitDriver, InitStep, Initialized, InvalidStep, SyncLazy};
+#[cfg(feature = "std")]
+pub use sync_mut::{SyncLazyMut, RestoreGuard};
+#[cfg(feature = "std")]
+pub use two_phase::{Phase, TwoPhaseLazy};
+#[cfg(feature = "std")]
+pub use type_map::{LazyDefault, LazyTypeMap};
+pub use typestate::{Ready, Uninit};
+pub use unsafe_lazy::UnsafeLazyMut;
+pub use validated::Validated;
+pub use value::{LazyState, LazyValue};
+#[doc(hidden)]
+pub use trace::name as __name;
+#[cfg(feature = "thread-affinity")]
+#[doc(hidden)]
+pub use affinity::claim as __claim;
+#[cfg(any(feature = "thread-affinity", feature = "registry"))]
+#[doc(hidden)]
+pub use core::ptr::addr_of as __addr_of;
 
 /// A macro that creates lazy variables
 ///
@@ -101,46 +807,543 @@ use std::fmt::{self, Display};
 ///
 ///     // Static variables
 ///     [pub [(VIS)]] static mut NAME: TY = EXPR;
+///
+///     // Thread-safe static variables
+///     [pub [(VIS)]] static NAME: TY = EXPR;
+///
+///     // Thread-safe static variables, initialized before `main` with the `ctor` feature
+///     #[eager] [pub [(VIS)]] static NAME: TY = EXPR;
+///
+///     // Static variables with a generated test that runs the initializer
+///     #[test_init] [pub [(VIS)]] static [mut] NAME: TY = EXPR;
+/// }
+/// ```
+///
+/// # Examples
+///
+/// Every form of the macro, used the way the crate documentation recommends (this also checks
+/// the expansion under Miri):
+///
+#[cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#[cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::ptr;
+///
+/// mod config {
+///     lazy_mut! {
+///         pub static mut NAMES: Vec<&'static str> = vec!["a"];
+///         pub(crate) static mut LIMIT: usize = 2;
+///         pub static GREETING: String = "hello".to_string();
+///         pub(crate) static ANSWER: u32 = 42;
+///     }
+/// }
+///
+/// lazy_mut! {
+///     static mut TOTAL: u64 = 0;
+///     static PRIMES: Vec<u32> = vec![2, 3, 5];
+/// }
+///
+/// # fn main() {
+/// lazy_mut! {
+///     let mut local: Vec<u8> = Vec::new();
+/// }
+/// local.init();
+/// local.push(1);
+/// assert_eq!(*local, [1]);
+///
+/// unsafe {
+///     let names = ptr::addr_of_mut!(config::NAMES);
+///     (*names).init();
+///     (*names).push("b");
+///     assert_eq!(**names, ["a", "b"]);
+///
+///     let limit = ptr::addr_of_mut!(config::LIMIT);
+///     **limit += 1;
+///     assert_eq!(**limit, 3);
+///
+///     let total = ptr::addr_of_mut!(TOTAL);
+///     **total += 5;
+///     assert_eq!(**total, 5);
+/// }
+///
+/// assert_eq!(*config::GREETING, "hello");
+/// assert_eq!(*config::ANSWER, 42);
+/// assert_eq!(PRIMES.iter().sum::<u32>(), 10);
+/// # }
+/// ```
+///
+/// Each expression becomes the body of a closure that is passed straight to the constructor of
+/// the lazy value, so the declaration is a constant expression and errors in the expression point
+/// at the expression itself. The closure is stored as a `fn() -> T`, so it can't capture local
+/// variables:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// # fn main() {
+/// let base = 10;
+/// lazy_mut! {
+///     let mut limit: u32 = base * 2;
 /// }
+/// # }
 /// ```
+///
+/// Values that can be computed at compile time can be declared with `const_lazy` instead, so they
+/// never run an initializer.
 #[macro_export]
 macro_rules! lazy_mut {
     (/* empty */) => {};
+    (#[test_init] $(#[$($attr:tt)*])* static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* static mut $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[test_init] $(#[$($attr:tt)*])* pub static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* pub static mut $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[test_init] $(#[$($attr:tt)*])* pub ($($vis:tt)+) static mut $N:ident: $T:ty = $e:expr;
+        $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* pub ($($vis)+) static mut $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[test_init] $(#[$($attr:tt)*])* static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* static $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[test_init] $(#[$($attr:tt)*])* pub static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* pub static $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[test_init] $(#[$($attr:tt)*])* pub ($($vis:tt)+) static $N:ident: $T:ty = $e:expr;
+        $($t:tt)*) => {
+        lazy_mut!($(#[$($attr)*])* pub ($($vis)+) static $N: $T = $e;);
+        $crate::__lazy_mut_test_init!($N: $T = $e);
+        lazy_mut!($($t)*);
+    };
+    (#[eager] $(#[$attr:meta])* static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$attr])* static $N: $T = $e;);
+        $crate::__lazy_mut_eager!($N);
+        lazy_mut!($($t)*);
+    };
+    (#[eager] $(#[$attr:meta])* pub static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        lazy_mut!($(#[$attr])* pub static $N: $T = $e;);
+        $crate::__lazy_mut_eager!($N);
+        lazy_mut!($($t)*);
+    };
+    (#[eager] $(#[$attr:meta])* pub ($($vis:tt)+) static $N:ident: $T:ty = $e:expr;
+        $($t:tt)*) => {
+        lazy_mut!($(#[$attr])* pub ($($vis)+) static $N: $T = $e;);
+        $crate::__lazy_mut_eager!($N);
+        lazy_mut!($($t)*);
+    };
     ($(#[$attr:meta])* let mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
         $(#[$attr])*
-        let mut $N: $crate::LazyMut<$T> = {
-            fn init() -> $T { $e }
-            $crate::LazyMut::Init(init)
-        };
+        let mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Init(|| -> $T {
+            $crate::__lazy_mut_name!($N);
+            $e
+        });
         lazy_mut!($($t)*);
     };
     ($(#[$attr:meta])* static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
         $(#[$attr])*
-        static mut $N: $crate::LazyMut<$T> = {
-            fn init() -> $T { $e }
-            $crate::LazyMut::Init(init)
-        };
+        static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Init(|| -> $T {
+            $crate::__lazy_mut_name!($N);
+            $crate::__lazy_mut_claim!($N);
+            $e
+        });
+        $crate::__lazy_mut_register_static_mut!($N);
+        lazy_mut!($($t)*);
+    };
+    ($(#[$attr:meta])* pub static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
+        $(#[$attr])*
+        pub static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Init(|| -> $T {
+            $crate::__lazy_mut_name!($N);
+            $crate::__lazy_mut_claim!($N);
+            $e
+        });
+        $crate::__lazy_mut_register_static_mut!($N);
+        lazy_mut!($($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static mut $N:ident: $T:ty = $e:expr;
+        $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
+        $(#[$attr])*
+        pub ($($vis)+) static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Init(|| -> $T {
+            $crate::__lazy_mut_name!($N);
+            $crate::__lazy_mut_claim!($N);
+            $e
+        });
+        $crate::__lazy_mut_register_static_mut!($N);
+        lazy_mut!($($t)*);
+    };
+    ($(#[$attr:meta])* static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        static $N: $crate::SyncLazy<$T> =
+            $crate::SyncLazy::named(stringify!($N), || -> $T {
+                $crate::__lazy_mut_name!($N);
+                $e
+            });
+        $crate::__lazy_mut_register!($N);
+        lazy_mut!($($t)*);
+    };
+    ($(#[$attr:meta])* pub static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        pub static $N: $crate::SyncLazy<$T> =
+            $crate::SyncLazy::named(stringify!($N), || -> $T {
+                $crate::__lazy_mut_name!($N);
+                $e
+            });
+        $crate::__lazy_mut_register!($N);
+        lazy_mut!($($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        pub ($($vis)+) static $N: $crate::SyncLazy<$T> =
+            $crate::SyncLazy::named(stringify!($N), || -> $T {
+                $crate::__lazy_mut_name!($N);
+                $e
+            });
+        $crate::__lazy_mut_register!($N);
         lazy_mut!($($t)*);
     };
-    ($(#[$attr:meta])* pub static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
-        $(#[$attr])*
-        pub static mut $N: $crate::LazyMut<$T> = {
-            fn init() -> $T { $e }
-            $crate::LazyMut::Init(init)
+}
+
+/// A macro that creates lazy variables which are already initialized at compile time
+///
+/// This accepts the same static forms as `lazy_mut`, and declares the same types, but evaluates
+/// each expression at compile time and stores the initialized value directly in the static. Code
+/// that works with lazy values doesn't need to know the difference, while values that can be
+/// computed in a `const` context never run an initializer:
+///
+#[cfg_attr(not(feature = "strict-statics"), doc = "```")]
+#[cfg_attr(feature = "strict-statics", doc = "```compile_fail")]
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use lazy_mut::SyncLazy;
+///
+/// const fn kib(n: usize) -> usize {
+///     n * 1024
+/// }
+///
+/// const_lazy! {
+///     static BUFFER_SIZE: usize = kib(64);
+///     static mut RETRIES: u32 = 3;
+/// }
+///
+/// lazy_mut! {
+///     static HOSTNAME: String = "localhost".to_string();
+/// }
+///
+/// fn describe<T: std::fmt::Debug>(lazy: &SyncLazy<T>) -> String {
+///     format!("{:?}", lazy)
+/// }
+///
+/// # fn main() {
+/// assert!(BUFFER_SIZE.is_initialized());
+/// assert_eq!(describe(&BUFFER_SIZE), "SyncLazy(65536)");
+/// assert_eq!(describe(&HOSTNAME), "HOSTNAME({uninitialized})");
+///
+/// unsafe {
+///     let retries = std::ptr::addr_of_mut!(RETRIES);
+///     **retries -= 1;
+///     assert_eq!(**retries, 2);
+/// }
+/// # }
+/// ```
+///
+/// Since the values are never dropped, this can only be used for types that can be built in a
+/// `const` context.
+#[macro_export]
+macro_rules! const_lazy {
+    (/* empty */) => {};
+    ($(#[$attr:meta])* static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
+        $(#[$attr])*
+        static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Value($e);
+        const_lazy!($($t)*);
+    };
+    ($(#[$attr:meta])* pub static mut $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
+        $(#[$attr])*
+        pub static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Value($e);
+        const_lazy!($($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static mut $N:ident: $T:ty = $e:expr;
+        $($t:tt)*) => {
+        $crate::__lazy_mut_static_mut!();
+        $(#[$attr])*
+        pub ($($vis)+) static mut $N: $crate::LazyMut<$T> = $crate::LazyMut::Value($e);
+        const_lazy!($($t)*);
+    };
+    ($(#[$attr:meta])* static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        static $N: $crate::SyncLazy<$T> = $crate::SyncLazy::with_value($e);
+        const_lazy!($($t)*);
+    };
+    ($(#[$attr:meta])* pub static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        pub static $N: $crate::SyncLazy<$T> = $crate::SyncLazy::with_value($e);
+        const_lazy!($($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static $N:ident: $T:ty = $e:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        pub ($($vis)+) static $N: $crate::SyncLazy<$T> = $crate::SyncLazy::with_value($e);
+        const_lazy!($($t)*);
+    };
+}
+
+/// A macro that creates several lazy statics from one initializer
+///
+/// The initializer returns a tuple with a value for each static, and runs the first time any of
+/// them is accessed, initializing all of them at once. This is useful when one expensive setup
+/// step produces several related values:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static SETUPS: AtomicUsize = AtomicUsize::new(0);
+///
+/// fn setup() -> (String, Vec<u16>) {
+///     SETUPS.fetch_add(1, Ordering::SeqCst);
+///     ("db.local".to_string(), vec![5432, 5433])
+/// }
+///
+/// lazy_group! {
+///     static (HOST: String, PORTS: Vec<u16>) = setup();
+/// }
+///
+/// # fn main() {
+/// assert!(!PORTS.is_initialized());
+/// assert_eq!(*HOST, "db.local");
+/// assert!(PORTS.is_initialized());
+/// assert_eq!(*PORTS, [5432, 5433]);
+/// assert_eq!(SETUPS.load(Ordering::SeqCst), 1);
+/// # }
+/// ```
+///
+/// Each static is a `GroupLazy`. Attributes are applied to every static in the group.
+#[macro_export]
+macro_rules! lazy_group {
+    (/* empty */) => {};
+    ($(#[$attr:meta])* static ($($N:ident: $T:ty),+ $(,)?) = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_group!([$(#[$attr])*] [] ($($T,)+), $e; $($N: $T),+);
+        lazy_group!($($t)*);
+    };
+    ($(#[$attr:meta])* pub static ($($N:ident: $T:ty),+ $(,)?) = $e:expr; $($t:tt)*) => {
+        $crate::__lazy_group!([$(#[$attr])*] [pub] ($($T,)+), $e; $($N: $T),+);
+        lazy_group!($($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static ($($N:ident: $T:ty),+ $(,)?) = $e:expr;
+        $($t:tt)*) => {
+        $crate::__lazy_group!([$(#[$attr])*] [pub ($($vis)+)] ($($T,)+), $e; $($N: $T),+);
+        lazy_group!($($t)*);
+    };
+}
+
+/// Declares the statics of a group, where the first one holds the value of the whole group and
+/// the rest refer to it
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_group {
+    ([$($attr:tt)*] [$($vis:tt)*] $G:ty, $e:expr; $first:ident: $T:ty $(, $N:ident: $U:ty)*) => {
+        $($attr)*
+        $($vis)* static $first: $crate::GroupLazy<$T, $G> = {
+            fn group() -> &'static $crate::SyncLazy<$G> {
+                static GROUP: $crate::SyncLazy<$G> = $crate::SyncLazy::new(|| -> $G { $e });
+                &GROUP
+            }
+            fn project(group: &$G) -> &$T {
+                let (ref value, ..) = *group;
+                value
+            }
+            $crate::GroupLazy::__new(stringify!($first), group, project)
+        };
+        $crate::__lazy_group!(@rest [$($attr)*] [$($vis)*] $G, $first; [_,] $($N: $U),*);
+    };
+    (@rest [$($attr:tt)*] [$($vis:tt)*] $G:ty, $first:ident; [$($skip:tt)*]
+        $N:ident: $T:ty $(, $rest:ident: $U:ty)*) => {
+        $($attr)*
+        $($vis)* static $N: $crate::GroupLazy<$T, $G> = {
+            fn group() -> &'static $crate::SyncLazy<$G> {
+                $crate::GroupLazy::__group(&$first)
+            }
+            fn project(group: &$G) -> &$T {
+                let ($($skip)* ref value, ..) = *group;
+                value
+            }
+            $crate::GroupLazy::__new(stringify!($N), group, project)
+        };
+        $crate::__lazy_group!(@rest [$($attr)*] [$($vis)*] $G, $first; [$($skip)* _,]
+            $($rest: $U),*);
+    };
+    (@rest [$($attr:tt)*] [$($vis:tt)*] $G:ty, $first:ident; [$($skip:tt)*]) => {};
+}
+
+/// A macro that panics unless every given lazy value is initialized
+///
+/// Unlike asserting that each value is initialized separately, this checks all of them before
+/// panicking, and the message names every value that wasn't initialized. This is useful for
+/// checking that a warm-up routine initializes everything that later code relies on:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::panic;
+///
+/// lazy_mut! {
+///     static ROUTES: Vec<&'static str> = vec!["/"];
+///     static TEMPLATES: Vec<&'static str> = vec!["index.html"];
+///     static CACHE: Vec<u8> = Vec::new();
+/// }
+///
+/// fn warm_up() {
+///     ROUTES.init();
+/// }
+///
+/// # fn main() {
+/// warm_up();
+/// assert_initialized!(ROUTES);
+///
+/// let err = panic::catch_unwind(|| assert_initialized!(ROUTES, TEMPLATES, CACHE)).unwrap_err();
+/// assert_eq!(
+///     err.downcast_ref::<String>().unwrap(),
+///     "lazy values are not initialized: `TEMPLATES`, `CACHE`",
+/// );
+/// # }
+/// ```
+///
+/// Each value can be any expression with an `is_initialized` method, so it works with every lazy
+/// type. With the `registry` feature, `registry::check_initialized` does the same for lazy statics
+/// looked up by name.
+#[macro_export]
+macro_rules! assert_initialized {
+    ($($lazy:expr),+ $(,)?) => {
+        $crate::__assert_initialized(&[$((stringify!($lazy), $lazy.is_initialized())),+])
+    };
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_register {
+    ($N:ident) => {};
+}
+
+#[cfg(not(feature = "registry"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_register_static_mut {
+    ($N:ident) => {};
+}
+
+#[cfg(not(feature = "thread-affinity"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_claim {
+    ($N:ident) => {};
+}
+
+#[cfg(not(feature = "strict-statics"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_static_mut {
+    () => {};
+}
+
+#[cfg(feature = "strict-statics")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_static_mut {
+    () => {
+        compile_error!("`static mut` is forbidden by the `strict-statics` feature of `lazy_mut`, \
+            use `static` for a `SyncLazy`, or `SyncLazyMut` for a value that can be modified");
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_test_init {
+    ($N:ident: $T:ty = $e:expr) => {
+        #[cfg(test)]
+        #[allow(non_snake_case)]
+        mod $N {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[test]
+            fn initializer_does_not_panic() {
+                let _: $T = $e;
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ctor")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_eager {
+    ($N:ident) => {
+        const _: () = {
+            $crate::__ctor::declarative::ctor! {
+                #[ctor(unsafe)]
+                fn eager() {
+                    $crate::SyncLazy::init(&$N);
+                }
+            }
         };
-        lazy_mut!($($t)*);
     };
-    ($(#[$attr:meta])* pub ($($vis:tt)+) static mut $N:ident: $T:ty = $e:expr;
-        $($t:tt)*) => {
-        $(#[$attr])*
-        pub ($($vis)+) static mut $N: $crate::LazyMut<$T> = {
-            fn init() -> $T { $e }
-            $crate::LazyMut::Init(init)
-        };
-        lazy_mut!($($t)*);
+}
+
+#[cfg(not(feature = "ctor"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_eager {
+    ($N:ident) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_name {
+    ($N:ident) => {
+        $crate::__name(stringify!($N));
     };
 }
 
+/// An initializer that can be run once to obtain a value
+///
+/// This is implemented for every `FnOnce() -> T`, as well as for `extern "C" fn() -> T`.
+pub trait Initializer<T> {
+    /// Runs the initializer
+    fn run(self) -> T;
+}
+
+impl<T, F> Initializer<T> for F where F: FnOnce() -> T {
+    fn run(self) -> T {
+        self()
+    }
+}
+
+impl<T> Initializer<T> for extern "C" fn() -> T {
+    fn run(self) -> T {
+        self()
+    }
+}
+
 /// A mutable lazy value with either an initializer or a value
 ///
 /// See the module-level documentation for more information on usage.
@@ -157,93 +1360,742 @@ impl<T> LazyMut<T> {
     pub fn unwrap(self) -> T {
         use LazyMut::*;
         match self {
-            Init(init) => init(),
+            Init(init) => trace::init(init),
             Value(val) => val,
         }
     }
 
     /// Initializes the wrapped value if it is uninitialized
+    #[inline]
+    #[cfg_attr(feature = "thread-affinity", track_caller)]
     pub fn init(&mut self) -> &mut LazyMut<T> {
-        use LazyMut::*;
-        let new = match self {
-            &mut Init(init) => Value(init()),
-            other => return other,
-        };
-        *self = new;
+        #[cfg(feature = "thread-affinity")]
+        affinity::check(self);
+        if let LazyMut::Init(_) = *self {
+            self.initialize();
+        }
         self
     }
 
     /// Initializes the wrapped value, panicking if it was already initialized
+    #[track_caller]
     pub fn init_once(&mut self) -> &mut LazyMut<T> {
-        use LazyMut::*;
-        let new = match self {
-            &mut Init(init) => Value(init()),
-            _ => panic!("call to `init_once` on already initialized value"),
-        };
-        *self = new;
+        match *self {
+            LazyMut::Init(_) => self.initialize(),
+            _ => already_initialized(None),
+        }
         self
     }
 
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    ///
+    /// This is for code that treats a second initialization as a recoverable error rather than a
+    /// bug, such as configuration that may be loaded from more than one place:
+    ///
+    /// ```
+    /// use lazy_mut::LazyMut;
+    ///
+    /// let mut config = LazyMut::Init(|| "from defaults".to_string());
+    ///
+    /// assert_eq!(config.try_init_once().unwrap(), "from defaults");
+    /// assert!(config.try_init_once().is_err());
+    /// ```
+    #[track_caller]
+    pub fn try_init_once(&mut self) -> Result<&mut T, AlreadyInitialized> {
+        match *self {
+            LazyMut::Init(_) => self.initialize(),
+            LazyMut::Value(_) => return Err(AlreadyInitialized { location: None }),
+        }
+        match LazyMut::get_mut(self) {
+            Some(val) => Ok(val),
+            None => unreachable!(),
+        }
+    }
+
+    /// Runs the initializer, kept out of line so that accesses stay small
+    #[cold]
+    #[inline(never)]
+    fn initialize(&mut self) {
+        use LazyMut::*;
+        if let Init(init) = *self {
+            *self = Value(trace::init(init));
+        }
+    }
+
+    /// Runs the initializer and returns its result, leaving the value uninitialized
+    ///
+    /// This is meant for checking ahead of time that the initializer works, such as validating
+    /// configuration on startup, without changing when the value is actually initialized. The
+    /// initializer is run again when the value is initialized, so it should have no side effects
+    /// that matter. This panics if the value has already been initialized, since its initializer
+    /// is gone.
+    ///
+    /// ```
+    /// use lazy_mut::LazyMut;
+    ///
+    /// let config = LazyMut::Init(|| "port = 80".to_string());
+    ///
+    /// assert_eq!(config.peek_init(), "port = 80");
+    /// assert!(!config.is_initialized());
+    /// ```
+    #[track_caller]
+    pub fn peek_init(&self) -> T {
+        match *self {
+            LazyMut::Init(init) => init(),
+            LazyMut::Value(_) => already_peeked(),
+        }
+    }
+
+    /// Gets a mutable reference to the value, initializing it with `f` if it is uninitialized
+    ///
+    /// The stored initializer is not run. If `f` returns an error, the value is left
+    /// uninitialized and the error is returned.
+    pub fn get_or_try_init<E, F>(&mut self, f: F) -> Result<&mut T, E>
+        where F: FnOnce() -> Result<T, E>
+    {
+        use LazyMut::*;
+        if let Init(_) = *self {
+            *self = Value(f()?);
+        }
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => Ok(val),
+        }
+    }
+
+    /// Replaces the value with the result of `f`, which is given the value by move, initializing
+    /// it first if needed
+    ///
+    /// This doesn't need a placeholder value of type `T` while `f` runs. Since the initializer is
+    /// a `fn() -> T`, it can't be combined with `f`, so an uninitialized value is initialized
+    /// before `f` is applied:
+    ///
+    /// ```
+    /// use lazy_mut::LazyMut;
+    ///
+    /// let mut words = LazyMut::Init(|| vec!["lazy", "values"]);
+    /// words.map_value_in_place(|words| words.into_iter().rev().collect());
+    ///
+    /// assert_eq!(*words, ["values", "lazy"]);
+    /// ```
+    ///
+    /// If `f` panics, the value is lost, and any later attempt to initialize it panics as well.
+    #[track_caller]
+    pub fn map_value_in_place<F>(&mut self, f: F) -> &mut T where F: FnOnce(T) -> T {
+        use LazyMut::*;
+        self.init();
+        if let Value(val) = mem::replace(self, Init(lost_in_map::<T>)) {
+            *self = Value(f(val));
+        }
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => val,
+        }
+    }
+
     /// Tries to get a reference to the value, returns `None` if the value is uninitialized
     ///
     /// Uses associated function syntax (`LazyMut::get(&VAL)`)
+    #[inline]
+    #[cfg_attr(feature = "thread-affinity", track_caller)]
     pub fn get(this: &LazyMut<T>) -> Option<&T> {
+        #[cfg(feature = "thread-affinity")]
+        affinity::check(this);
         use LazyMut::*;
-        match this {
-            &Init(_) => None,
-            &Value(ref val) => Some(val),
+        match *this {
+            Init(_) => None,
+            Value(ref val) => Some(val),
         }
     }
 
     /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
     ///
     /// Uses associated function syntax (`LazyMut::get_mut(&mut VAL)`)
+    #[inline]
+    #[cfg_attr(feature = "thread-affinity", track_caller)]
     pub fn get_mut(this: &mut LazyMut<T>) -> Option<&mut T> {
+        #[cfg(feature = "thread-affinity")]
+        affinity::check(this);
         use LazyMut::*;
+        match *this {
+            Init(_) => None,
+            Value(ref mut val) => Some(val),
+        }
+    }
+
+    /// Borrows the value as a `LazyRef`, which can initialize it but not replace it
+    ///
+    /// Uses associated function syntax (`LazyMut::by_ref(&mut VAL)`)
+    #[inline]
+    pub fn by_ref(this: &mut LazyMut<T>) -> LazyRef<'_, T> {
+        LazyRef::new(this)
+    }
+
+    /// Returns a view of the part of the value picked out by `f`, or an uninitialized view if the
+    /// value is uninitialized
+    ///
+    /// See `LazyRefProj` for more information.
+    ///
+    /// Uses associated function syntax (`LazyMut::map_ref(&VAL, f)`)
+    pub fn map_ref<U: ?Sized>(this: &LazyMut<T>, f: fn(&T) -> &U) -> LazyRefProj<'_, U> {
+        LazyRefProj::new(this, f)
+    }
+
+    /// Splits the value into its initializer and a `Slot` holding the value, if there is one
+    ///
+    /// The initializer is `None` if the value was already initialized, in which case the slot is
+    /// already filled. See `Slot` for how to put them back together.
+    ///
+    /// Uses associated function syntax (`LazyMut::split_init(VAL)`)
+    pub fn split_init(this: LazyMut<T>) -> SplitInit<T> {
+        match this {
+            LazyMut::Init(init) => (Some(init), Slot::new()),
+            LazyMut::Value(val) => (None, Slot::with_value(val)),
+        }
+    }
+
+    /// Converts the value into a thread-safe `SyncLazy`, which keeps the value if it is
+    /// initialized and the initializer otherwise
+    ///
+    /// Uses associated function syntax (`LazyMut::upgrade(VAL)`)
+    #[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+    pub fn upgrade(this: LazyMut<T>) -> SyncLazy<T> {
         match this {
-            &mut Init(_) => None,
-            &mut Value(ref mut val) => Some(val),
+            LazyMut::Init(init) => SyncLazy::new(init),
+            LazyMut::Value(val) => SyncLazy::with_value(val),
         }
     }
 
     /// Returns `true` if the wrapped value has been initialized
+    #[inline]
     pub fn is_initialized(&self) -> bool {
         use LazyMut::*;
-        match self {
-            &Init(_) => false,
-            &Value(_) => true,
+        match *self {
+            Init(_) => false,
+            Value(_) => true,
+        }
+    }
+
+    /// Returns `true` if the wrapped value hasn't been initialized yet
+    ///
+    /// This is meant for `#[serde(skip_serializing_if = "LazyMut::is_uninit")]`.
+    ///
+    /// Uses associated function syntax (`LazyMut::is_uninit(&VAL)`)
+    #[inline]
+    pub fn is_uninit(this: &LazyMut<T>) -> bool {
+        !this.is_initialized()
+    }
+}
+
+/// Lazy values are often kept in a collection, such as a pool of workers that are each built the
+/// first time they are needed. These functions work on a whole slice of them at once:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+///
+/// let mut workers = vec![LazyMut::Init(|| "worker".to_string()); 4];
+/// workers[1].init();
+/// workers[3].init();
+///
+/// assert_eq!(LazyMut::initialized_count(&workers), 2);
+/// assert_eq!(LazyMut::initialized(&workers).count(), 2);
+///
+/// LazyMut::init_all(&mut workers);
+/// assert_eq!(LazyMut::initialized_count(&workers), 4);
+/// ```
+impl<T> LazyMut<T> {
+    /// Initializes every value in a slice that is uninitialized, in order
+    ///
+    /// Uses associated function syntax (`LazyMut::init_all(&mut VALS)`)
+    pub fn init_all(lazies: &mut [LazyMut<T>]) {
+        for lazy in lazies {
+            lazy.init();
+        }
+    }
+
+    /// Returns the number of values in a slice that have been initialized
+    ///
+    /// Uses associated function syntax (`LazyMut::initialized_count(&VALS)`)
+    pub fn initialized_count(lazies: &[LazyMut<T>]) -> usize {
+        lazies.iter().filter(|lazy| lazy.is_initialized()).count()
+    }
+
+    /// Returns an iterator over the values in a slice that have been initialized, skipping the rest
+    ///
+    /// Uses associated function syntax (`LazyMut::initialized(&VALS)`)
+    pub fn initialized(lazies: &[LazyMut<T>]) -> impl Iterator<Item = &T> {
+        lazies.iter().filter_map(LazyMut::get)
+    }
+
+    /// Returns an iterator over mutable references to the values in a slice that have been
+    /// initialized, skipping the rest
+    ///
+    /// Uses associated function syntax (`LazyMut::initialized_mut(&mut VALS)`)
+    pub fn initialized_mut(lazies: &mut [LazyMut<T>]) -> impl Iterator<Item = &mut T> {
+        lazies.iter_mut().filter_map(LazyMut::get_mut)
+    }
+}
+
+/// A `LazyMut<Cow<'static, B>>` can start out borrowing a `'static` value, so that reading it
+/// never allocates, and only make an owned copy when it is first modified. This suits strings
+/// with a literal default that are rarely changed:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+///
+/// let mut title = LazyMut::from_static_str("untitled");
+/// assert_eq!(*title, "untitled");
+/// assert!(LazyMut::is_borrowed(&title));
+///
+/// LazyMut::to_mut(&mut title).push_str(" (1)");
+/// assert_eq!(*title, "untitled (1)");
+/// assert!(!LazyMut::is_borrowed(&title));
+/// ```
+///
+/// Only available with the `alloc` feature.
+#[cfg(feature = "alloc")]
+impl<B> LazyMut<Cow<'static, B>> where B: ToOwned + ?Sized + 'static {
+    /// Creates a new initialized value that borrows `val`
+    pub const fn from_static(val: &'static B) -> LazyMut<Cow<'static, B>> {
+        LazyMut::Value(Cow::Borrowed(val))
+    }
+
+    /// Initializes the value if needed and returns a mutable reference to an owned copy of it,
+    /// which is made if the value is still borrowed
+    ///
+    /// Uses associated function syntax (`LazyMut::to_mut(&mut VAL)`)
+    #[track_caller]
+    pub fn to_mut<'a>(this: &'a mut LazyMut<Cow<'static, B>>) -> &'a mut B::Owned {
+        match LazyMut::get_mut(this.init()) {
+            Some(val) => val.to_mut(),
+            None => unreachable!(),
         }
     }
+
+    /// Returns `true` if the value is initialized and still borrows a `'static` value
+    ///
+    /// Uses associated function syntax (`LazyMut::is_borrowed(&VAL)`)
+    pub fn is_borrowed(this: &LazyMut<Cow<'static, B>>) -> bool {
+        matches!(LazyMut::get(this), Some(Cow::Borrowed(_)))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl LazyMut<Cow<'static, str>> {
+    /// Creates a new initialized string that borrows `s` until it is modified
+    pub const fn from_static_str(s: &'static str) -> LazyMut<Cow<'static, str>> {
+        LazyMut::from_static(s)
+    }
+}
+
+/// A `static mut` is best accessed through a raw pointer from `ptr::addr_of_mut!`, since any
+/// reference to it must not overlap with another access. These functions work on raw pointers to
+/// a `LazyMut` without creating a reference to it, so that the value can be read and written
+/// through raw pointers as well:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+/// use std::ptr;
+///
+/// static mut COUNTER: LazyMut<u64> = LazyMut::Init(|| 10);
+///
+/// unsafe {
+///     let lazy = ptr::addr_of_mut!(COUNTER);
+///     assert!(LazyMut::raw_get(lazy).is_null());
+///
+///     let counter = LazyMut::raw_init(lazy);
+///     *counter += 1;
+///     assert_eq!(*LazyMut::raw_get(lazy), 11);
+/// }
+/// ```
+///
+/// Finding where the value is stored borrows the value (but not the rest of the `LazyMut`) for an
+/// instant, so no reference to the value may be alive during a call.
+impl<T> LazyMut<T> {
+    /// Returns a pointer to the value, or a null pointer if it is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyMut::raw_get(ptr::addr_of!(VAL))`)
+    ///
+    /// # Safety
+    ///
+    /// `this` must be valid for reads, and no mutable reference to the value may be alive.
+    #[inline]
+    pub unsafe fn raw_get(this: *const LazyMut<T>) -> *const T {
+        match *this {
+            LazyMut::Init(_) => ptr::null(),
+            LazyMut::Value(ref val) => val,
+        }
+    }
+
+    /// Returns a mutable pointer to the value, or a null pointer if it is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyMut::raw_get_mut(ptr::addr_of_mut!(VAL))`)
+    ///
+    /// # Safety
+    ///
+    /// `this` must be valid for reads and writes, and no reference to the value may be alive.
+    #[inline]
+    pub unsafe fn raw_get_mut(this: *mut LazyMut<T>) -> *mut T {
+        match *this {
+            LazyMut::Init(_) => ptr::null_mut(),
+            LazyMut::Value(ref mut val) => val,
+        }
+    }
+
+    /// Initializes the value if it is uninitialized, returning a mutable pointer to it
+    ///
+    /// Uses associated function syntax (`LazyMut::raw_init(ptr::addr_of_mut!(VAL))`)
+    ///
+    /// # Safety
+    ///
+    /// `this` must be valid for reads and writes, and no reference to the value may be alive. The
+    /// initializer must not access the same `LazyMut`.
+    pub unsafe fn raw_init(this: *mut LazyMut<T>) -> *mut T {
+        if let LazyMut::Init(init) = *this {
+            let val = trace::init(init);
+            ptr::write(this, LazyMut::Value(val));
+        }
+        LazyMut::raw_get_mut(this)
+    }
+}
+
+/// Pinning is structural for the value of a `LazyMut`: once a pinned `LazyMut` has been
+/// initialized, its value is never moved until it is dropped, so a `Pin<&mut LazyMut<T>>` can be
+/// projected to a `Pin<&mut T>`. Initializing a pinned `LazyMut` only replaces the initializer,
+/// since there is no value to move yet. This lets a value that must stay pinned, such as a future,
+/// be created lazily in a field of a pinned struct:
+///
+/// ```
+/// use lazy_mut::LazyMut;
+/// use std::marker::PhantomPinned;
+/// use std::pin::{pin, Pin};
+///
+/// struct Counter {
+///     count: u32,
+///     _pinned: PhantomPinned,
+/// }
+///
+/// impl Counter {
+///     fn new() -> Counter {
+///         Counter { count: 0, _pinned: PhantomPinned }
+///     }
+///
+///     fn bump(self: Pin<&mut Counter>) -> u32 {
+///         let this = unsafe { self.get_unchecked_mut() };
+///         this.count += 1;
+///         this.count
+///     }
+/// }
+///
+/// let mut lazy = pin!(LazyMut::Init(Counter::new));
+/// assert!(LazyMut::as_pin_ref(lazy.as_ref()).is_none());
+///
+/// assert_eq!(LazyMut::init_pin(lazy.as_mut()).bump(), 1);
+/// assert_eq!(LazyMut::as_pin_mut(lazy.as_mut()).unwrap().bump(), 2);
+/// ```
+impl<T> LazyMut<T> {
+    /// Initializes the wrapped value if it is uninitialized, returning a pinned mutable reference
+    /// to it
+    ///
+    /// Uses associated function syntax (`LazyMut::init_pin(VAL)`)
+    #[inline]
+    pub fn init_pin(this: Pin<&mut LazyMut<T>>) -> Pin<&mut T> {
+        // The value is initialized in place and never moved out of a pinned `LazyMut`
+        unsafe {
+            let this = this.get_unchecked_mut();
+            if let LazyMut::Init(_) = *this {
+                this.initialize();
+            }
+            match LazyMut::get_mut(this) {
+                Some(val) => Pin::new_unchecked(val),
+                None => unreachable!(),
+            }
+        }
+    }
+
+    /// Tries to get a pinned reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyMut::as_pin_ref(VAL)`)
+    #[inline]
+    pub fn as_pin_ref(this: Pin<&LazyMut<T>>) -> Option<Pin<&T>> {
+        // Pinning is structural for the value, which is never moved out of a pinned `LazyMut`
+        unsafe { LazyMut::get(this.get_ref()).map(|val| Pin::new_unchecked(val)) }
+    }
+
+    /// Tries to get a pinned mutable reference to the value, returns `None` if the value is
+    /// uninitialized
+    ///
+    /// Uses associated function syntax (`LazyMut::as_pin_mut(VAL)`)
+    #[inline]
+    pub fn as_pin_mut(this: Pin<&mut LazyMut<T>>) -> Option<Pin<&mut T>> {
+        // Pinning is structural for the value, which is never moved out of a pinned `LazyMut`
+        unsafe { LazyMut::get_mut(this.get_unchecked_mut()).map(|val| Pin::new_unchecked(val)) }
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl<T> LazyMut<T> where T: Sync + 'static {
+    /// Registers a function that supplies a value when this is dereferenced immutably before it
+    /// is initialized, instead of panicking
+    ///
+    /// The value stays uninitialized, so the function is called again on every such access, and
+    /// the stored initializer still runs the first time the value is initialized. This lets a
+    /// program fall back to default settings when a plugin hasn't loaded its own yet:
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate lazy_mut;
+    ///
+    /// use lazy_mut::{LazyMut, SyncLazy};
+    /// use std::ptr;
+    ///
+    /// lazy_mut! {
+    ///     static mut SETTINGS: Vec<&'static str> = vec!["plugin"];
+    /// }
+    ///
+    /// static DEFAULTS: SyncLazy<Vec<&str>> = SyncLazy::new(|| vec!["default"]);
+    ///
+    /// # fn main() {
+    /// unsafe {
+    ///     let settings: *mut LazyMut<Vec<&str>> = ptr::addr_of_mut!(SETTINGS);
+    ///     (*settings).on_uninit(|| &*DEFAULTS);
+    ///     assert_eq!(**settings, ["default"]);
+    ///
+    ///     (*settings).init();
+    ///     assert_eq!(**settings, ["plugin"]);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Only available with the `hooks` feature.
+    pub fn on_uninit<H>(&'static self, fallback: H)
+        where H: Fn() -> &'static T + Send + Sync + 'static
+    {
+        let fallback = move || fallback() as *const T as *const ();
+        hooks::register_fallback(hooks::key(self), core::any::type_name::<T>(), Arc::new(fallback));
+    }
 }
 
 impl<T> Deref for LazyMut<T> {
     type Target = T;
+    #[inline]
+    #[track_caller]
     fn deref(&self) -> &T {
+        #[cfg(feature = "thread-affinity")]
+        affinity::check(self);
         use LazyMut::*;
-        match self {
-            &Init(_) => panic!("cannot dereference uninitialized value"),
-            &Value(ref val) => val,
+        match *self {
+            #[cfg(feature = "hooks")]
+            Init(_) => fallback(self),
+            #[cfg(not(feature = "hooks"))]
+            Init(_) => uninitialized_lazy_mut(self),
+            Value(ref val) => val,
         }
     }
 }
 
 impl<T> DerefMut for LazyMut<T> {
+    #[inline]
+    #[cfg_attr(feature = "thread-affinity", track_caller)]
     fn deref_mut(&mut self) -> &mut T {
+        if !self.is_initialized() {
+            trace::implicit_init::<T>();
+        }
         self.init();
         use LazyMut::*;
-        match self {
-            &mut Init(_) => unreachable!(),
-            &mut Value(ref mut val) => val,
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => val,
         }
     }
 }
 
+#[cfg(any(feature = "std", feature = "spin", feature = "critical-section"))]
+impl<T> From<LazyMut<T>> for SyncLazy<T> {
+    fn from(lazy: LazyMut<T>) -> SyncLazy<T> {
+        LazyMut::upgrade(lazy)
+    }
+}
+
 impl<T> Display for LazyMut<T> where T: Display {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use LazyMut::*;
-        match self {
-            &Init(_) => write!(f, "{{uninitialized}}"),
-            &Value(ref val) => val.fmt(f),
+        match *self {
+            Init(_) => write!(f, "{{uninitialized}}"),
+            Value(ref val) => val.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for LazyMut<T> where T: serde::Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
+        use LazyMut::*;
+        match *self {
+            Init(_) => {
+                Err(serde::ser::Error::custom("can't serialize an uninitialized lazy value"))
+            }
+            Value(ref val) => val.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for LazyMut<T> where T: serde::Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<LazyMut<T>, D::Error>
+        where D: serde::Deserializer<'de>
+    {
+        T::deserialize(deserializer).map(LazyMut::Value)
+    }
+}
+
+#[cfg(feature = "embedded-logging")]
+impl<T> defmt::Format for LazyMut<T> where T: defmt::Format {
+    fn format(&self, f: defmt::Formatter) {
+        use LazyMut::*;
+        match *self {
+            Init(_) => defmt::write!(f, "{{uninitialized}}"),
+            Value(ref val) => val.format(f),
+        }
+    }
+}
+
+/// The initializer left behind by `LazyMut::map_value_in_place` while its function runs
+#[cold]
+#[inline(never)]
+fn lost_in_map<T>() -> T {
+    panic!("lazy value was lost because a function mapping it panicked")
+}
+
+/// Reports a call to `init_once` on a value that was already initialized
+///
+/// In debug builds, types that track where they were initialized include that location.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn already_initialized(location: Option<&'static Location<'static>>) -> ! {
+    match location {
+        Some(location) => {
+            panic!("call to `init_once` on value already initialized at {}", location)
+        }
+        None => panic!("call to `init_once` on already initialized value"),
+    }
+}
+
+/// Panics because `peek_init` was called on a value that no longer has its initializer
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn already_peeked() -> ! {
+    panic!("lazy value already initialized, so its initializer can't be peeked")
+}
+
+/// An error from calling `try_init_once` on a value that was already initialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlreadyInitialized {
+    location: Option<&'static Location<'static>>,
+}
+
+impl AlreadyInitialized {
+    /// Returns the location of the call that initialized the value, for types that track it
+    ///
+    /// This is only tracked in debug builds, so it always returns `None` in release builds.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        self.location
+    }
+}
+
+impl Display for AlreadyInitialized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "value already initialized at {}", location),
+            None => write!(f, "value already initialized"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AlreadyInitialized {}
+
+/// Returns the value supplied by the fallback of an uninitialized `LazyMut`, or reports the access
+/// if it doesn't have one
+#[cfg(feature = "hooks")]
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn fallback<T>(lazy: &LazyMut<T>) -> &T {
+    match hooks::fallback(hooks::key(lazy), core::any::type_name::<T>()) {
+        // The fallback was registered on this value, so it returns a `&'static T`
+        Some(val) => unsafe { &*(val as *const T) },
+        None => uninitialized_lazy_mut(lazy),
+    }
+}
+
+/// Panics with the names of the values that aren't initialized, if there are any
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_initialized(values: &[(&str, bool)]) {
+    struct Missing<'a>(&'a [(&'a str, bool)]);
+
+    impl<'a> Display for Missing<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let mut names = self.0.iter().filter(|value| !value.1).map(|value| value.0);
+            if let Some(name) = names.next() {
+                write!(f, "`{}`", name)?;
+            }
+            for name in names {
+                write!(f, ", `{}`", name)?;
+            }
+            Ok(())
+        }
+    }
+
+    if values.iter().any(|value| !value.1) {
+        panic!("lazy values are not initialized: {}", Missing(values));
+    }
+}
+
+/// Reports an access to an uninitialized value of type `T`, naming the value if its name is known
+///
+/// With the `abort-on-uninit` feature, this aborts the process instead of panicking.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn uninitialized<T: ?Sized>(name: Option<&'static str>) -> ! {
+    #[cfg(feature = "hooks")]
+    hooks::uninit(core::any::type_name::<T>());
+    #[cfg(feature = "abort-on-uninit")]
+    {
+        match name {
+            Some(name) => eprintln!(
+                "cannot dereference uninitialized value `{}` of type `{}` at {}",
+                name, core::any::type_name::<T>(), Location::caller()),
+            None => eprintln!("cannot dereference uninitialized value of type `{}` at {}",
+                core::any::type_name::<T>(), Location::caller()),
         }
+        std::process::abort()
+    }
+    #[cfg(not(feature = "abort-on-uninit"))]
+    match name {
+        Some(name) => panic!("cannot dereference uninitialized value `{}` of type `{}`",
+            name, core::any::type_name::<T>()),
+        None => panic!("cannot dereference uninitialized value of type `{}`",
+            core::any::type_name::<T>()),
     }
-}
\ No newline at end of file
+}
+
+/// Reports an access to an uninitialized `LazyMut`, naming it if it is a `static mut` declared
+/// with the macro and the `registry` feature is enabled
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn uninitialized_lazy_mut<T>(lazy: &LazyMut<T>) -> ! {
+    #[cfg(feature = "registry")]
+    let name = registry::static_mut_name(lazy as *const LazyMut<T> as *const ());
+    #[cfg(not(feature = "registry"))]
+    let name = {
+        let _ = lazy;
+        None
+    };
+    uninitialized::<T>(name)
+}
@@ -0,0 +1,37 @@
+use core::panic::Location;
+
+/// The location of the call that initialized a value, which is only tracked in debug builds
+///
+/// In release builds, this is zero-sized and `get` always returns `None`.
+#[derive(Clone, Copy)]
+pub struct InitLocation {
+    #[cfg(debug_assertions)]
+    location: Option<&'static Location<'static>>,
+}
+
+impl InitLocation {
+    pub const fn new() -> InitLocation {
+        InitLocation {
+            #[cfg(debug_assertions)]
+            location: None,
+        }
+    }
+
+    /// Records the location of the caller
+    #[inline]
+    #[cfg_attr(debug_assertions, track_caller)]
+    pub fn set(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            self.location = Some(Location::caller());
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> Option<&'static Location<'static>> {
+        #[cfg(debug_assertions)]
+        return self.location;
+        #[cfg(not(debug_assertions))]
+        return None;
+    }
+}
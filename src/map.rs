@@ -0,0 +1,148 @@
+//! A map whose entries are initialized on first access
+//!
+//! See `LazyMap` for more information.
+
+use core::hash::Hash;
+use core::fmt::{self, Debug};
+use std::collections::hash_map::{self, HashMap};
+use LazyField;
+
+/// A map whose entries are initialized on first access
+///
+/// Every key is present in a `LazyMap`, but its value isn't created until it is first accessed
+/// through `LazyMap::get` or `LazyMap::get_mut`, which run the initializer with the key. The map
+/// itself isn't allocated until the first entry is created, so a `LazyMap` can be created in a
+/// constant expression. Iteration only visits the entries that have been initialized.
+///
+/// ```
+/// use lazy_mut::map::LazyMap;
+///
+/// let mut lengths = LazyMap::new(|word: &&str| word.len());
+///
+/// assert_eq!(*lengths.get(&"lazy"), 4);
+/// *lengths.get_mut(&"map") += 10;
+///
+/// assert!(lengths.is_initialized(&"map"));
+/// assert!(!lengths.is_initialized(&"other"));
+///
+/// let mut entries: Vec<_> = lengths.iter().collect();
+/// entries.sort();
+/// assert_eq!(entries, [(&"lazy", &4), (&"map", &13)]);
+/// ```
+pub struct LazyMap<K, V> {
+    init: fn(&K) -> V,
+    map: LazyField<HashMap<K, V>>,
+}
+
+impl<K, V> LazyMap<K, V> where K: Eq + Hash + Clone {
+    /// Creates a new map with an initializer for its entries
+    pub const fn new(init: fn(&K) -> V) -> LazyMap<K, V> {
+        LazyMap {
+            init,
+            map: LazyField::new(HashMap::new),
+        }
+    }
+
+    /// Returns a reference to the value for a key, initializing it if needed
+    pub fn get(&mut self, key: &K) -> &V {
+        self.get_mut(key)
+    }
+
+    /// Returns a mutable reference to the value for a key, initializing it if needed
+    ///
+    /// The key is cloned when the value is initialized.
+    pub fn get_mut(&mut self, key: &K) -> &mut V {
+        let map = LazyField::force(&mut self.map);
+        if !map.contains_key(key) {
+            map.insert(key.clone(), (self.init)(key));
+        }
+        match map.get_mut(key) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Tries to get a reference to the value for a key, returns `None` if it is uninitialized
+    pub fn try_get(&self, key: &K) -> Option<&V> {
+        LazyField::get(&self.map).and_then(|map| map.get(key))
+    }
+
+    /// Tries to get a mutable reference the value for a key, returns `None` if it is uninitialized
+    pub fn try_get_mut(&mut self, key: &K) -> Option<&mut V> {
+        LazyField::get_mut(&mut self.map).and_then(|map| map.get_mut(key))
+    }
+
+    /// Returns `true` if the value for a key has been initialized
+    pub fn is_initialized(&self, key: &K) -> bool {
+        self.try_get(key).is_some()
+    }
+
+    /// Removes the value for a key, so that it will be initialized again on next access
+    pub fn reset(&mut self, key: &K) -> Option<V> {
+        LazyField::get_mut(&mut self.map).and_then(|map| map.remove(key))
+    }
+
+    /// Returns the number of initialized entries
+    pub fn len(&self) -> usize {
+        LazyField::get(&self.map).map_or(0, HashMap::len)
+    }
+
+    /// Returns `true` if no entries have been initialized
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K, V> LazyMap<K, V> {
+    /// Returns an iterator over the initialized entries
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(LazyField::get(&self.map).map(HashMap::iter))
+    }
+
+    /// Returns an iterator over the initialized entries, with mutable references to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(LazyField::get_mut(&mut self.map).map(HashMap::iter_mut))
+    }
+}
+
+impl<K, V> Debug for LazyMap<K, V> where K: Debug, V: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a LazyMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut LazyMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the initialized entries of a `LazyMap`
+pub struct Iter<'a, K, V>(Option<hash_map::Iter<'a, K, V>>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.0.as_mut().and_then(Iterator::next)
+    }
+}
+
+/// A mutable iterator over the initialized entries of a `LazyMap`
+pub struct IterMut<'a, K, V>(Option<hash_map::IterMut<'a, K, V>>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.0.as_mut().and_then(Iterator::next)
+    }
+}
@@ -0,0 +1,147 @@
+use core::hash::Hash;
+use core::fmt::{self, Debug};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use {LazyField, SyncLazy};
+
+/// A function that caches its result for each argument it is called with
+///
+/// The cache is a `HashMap` that isn't allocated until the first call, so a `Memo` can be created
+/// in a constant expression. Calling the function takes `&mut self` and returns a reference into
+/// the cache. Use `SyncMemo` for a memoized function that can be shared between threads.
+///
+/// ```
+/// use lazy_mut::Memo;
+///
+/// fn collatz_len(mut n: u64) -> u32 {
+///     let mut len = 1;
+///     while n != 1 {
+///         n = if n % 2 == 0 { n / 2 } else { 3 * n + 1 };
+///         len += 1;
+///     }
+///     len
+/// }
+///
+/// let mut memo = Memo::new(collatz_len);
+///
+/// assert!(!memo.is_cached(&27));
+/// assert_eq!(*memo.get(27), 112);
+/// assert!(memo.is_cached(&27));
+/// ```
+pub struct Memo<A, R> {
+    func: fn(A) -> R,
+    cache: LazyField<HashMap<A, R>>,
+}
+
+impl<A, R> Memo<A, R> where A: Eq + Hash + Clone {
+    /// Creates a new memoized function with an empty cache
+    pub const fn new(func: fn(A) -> R) -> Memo<A, R> {
+        Memo {
+            func,
+            cache: LazyField::new(HashMap::new),
+        }
+    }
+
+    /// Returns the result of the function for an argument, calling it if it isn't cached yet
+    pub fn get(&mut self, arg: A) -> &R {
+        let func = self.func;
+        self.cache.entry(arg).or_insert_with_key(|arg| func(arg.clone()))
+    }
+
+    /// Returns `true` if the result for an argument is cached
+    pub fn is_cached(&self, arg: &A) -> bool {
+        LazyField::get(&self.cache).is_some_and(|cache| cache.contains_key(arg))
+    }
+
+    /// Removes every cached result
+    pub fn clear(&mut self) {
+        if let Some(cache) = LazyField::get_mut(&mut self.cache) {
+            cache.clear();
+        }
+    }
+}
+
+impl<A, R> Debug for Memo<A, R> where A: Debug, R: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut map = f.debug_map();
+        if let Some(cache) = LazyField::get(&self.cache) {
+            map.entries(cache);
+        }
+        map.finish()
+    }
+}
+
+/// A thread-safe function that caches its result for each argument it is called with
+///
+/// The cache is stored behind a lock in a `SyncLazy`, so a `SyncMemo` can be used as a static.
+/// The lock is not held while the function runs, which lets the function call the `SyncMemo` it
+/// is stored in recursively. If multiple threads call it with the same uncached argument at once,
+/// the function may run more than once, but only the first result is kept. Results are cloned out
+/// of the cache.
+///
+/// ```
+/// use lazy_mut::SyncMemo;
+///
+/// static FIB: SyncMemo<u64, u64> = SyncMemo::new(fib);
+///
+/// fn fib(n: u64) -> u64 {
+///     if n < 2 { n } else { FIB.get(n - 1) + FIB.get(n - 2) }
+/// }
+///
+/// assert_eq!(FIB.get(80), 23_416_728_348_467_685);
+/// ```
+pub struct SyncMemo<A, R> {
+    func: fn(A) -> R,
+    cache: SyncLazy<Mutex<HashMap<A, R>>>,
+}
+
+impl<A, R> SyncMemo<A, R> where A: Eq + Hash + Clone {
+    /// Creates a new memoized function with an empty cache
+    pub const fn new(func: fn(A) -> R) -> SyncMemo<A, R> {
+        SyncMemo {
+            func,
+            cache: SyncLazy::new(new_cache::<A, R>),
+        }
+    }
+
+    /// Returns the result of the function for an argument, calling it if it isn't cached yet
+    pub fn get(&self, arg: A) -> R where R: Clone {
+        if let Some(val) = self.lock().get(&arg) {
+            return val.clone();
+        }
+        let val = (self.func)(arg.clone());
+        self.lock().entry(arg).or_insert(val).clone()
+    }
+
+    /// Returns `true` if the result for an argument is cached
+    pub fn is_cached(&self, arg: &A) -> bool {
+        SyncLazy::get(&self.cache).is_some_and(|cache| {
+            cache.lock().unwrap_or_else(PoisonError::into_inner).contains_key(arg)
+        })
+    }
+
+    /// Removes every cached result
+    pub fn clear(&self) {
+        if SyncLazy::get(&self.cache).is_some() {
+            self.lock().clear();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<A, R>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<A, R> Debug for SyncMemo<A, R> where A: Debug, R: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut map = f.debug_map();
+        if let Some(cache) = SyncLazy::get(&self.cache) {
+            map.entries(&*cache.lock().unwrap_or_else(PoisonError::into_inner));
+        }
+        map.finish()
+    }
+}
+
+fn new_cache<A, R>() -> Mutex<HashMap<A, R>> {
+    Mutex::new(HashMap::new())
+}
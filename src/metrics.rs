@@ -0,0 +1,63 @@
+//! Metrics recorded while initializing thread-safe lazy values
+//!
+//! With the `metrics` feature enabled, every `SyncLazy` records when it was first accessed and how
+//! long its initializer took to run. Together with the registry, this makes it easy to find which
+//! lazy statics slow down the first request to a server:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::SyncLazy;
+//! use std::time::Duration;
+//!
+//! lazy_mut! {
+//!     static TABLE: Vec<u64> = {
+//!         std::thread::sleep(Duration::from_millis(10));
+//!         (0..1024).collect()
+//!     };
+//! }
+//!
+//! # fn main() {
+//! assert!(SyncLazy::metrics(&TABLE).is_none());
+//! assert_eq!(TABLE.len(), 1024);
+//!
+//! let metrics = SyncLazy::metrics(&TABLE).unwrap();
+//! assert!(metrics.duration() >= Duration::from_millis(10));
+//!
+//! for entry in lazy_mut::registry::entries() {
+//!     if let Some(metrics) = entry.metrics() {
+//!         println!("{} took {:?}", entry.name(), metrics.duration());
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::time::{Duration, Instant, SystemTime};
+
+/// The time at which a lazy value was first accessed and how long it took to initialize
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InitMetrics {
+    first_access: SystemTime,
+    duration: Duration,
+}
+
+impl InitMetrics {
+    /// Returns the time at which the initializer started running
+    pub fn first_access(&self) -> SystemTime {
+        self.first_access
+    }
+
+    /// Returns how long the initializer took to run
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+#[doc(hidden)]
+pub fn measure<R, F>(f: F) -> (R, InitMetrics) where F: FnOnce() -> R {
+    let first_access = SystemTime::now();
+    let start = Instant::now();
+    let val = f();
+    (val, InitMetrics { first_access, duration: start.elapsed() })
+}
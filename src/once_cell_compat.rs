@@ -0,0 +1,200 @@
+//! Compatibility with the lazy types of the `once_cell` crate
+//!
+//! Each lazy type gets the associated functions that `once_cell::sync::Lazy` and
+//! `once_cell::unsync::Lazy` have, so that code written against either crate can switch to the
+//! other by changing the type names. `SyncLazy` corresponds to `sync::Lazy`, and `CompactLazy` and
+//! `LazyMut` correspond to `unsync::Lazy`. Since the unsynchronized types can't initialize
+//! through a shared reference, their versions of `force` and `get_or_init` take `&mut`.
+//!
+//! Conversions keep the state of the value. Converting from `once_cell` never has to allocate,
+//! but converting into it boxes the initializer, since `once_cell` can't create a `Lazy` that is
+//! already initialized.
+
+use std::boxed::Box;
+use once_cell::{sync, unsync};
+use {CompactLazy, Initializer, LazyMut, SyncLazy};
+
+impl<T, F> SyncLazy<T, F> where F: Initializer<T> {
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// This is the same as `SyncLazy::init`.
+    ///
+    /// Uses associated function syntax (`SyncLazy::force(&VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn force(this: &SyncLazy<T, F>) -> &T {
+        this.init()
+    }
+
+    /// Initializes the wrapped value if needed and returns a mutable reference to it
+    ///
+    /// Uses associated function syntax (`SyncLazy::force_mut(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn force_mut(this: &mut SyncLazy<T, F>) -> &mut T {
+        this.init();
+        match SyncLazy::get_mut(this) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// This is the same as `SyncLazy::force`.
+    ///
+    /// Uses associated function syntax (`SyncLazy::get_or_init(&VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn get_or_init(this: &SyncLazy<T, F>) -> &T {
+        this.init()
+    }
+}
+
+impl<T, F> SyncLazy<T, F> {
+    /// Returns the wrapped value, or the initializer if the value is uninitialized
+    ///
+    /// This is the same as `SyncLazy::into_inner`.
+    ///
+    /// Uses associated function syntax (`SyncLazy::into_value(VAL)`)
+    #[track_caller]
+    pub fn into_value(this: SyncLazy<T, F>) -> Result<T, F> {
+        SyncLazy::into_inner(this)
+    }
+}
+
+impl<T, F> CompactLazy<T, F> where F: Initializer<T> {
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// Uses associated function syntax (`CompactLazy::force(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn force(this: &mut CompactLazy<T, F>) -> &T {
+        CompactLazy::force_mut(this)
+    }
+
+    /// Initializes the wrapped value if needed and returns a mutable reference to it
+    ///
+    /// Uses associated function syntax (`CompactLazy::force_mut(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn force_mut(this: &mut CompactLazy<T, F>) -> &mut T {
+        match CompactLazy::get_mut(this.init()) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// This is the same as `CompactLazy::force`.
+    ///
+    /// Uses associated function syntax (`CompactLazy::get_or_init(&mut VAL)`)
+    #[inline]
+    #[track_caller]
+    pub fn get_or_init(this: &mut CompactLazy<T, F>) -> &T {
+        CompactLazy::force_mut(this)
+    }
+}
+
+impl<T, F> CompactLazy<T, F> {
+    /// Returns the wrapped value, or the initializer if the value is uninitialized
+    ///
+    /// This is the same as `CompactLazy::into_inner`.
+    ///
+    /// Uses associated function syntax (`CompactLazy::into_value(VAL)`)
+    #[track_caller]
+    pub fn into_value(this: CompactLazy<T, F>) -> Result<T, F> {
+        CompactLazy::into_inner(this)
+    }
+}
+
+impl<T> LazyMut<T> {
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// Uses associated function syntax (`LazyMut::force(&mut VAL)`)
+    #[inline]
+    pub fn force(this: &mut LazyMut<T>) -> &T {
+        LazyMut::force_mut(this)
+    }
+
+    /// Initializes the wrapped value if needed and returns a mutable reference to it
+    ///
+    /// Uses associated function syntax (`LazyMut::force_mut(&mut VAL)`)
+    #[inline]
+    pub fn force_mut(this: &mut LazyMut<T>) -> &mut T {
+        match LazyMut::get_mut(this.init()) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Initializes the wrapped value if needed and returns a reference to it
+    ///
+    /// This is the same as `LazyMut::force`.
+    ///
+    /// Uses associated function syntax (`LazyMut::get_or_init(&mut VAL)`)
+    #[inline]
+    pub fn get_or_init(this: &mut LazyMut<T>) -> &T {
+        LazyMut::force_mut(this)
+    }
+
+    /// Returns the wrapped value, or the initializer if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`LazyMut::into_value(VAL)`)
+    pub fn into_value(this: LazyMut<T>) -> Result<T, fn() -> T> {
+        match this {
+            LazyMut::Init(init) => Err(init),
+            LazyMut::Value(val) => Ok(val),
+        }
+    }
+}
+
+impl<T, F> From<sync::Lazy<T, F>> for SyncLazy<T, F> where F: Initializer<T> {
+    fn from(lazy: sync::Lazy<T, F>) -> SyncLazy<T, F> {
+        match sync::Lazy::into_value(lazy) {
+            Ok(val) => SyncLazy::with_value(val),
+            Err(init) => SyncLazy::new(init),
+        }
+    }
+}
+
+impl<T, F> From<unsync::Lazy<T, F>> for CompactLazy<T, F> where F: Initializer<T> {
+    fn from(lazy: unsync::Lazy<T, F>) -> CompactLazy<T, F> {
+        match unsync::Lazy::into_value(lazy) {
+            Ok(val) => CompactLazy::with_value(val),
+            Err(init) => CompactLazy::new(init),
+        }
+    }
+}
+
+impl<T> From<unsync::Lazy<T>> for LazyMut<T> {
+    fn from(lazy: unsync::Lazy<T>) -> LazyMut<T> {
+        match unsync::Lazy::into_value(lazy) {
+            Ok(val) => LazyMut::Value(val),
+            Err(init) => LazyMut::Init(init),
+        }
+    }
+}
+
+impl<'a, T: 'a, F: 'a> From<SyncLazy<T, F>> for sync::Lazy<T, Box<dyn FnOnce() -> T + Send + 'a>>
+    where T: Send, F: Initializer<T> + Send
+{
+    fn from(lazy: SyncLazy<T, F>) -> sync::Lazy<T, Box<dyn FnOnce() -> T + Send + 'a>> {
+        sync::Lazy::new(Box::new(move || lazy.unwrap()))
+    }
+}
+
+impl<'a, T: 'a, F: 'a> From<CompactLazy<T, F>> for unsync::Lazy<T, Box<dyn FnOnce() -> T + 'a>>
+    where F: Initializer<T>
+{
+    fn from(lazy: CompactLazy<T, F>) -> unsync::Lazy<T, Box<dyn FnOnce() -> T + 'a>> {
+        unsync::Lazy::new(Box::new(move || lazy.unwrap()))
+    }
+}
+
+impl<'a, T: 'a> From<LazyMut<T>> for unsync::Lazy<T, Box<dyn FnOnce() -> T + 'a>> {
+    fn from(lazy: LazyMut<T>) -> unsync::Lazy<T, Box<dyn FnOnce() -> T + 'a>> {
+        unsync::Lazy::new(Box::new(move || lazy.unwrap()))
+    }
+}
@@ -0,0 +1,63 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Display};
+
+/// Pads and aligns a value to the size of a cache line
+///
+/// Wrapping a heavily contended lazy value in this keeps it from sharing a cache line with other
+/// statics, so that threads writing to neighboring values don't slow down access to it. The
+/// alignment is 128 bytes on x86-64, AArch64, and 64-bit PowerPC (which prefetch cache lines in
+/// pairs or have 128-byte lines), and 64 bytes everywhere else.
+///
+/// ```
+/// use lazy_mut::{CachePadded, SyncLazy};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static HITS: CachePadded<SyncLazy<AtomicUsize>> = CachePadded::new(SyncLazy::new(|| {
+///     AtomicUsize::new(0)
+/// }));
+///
+/// HITS.fetch_add(1, Ordering::Relaxed);
+/// assert_eq!(HITS.load(Ordering::Relaxed), 1);
+/// assert!(std::mem::align_of_val(&HITS) >= 64);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64"),
+    repr(align(128)))]
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64")),
+    repr(align(64)))]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    /// Pads and aligns a value to the size of a cache line
+    pub const fn new(value: T) -> CachePadded<T> {
+        CachePadded { value }
+    }
+
+    /// Returns the wrapped value
+    pub fn unwrap(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Display for CachePadded<T> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
@@ -0,0 +1,161 @@
+use core::fmt::{self, Debug};
+use std::boxed::Box;
+use std::sync::OnceLock;
+
+/// Fieldless enums whose variants can be used as indices, usually implemented with `enum_array`
+pub trait EnumArray: Copy + 'static {
+    /// Every variant of the enum, in order of their indices
+    const VARIANTS: &'static [Self];
+
+    /// Returns the position of a variant in `VARIANTS`
+    fn index(self) -> usize;
+}
+
+/// A thread-safe container that lazily creates one value for each variant of an enum
+///
+/// The initializer is called with a variant the first time that variant's value is requested
+/// through `LazyPerVariant::get`, and the same value is returned from then on. This replaces a
+/// separate lazy static for each variant, such as one connection per backend:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use lazy_mut::LazyPerVariant;
+///
+/// enum_array! {
+///     #[derive(Debug, PartialEq)]
+///     pub enum Backend {
+///         Local,
+///         Staging,
+///         Production,
+///     }
+/// }
+///
+/// fn connect(backend: Backend) -> String {
+///     format!("connected to {:?}", backend)
+/// }
+///
+/// static CONNECTIONS: LazyPerVariant<Backend, String> = LazyPerVariant::new(connect);
+///
+/// # fn main() {
+/// assert_eq!(CONNECTIONS.get(Backend::Staging), "connected to Staging");
+/// assert!(CONNECTIONS.is_initialized(Backend::Staging));
+/// assert!(!CONNECTIONS.is_initialized(Backend::Production));
+/// assert_eq!(CONNECTIONS.try_get(Backend::Local), None);
+/// assert_eq!(CONNECTIONS.iter().map(|(backend, _)| backend).collect::<Vec<_>>(),
+///     [Backend::Staging]);
+/// # }
+/// ```
+///
+/// If multiple threads request the same variant at once only one of them runs the initializer. If
+/// the initializer panics, that variant's value stays uninitialized and the next request runs the
+/// initializer again.
+///
+/// Only available with the `std` feature.
+pub struct LazyPerVariant<E, T> {
+    init: fn(E) -> T,
+    slots: OnceLock<Box<[OnceLock<T>]>>,
+}
+
+impl<E, T> LazyPerVariant<E, T> where E: EnumArray {
+    /// Creates a new container of uninitialized values with an initializer
+    pub const fn new(init: fn(E) -> T) -> LazyPerVariant<E, T> {
+        LazyPerVariant {
+            init,
+            slots: OnceLock::new(),
+        }
+    }
+
+    /// Returns the value of a variant, initializing it if needed
+    #[track_caller]
+    pub fn get(&self, variant: E) -> &T {
+        let init = self.init;
+        self.slot(variant).get_or_init(|| ::trace::init(|| init(variant)))
+    }
+
+    /// Returns the value of a variant if it has been initialized
+    #[inline]
+    pub fn try_get(&self, variant: E) -> Option<&T> {
+        self.slots.get()?[variant.index()].get()
+    }
+
+    /// Returns a mutable reference to the value of a variant if it has been initialized
+    #[inline]
+    pub fn get_mut(&mut self, variant: E) -> Option<&mut T> {
+        self.slots.get_mut()?[variant.index()].get_mut()
+    }
+
+    /// Returns `true` if the value of a variant has been initialized
+    #[inline]
+    pub fn is_initialized(&self, variant: E) -> bool {
+        self.try_get(variant).is_some()
+    }
+
+    /// Returns an iterator over the variants and values that have been initialized
+    pub fn iter(&self) -> impl Iterator<Item = (E, &T)> + '_ {
+        E::VARIANTS.iter().filter_map(move |&variant| {
+            self.try_get(variant).map(|val| (variant, val))
+        })
+    }
+
+    fn slot(&self, variant: E) -> &OnceLock<T> {
+        let slots = self.slots.get_or_init(|| {
+            E::VARIANTS.iter().map(|_| OnceLock::new()).collect()
+        });
+        &slots[variant.index()]
+    }
+}
+
+impl<E, T> Debug for LazyPerVariant<E, T> where E: EnumArray + Debug, T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A macro that declares a fieldless enum implementing `EnumArray`
+///
+/// The enum also derives `Clone` and `Copy`, and the index of each variant is its position in the
+/// declaration. Variants can't have fields or explicit discriminants:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use lazy_mut::EnumArray;
+///
+/// enum_array! {
+///     /// The side of a trade
+///     #[derive(Debug)]
+///     pub enum Side {
+///         Buy,
+///         Sell,
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Side::VARIANTS.len(), 2);
+/// assert_eq!(Side::Sell.index(), 1);
+/// # }
+/// ```
+///
+/// Only available with the `std` feature.
+#[macro_export]
+macro_rules! enum_array {
+    ($(#[$attr:meta])* $vis:vis enum $E:ident { $($(#[$vattr:meta])* $V:ident),* $(,)* }) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy)]
+        $vis enum $E {
+            $($(#[$vattr])* $V),*
+        }
+
+        impl $crate::EnumArray for $E {
+            const VARIANTS: &'static [$E] = &[$($E::$V),*];
+
+            #[inline]
+            fn index(self) -> usize {
+                self as usize
+            }
+        }
+    };
+}
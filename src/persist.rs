@@ -0,0 +1,177 @@
+//! Lazy values cached in a file between runs of a program
+//!
+//! With the `persist` feature enabled, a `PersistLazy<T>` stores the result of its initializer in
+//! a cache file, serialized as JSON with `serde_json`. The next time the program runs, the value
+//! is read back from the file instead of running the initializer again, which helps when building
+//! the value takes much longer than loading it:
+//!
+//! ```
+//! use lazy_mut::persist::PersistLazy;
+//! use std::collections::BTreeMap;
+//!
+//! static INDEX: PersistLazy<BTreeMap<String, Vec<u32>>> =
+//!     PersistLazy::new("target/doctest-index.cache", "v1", build_index);
+//!
+//! fn build_index() -> BTreeMap<String, Vec<u32>> {
+//!     let mut index = BTreeMap::new();
+//!     for (line, text) in ["lazy values", "lazy statics"].iter().enumerate() {
+//!         for word in text.split(' ') {
+//!             index.entry(word.to_string()).or_insert_with(Vec::new).push(line as u32);
+//!         }
+//!     }
+//!     index
+//! }
+//!
+//! assert_eq!(INDEX["lazy"], [0, 1]);
+//! ```
+//!
+//! The cache file starts with a version string, and is only used if its version matches the one
+//! the `PersistLazy` was created with. Change the version whenever the initializer or the type
+//! changes in a way that would make old cache files wrong. The version must not contain a newline.
+//!
+//! A cache file that is missing, has a different version, or can't be deserialized is ignored. The
+//! initializer runs instead, and its result is written to the file for next time. If the file
+//! can't be written, a warning is printed (or logged with the `log` feature). Cache files are
+//! written to a temporary file first and then renamed, so a program that crashes while writing one
+//! doesn't leave a truncated cache behind.
+
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::string::String;
+use std::sync::OnceLock;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+/// A thread-safe lazy value that is cached in a file between runs of a program
+///
+/// See the module documentation for more information.
+pub struct PersistLazy<T> {
+    path: &'static str,
+    version: &'static str,
+    init: fn() -> T,
+    value: OnceLock<T>,
+}
+
+impl<T> PersistLazy<T> where T: Serialize + DeserializeOwned {
+    /// Creates a new uninitialized value cached in a file with a version string
+    pub const fn new(path: &'static str, version: &'static str, init: fn() -> T) -> PersistLazy<T> {
+        PersistLazy {
+            path,
+            version,
+            init,
+            value: OnceLock::new(),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized, reading it from the cache file if
+    /// the file is valid
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish.
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        self.value.get_or_init(|| self.load())
+    }
+
+    /// Removes the cache file, so that the initializer runs again the next time the program runs
+    ///
+    /// The value in memory isn't affected. Returns `Ok` if there was no cache file.
+    pub fn remove_cache(&self) -> io::Result<()> {
+        match fs::remove_file(self.path) {
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn load(&self) -> T {
+        if let Some(val) = self.read() {
+            return val;
+        }
+        let val = ::trace::init(self.init);
+        if let Err(err) = self.write(&val) {
+            warn(self.path, &err);
+        }
+        val
+    }
+
+    fn read(&self) -> Option<T> {
+        let mut reader = BufReader::new(File::open(self.path).ok()?);
+        let mut version = String::new();
+        reader.read_line(&mut version).ok()?;
+        if version.trim_end_matches('\n') != self.version {
+            return None;
+        }
+        serde_json::from_reader(reader).ok()
+    }
+
+    fn write(&self, val: &T) -> io::Result<()> {
+        let path = Path::new(self.path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writeln!(writer, "{}", self.version)?;
+        serde_json::to_writer(&mut writer, val)?;
+        writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        fs::rename(temp, path)
+    }
+}
+
+impl<T> PersistLazy<T> {
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`PersistLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &PersistLazy<T>) -> Option<&T> {
+        this.value.get()
+    }
+
+    /// Returns the path of the cache file
+    ///
+    /// Uses associated function syntax (`PersistLazy::path(&VAL)`)
+    #[inline]
+    pub fn path(this: &PersistLazy<T>) -> &Path {
+        Path::new(this.path)
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Deref for PersistLazy<T> where T: Serialize + DeserializeOwned {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T> Debug for PersistLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match PersistLazy::get(self) {
+            Some(val) => f.debug_tuple("PersistLazy").field(val).finish(),
+            None => write!(f, "PersistLazy({{uninitialized}})"),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+fn warn(path: &str, err: &io::Error) {
+    ::log::warn!(target: "lazy_mut", "cannot write cache file `{}`: {}", path, err);
+}
+
+#[cfg(not(feature = "log"))]
+fn warn(path: &str, err: &io::Error) {
+    eprintln!("cannot write cache file `{}`: {}", path, err);
+}
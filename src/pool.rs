@@ -0,0 +1,218 @@
+use core::fmt::{self, Debug};
+use core::mem;
+use std::boxed::Box;
+use std::format;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::vec::Vec;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of worker threads that is started the first time a job is given to it
+///
+/// The threads are only spawned on the first call to `LazyPool::execute`, so a program that never
+/// uses the pool never pays for it. Jobs run in the order they were given, on whichever worker is
+/// free, and a job that panics doesn't take its worker down with it.
+///
+/// A pool in a `static` is never dropped, so `LazyPool::shutdown` should be called at the end of
+/// `main` to finish the queued jobs and join the workers, instead of letting the process exit
+/// while they are still running:
+///
+/// ```
+/// use lazy_mut::LazyPool;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static WORKERS: LazyPool = LazyPool::named("worker", 4);
+/// static DONE: AtomicUsize = AtomicUsize::new(0);
+///
+/// assert!(!WORKERS.is_initialized());
+/// for _ in 0..16 {
+///     WORKERS.execute(|| {
+///         DONE.fetch_add(1, Ordering::SeqCst);
+///     });
+/// }
+/// assert!(WORKERS.is_initialized());
+///
+/// WORKERS.shutdown();
+/// assert_eq!(DONE.load(Ordering::SeqCst), 16);
+/// ```
+///
+/// Shutting down a pool that was never started doesn't start it, calling `shutdown` again does
+/// nothing, and calling it from one of the workers finishes the other workers without waiting for
+/// the calling one. Giving a job to a pool that has been shut down panics.
+///
+/// Only available with the `std` feature.
+pub struct LazyPool {
+    name: Option<&'static str>,
+    threads: usize,
+    state: Mutex<State>,
+}
+
+enum State {
+    Idle,
+    Running(Sender<Job>, Vec<JoinHandle<()>>),
+    ShutDown,
+}
+
+impl LazyPool {
+    /// Creates a new pool that will start a number of threads, or one for each available CPU if
+    /// the number is zero
+    pub const fn new(threads: usize) -> LazyPool {
+        LazyPool { name: None, threads, state: Mutex::new(State::Idle) }
+    }
+
+    /// Creates a new pool like `LazyPool::new`, naming its threads after `name` followed by their
+    /// index, such as `worker-0`
+    pub const fn named(name: &'static str, threads: usize) -> LazyPool {
+        LazyPool { name: Some(name), threads, state: Mutex::new(State::Idle) }
+    }
+
+    /// Gives a job to the pool, starting the pool if needed
+    #[track_caller]
+    pub fn execute<J>(&self, job: J) where J: FnOnce() + Send + 'static {
+        let mut state = self.lock();
+        if let State::Idle = *state {
+            *state = self.start();
+        }
+        match *state {
+            State::Running(ref sender, _) => {
+                // The workers only stop once the sender has been dropped by `shutdown`
+                let _ = sender.send(Box::new(job));
+            }
+            _ => shut_down(),
+        }
+    }
+
+    /// Finishes every job that has been given to the pool and joins its threads
+    ///
+    /// Any later call to `LazyPool::execute` panics.
+    pub fn shutdown(&self) {
+        let workers = match mem::replace(&mut *self.lock(), State::ShutDown) {
+            State::Running(sender, workers) => {
+                drop(sender);
+                workers
+            }
+            _ => return,
+        };
+        let current = thread::current().id();
+        for worker in workers {
+            if worker.thread().id() != current {
+                // Jobs catch their own panics, so a worker can't have panicked
+                let _ = worker.join();
+            }
+        }
+    }
+
+    /// Returns `true` if the threads of the pool have been started
+    pub fn is_initialized(&self) -> bool {
+        !matches!(*self.lock(), State::Idle)
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn start(&self) -> State {
+        ::trace::init(|| {
+            let threads = match self.threads {
+                0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+                threads => threads,
+            };
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            let workers = (0..threads)
+                .map(|i| {
+                    let receiver = receiver.clone();
+                    let mut builder = thread::Builder::new();
+                    if let Some(name) = self.name {
+                        builder = builder.name(format!("{}-{}", name, i));
+                    }
+                    match builder.spawn(move || work(&receiver)) {
+                        Ok(worker) => worker,
+                        Err(err) => panic!("failed to spawn worker thread: {}", err),
+                    }
+                })
+                .collect();
+            State::Running(sender, workers)
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+/// Runs jobs until the pool is shut down and every queued job has been taken
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(err) => err.into_inner().recv(),
+        };
+        match job {
+            Ok(job) => {
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn shut_down() -> ! {
+    panic!("job given to a thread pool that has been shut down")
+}
+
+impl Debug for LazyPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name.unwrap_or("LazyPool");
+        match *self.lock() {
+            State::Idle => write!(f, "{}({{uninitialized}})", name),
+            State::Running(_, ref workers) => write!(f, "{}({} threads)", name, workers.len()),
+            State::ShutDown => write!(f, "{}({{shut down}})", name),
+        }
+    }
+}
+
+/// A macro that declares lazily started thread pools
+///
+/// Each `static NAME = THREADS;` declares a `LazyPool` whose threads are named after the static,
+/// such as `WORKERS-0`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::sync::mpsc;
+///
+/// lazy_pool! {
+///     /// Runs blocking file operations
+///     pub static IO_POOL = 2;
+/// }
+///
+/// # fn main() {
+/// let (sender, receiver) = mpsc::channel();
+/// IO_POOL.execute(move || {
+///     sender.send(std::thread::current().name().map(String::from)).unwrap();
+/// });
+/// assert!(receiver.recv().unwrap().unwrap().starts_with("IO_POOL-"));
+/// IO_POOL.shutdown();
+/// # }
+/// ```
+///
+/// Only available with the `std` feature.
+#[macro_export]
+macro_rules! lazy_pool {
+    (/* empty */) => {};
+    ($(#[$attr:meta])* $vis:vis static $N:ident = $threads:expr; $($t:tt)*) => {
+        $(#[$attr])*
+        $vis static $N: $crate::LazyPool = $crate::LazyPool::named(stringify!($N), $threads);
+        lazy_pool!($($t)*);
+    };
+}
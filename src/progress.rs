@@ -0,0 +1,161 @@
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use core::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+
+/// A thread-safe lazy value whose initializer reports how far along it is
+///
+/// The initializer is given a `Progress` handle, which it updates as it goes with a fraction
+/// between `0.0` and `1.0`. Other threads can read the latest fraction with
+/// `ProgressLazy::init_progress` while they wait, for instance to show a loading bar:
+///
+/// ```
+/// use lazy_mut::{Progress, ProgressLazy};
+/// use std::thread;
+/// use std::time::Duration;
+///
+/// static MODEL: ProgressLazy<Vec<u8>> = ProgressLazy::new(load_model);
+///
+/// fn load_model(progress: &Progress) -> Vec<u8> {
+///     let mut model = Vec::new();
+///     for chunk in 0..4 {
+///         model.extend_from_slice(&[chunk; 256]);
+///         progress.set((chunk + 1) as f32 / 4.0);
+///     }
+///     model
+/// }
+///
+/// assert_eq!(MODEL.init_progress(), 0.0);
+///
+/// let loader = thread::spawn(|| MODEL.len());
+/// while !MODEL.is_initialized() {
+///     println!("loading model... {:.0}%", MODEL.init_progress() * 100.0);
+///     thread::sleep(Duration::from_millis(1));
+/// }
+///
+/// assert_eq!(loader.join().unwrap(), 1024);
+/// assert_eq!(MODEL.init_progress(), 1.0);
+/// ```
+///
+/// A reporter function can also be set with `ProgressLazy::on_progress`, which is called by the
+/// initializing thread every time the progress changes, such as to forward it over a channel.
+///
+/// Only available with the `std` feature.
+pub struct ProgressLazy<T> {
+    init: fn(&Progress) -> T,
+    report: Option<fn(f32)>,
+    progress: AtomicU32,
+    value: OnceLock<T>,
+}
+
+impl<T> ProgressLazy<T> {
+    /// Creates a new uninitialized value with an initializer that reports its progress
+    pub const fn new(init: fn(&Progress) -> T) -> ProgressLazy<T> {
+        ProgressLazy {
+            init,
+            report: None,
+            progress: AtomicU32::new(0),
+            value: OnceLock::new(),
+        }
+    }
+
+    /// Sets a function to be called with the progress every time the initializer updates it
+    pub const fn on_progress(mut self, report: fn(f32)) -> ProgressLazy<T> {
+        self.report = Some(report);
+        self
+    }
+
+    /// Initializes the wrapped value if it is uninitialized, returning a reference to it
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish.
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        match self.value.get() {
+            Some(val) => val,
+            None => self.initialize(),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) -> &T {
+        self.value.get_or_init(|| {
+            let progress = Progress { progress: &self.progress, report: self.report };
+            progress.set(0.0);
+            let val = ::trace::init(|| (self.init)(&progress));
+            progress.set(1.0);
+            val
+        })
+    }
+
+    /// Returns how far along the initializer is, from `0.0` before it starts to `1.0` once the
+    /// value is initialized
+    #[inline]
+    pub fn init_progress(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`ProgressLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &ProgressLazy<T>) -> Option<&T> {
+        this.value.get()
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T> Deref for ProgressLazy<T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T> Debug for ProgressLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match ProgressLazy::get(self) {
+            Some(val) => f.debug_tuple("ProgressLazy").field(val).finish(),
+            None => write!(f, "ProgressLazy({{uninitialized}}, {:.0}%)",
+                self.init_progress() * 100.0),
+        }
+    }
+}
+
+/// A handle given to the initializer of a `ProgressLazy` for reporting its progress
+pub struct Progress<'a> {
+    progress: &'a AtomicU32,
+    report: Option<fn(f32)>,
+}
+
+impl<'a> Progress<'a> {
+    /// Sets the progress to a fraction between `0.0` and `1.0`
+    ///
+    /// Values outside of that range are clamped, and `NaN` is treated as `0.0`.
+    pub fn set(&self, progress: f32) {
+        let progress = if progress.is_nan() { 0.0 } else { progress.clamp(0.0, 1.0) };
+        self.progress.store(progress.to_bits(), Ordering::Relaxed);
+        if let Some(report) = self.report {
+            report(progress);
+        }
+    }
+
+    /// Returns the progress that was last set
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+}
+
+impl<'a> Debug for Progress<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Progress").field(&self.get()).finish()
+    }
+}
@@ -0,0 +1,261 @@
+use core::cell::UnsafeCell;
+use core::mem::{self, MaybeUninit};
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use core::sync::atomic::AtomicU8;
+use core::sync::atomic::Ordering::{Acquire, Release};
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+const POISONED: u8 = 3;
+
+/// A thread-safe lazy value for programs with real-time threads, which must never wait on a
+/// lower-priority thread for an unbounded amount of time
+///
+/// A thread that finds the value being initialized by another thread can choose between two
+/// strategies:
+///
+/// - `RealtimeLazy::init` (and dereferencing) waits for the initializer, boosting the thread
+///   running it to the priority of the waiting thread for as long as it waits. This uses a
+///   priority-inheritance futex on Linux. On other platforms, the waiting thread yields without
+///   boosting the other thread.
+/// - `RealtimeLazy::get_or_fallback` never waits or runs the initializer, and returns the
+///   fallback value given to `RealtimeLazy::new` until the value has been initialized.
+///
+/// ```
+/// use lazy_mut::RealtimeLazy;
+///
+/// fn load_impulse_response() -> Vec<f32> {
+///     vec![1.0, 0.5, 0.25]
+/// }
+///
+/// static IMPULSE: RealtimeLazy<Vec<f32>> = RealtimeLazy::new(load_impulse_response, Vec::new());
+///
+/// // On the audio thread, process silence until the impulse response is ready
+/// assert!(IMPULSE.get_or_fallback().is_empty());
+///
+/// // On a background thread
+/// IMPULSE.init();
+/// assert_eq!(*IMPULSE.get_or_fallback(), [1.0, 0.5, 0.25]);
+/// ```
+///
+/// Since `get_or_fallback` never runs the initializer, another thread has to call `init` before
+/// real-time threads see the value. If the initializer panics, the value is poisoned: `init`
+/// panics, while `get_or_fallback` keeps returning the fallback value.
+///
+/// Only available with the `std` feature.
+pub struct RealtimeLazy<T, F = fn() -> T> {
+    state: AtomicU8,
+    lock: Lock,
+    init: F,
+    value: UnsafeCell<MaybeUninit<T>>,
+    fallback: T,
+}
+
+unsafe impl<T, F> Sync for RealtimeLazy<T, F> where T: Send + Sync, F: Sync {}
+
+impl<T, F> RealtimeLazy<T, F> where F: Fn() -> T {
+    /// Creates a new uninitialized value with an initializer and a fallback value
+    pub const fn new(init: F, fallback: T) -> RealtimeLazy<T, F> {
+        RealtimeLazy {
+            state: AtomicU8::new(UNINIT),
+            lock: Lock::new(),
+            init,
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            fallback,
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized, returning a reference to it
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish,
+    /// boosting its priority where supported.
+    #[inline]
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        if self.state.load(Acquire) != READY {
+            self.lock.lock();
+            self.initialize();
+        }
+        match RealtimeLazy::get(self) {
+            Some(val) => val,
+            None => poisoned(),
+        }
+    }
+
+    /// Runs the initializer if it hasn't run yet, after the lock has been taken by this thread
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) {
+        if self.state.load(Acquire) == UNINIT {
+            self.state.store(RUNNING, Release);
+            let poison = Poison(self);
+            let val = ::trace::init(&self.init);
+            unsafe { (*self.value.get()).as_mut_ptr().write(val) };
+            mem::forget(poison);
+            self.state.store(READY, Release);
+        }
+        self.lock.unlock();
+    }
+}
+
+impl<T, F> RealtimeLazy<T, F> {
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`RealtimeLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &RealtimeLazy<T, F>) -> Option<&T> {
+        if this.state.load(Acquire) == READY {
+            Some(unsafe { &*(*this.value.get()).as_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value if it is initialized, or the fallback value otherwise
+    ///
+    /// This never waits for another thread or runs the initializer, so it takes a bounded amount
+    /// of time.
+    #[inline]
+    pub fn get_or_fallback(&self) -> &T {
+        match RealtimeLazy::get(self) {
+            Some(val) => val,
+            None => &self.fallback,
+        }
+    }
+
+    /// Returns the fallback value
+    ///
+    /// Uses associated function syntax (`RealtimeLazy::fallback(&VAL)`)
+    #[inline]
+    pub fn fallback(this: &RealtimeLazy<T, F>) -> &T {
+        &this.fallback
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Acquire) == READY
+    }
+}
+
+/// Marks a value as poisoned and releases its lock if its initializer unwinds
+struct Poison<'a, T: 'a, F: 'a>(&'a RealtimeLazy<T, F>);
+
+impl<'a, T, F> Drop for Poison<'a, T, F> {
+    fn drop(&mut self) {
+        self.0.state.store(POISONED, Release);
+        self.0.lock.unlock();
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn poisoned() -> ! {
+    panic!("initializer of lazy value previously panicked")
+}
+
+/// A lock using a priority-inheritance futex, so a thread waiting for it lends its priority to the
+/// thread holding it
+///
+/// The futex word holds the thread ID of the owner, or zero if it is unlocked. Locking and
+/// unlocking without contention don't need a system call.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct Lock(::core::sync::atomic::AtomicU32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Lock {
+    const fn new() -> Lock {
+        Lock(::core::sync::atomic::AtomicU32::new(0))
+    }
+
+    #[track_caller]
+    fn lock(&self) {
+        if self.0.compare_exchange(0, thread_id(), Acquire, Acquire).is_ok() {
+            return;
+        }
+        loop {
+            let result = unsafe { futex(&self.0, ::libc::FUTEX_LOCK_PI) };
+            match result {
+                0 => return,
+                _ => match ::std::io::Error::last_os_error().raw_os_error() {
+                    Some(::libc::EINTR) | Some(::libc::EAGAIN) => {}
+                    Some(::libc::EDEADLK) => {
+                        panic!("lazy value accessed during its own initialization")
+                    }
+                    _ => panic!("failed to lock lazy value: {}",
+                        ::std::io::Error::last_os_error()),
+                },
+            }
+        }
+    }
+
+    fn unlock(&self) {
+        // If there are waiters, the kernel has set a bit in the word and must hand the lock over
+        if self.0.compare_exchange(thread_id(), 0, Release, Acquire).is_err() {
+            unsafe { futex(&self.0, ::libc::FUTEX_UNLOCK_PI) };
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn thread_id() -> u32 {
+    unsafe { ::libc::syscall(::libc::SYS_gettid) as u32 }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn futex(word: &::core::sync::atomic::AtomicU32, op: ::libc::c_int) -> ::libc::c_long {
+    ::libc::syscall(::libc::SYS_futex, word.as_ptr(), op | ::libc::FUTEX_PRIVATE_FLAG, 0,
+        ::core::ptr::null::<::libc::timespec>())
+}
+
+/// A lock that waiting threads spin on, yielding to the operating system, where priority
+/// inheritance isn't available
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+struct Lock(::core::sync::atomic::AtomicBool);
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+impl Lock {
+    const fn new() -> Lock {
+        Lock(::core::sync::atomic::AtomicBool::new(false))
+    }
+
+    fn lock(&self) {
+        while self.0.compare_exchange(false, true, Acquire, Acquire).is_err() {
+            ::std::thread::yield_now();
+        }
+    }
+
+    fn unlock(&self) {
+        self.0.store(false, Release);
+    }
+}
+
+impl<T, F> Deref for RealtimeLazy<T, F> where F: Fn() -> T {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T, F> Drop for RealtimeLazy<T, F> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            unsafe { (*self.value.get()).as_mut_ptr().drop_in_place() };
+        }
+    }
+}
+
+impl<T, F> Debug for RealtimeLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match RealtimeLazy::get(self) {
+            Some(val) => f.debug_tuple("RealtimeLazy").field(val).finish(),
+            None => write!(f, "RealtimeLazy({{uninitialized}})"),
+        }
+    }
+}
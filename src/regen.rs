@@ -0,0 +1,168 @@
+//! Cached values that can be released to free memory and regenerated later
+//!
+//! A `RegenLazy<T>` initializes its value on first access like any other lazy value, and hands it
+//! out as an `Arc<T>`. Unlike other lazy values, it can later be released: the cached `Arc` is
+//! dropped, so the value is freed as soon as the code that is still using it is done, and the
+//! initializer runs again the next time it is needed. This suits caches of values that are
+//! expensive to build but can always be built again, such as decoded images:
+//!
+//! ```
+//! use lazy_mut::regen::RegenLazy;
+//!
+//! static ICON: RegenLazy<Vec<u8>> = RegenLazy::new(|| vec![0xff; 64 * 64 * 4]);
+//!
+//! let icon = ICON.load();
+//! assert_eq!(icon.len(), 16384);
+//!
+//! ICON.release();
+//! assert!(!ICON.is_cached());
+//!
+//! // The value is still in use, so it is reused instead of regenerated
+//! assert!(std::sync::Arc::ptr_eq(&icon, &ICON.load()));
+//!
+//! drop(icon);
+//! ICON.release_on_pressure();
+//! assert_eq!(lazy_mut::regen::release_all(), 1);
+//! assert_eq!(ICON.load().len(), 16384); // regenerated
+//! ```
+//!
+//! Lazy values that are registered with `RegenLazy::release_on_pressure` are all released by
+//! `release_all`, which can be called from whatever low-memory notification the platform
+//! provides.
+
+use core::fmt::{self, Debug};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::vec::Vec;
+
+/// A thread-safe lazy value that can be released and regenerated on the next access
+///
+/// See the module documentation for more information.
+pub struct RegenLazy<T, F = fn() -> T> {
+    init: F,
+    slot: Mutex<Slot<T>>,
+    loading: Mutex<()>,
+}
+
+/// The cached value, along with a weak reference that outlives it after it is released
+struct Slot<T> {
+    cached: Option<Arc<T>>,
+    weak: Weak<T>,
+}
+
+impl<T, F> RegenLazy<T, F> where F: Fn() -> T {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> RegenLazy<T, F> {
+        RegenLazy {
+            init,
+            slot: Mutex::new(Slot { cached: None, weak: Weak::new() }),
+            loading: Mutex::new(()),
+        }
+    }
+
+    /// Returns the value, running the initializer if it isn't cached and isn't in use
+    ///
+    /// If another thread is currently running the initializer, this waits for it to finish.
+    #[track_caller]
+    pub fn load(&self) -> Arc<T> {
+        if let Some(val) = self.get() {
+            return val;
+        }
+        self.regenerate()
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn regenerate(&self) -> Arc<T> {
+        let _loading = self.loading.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(val) = self.get() {
+            return val;
+        }
+        let val = Arc::new(::trace::init(&self.init));
+        let mut slot = self.lock();
+        slot.weak = Arc::downgrade(&val);
+        slot.cached = Some(val.clone());
+        val
+    }
+}
+
+impl<T, F> RegenLazy<T, F> {
+    /// Returns the value without running the initializer, returns `None` if it has been released
+    /// and is no longer in use
+    ///
+    /// A released value that is still in use is cached again.
+    pub fn get(&self) -> Option<Arc<T>> {
+        let mut slot = self.lock();
+        if let Some(ref val) = slot.cached {
+            return Some(val.clone());
+        }
+        let val = slot.weak.upgrade()?;
+        slot.cached = Some(val.clone());
+        Some(val)
+    }
+
+    /// Drops the cached value, returning `true` if it was cached
+    ///
+    /// The value is freed once every `Arc` that was handed out for it has been dropped. Until
+    /// then, the next access reuses it instead of running the initializer again.
+    pub fn release(&self) -> bool {
+        let val = self.lock().cached.take();
+        val.is_some()
+    }
+
+    /// Returns `true` if the value is cached
+    pub fn is_cached(&self) -> bool {
+        self.lock().cached.is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Slot<T>> {
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T, F> RegenLazy<T, F> where T: Send + Sync, F: Sync {
+    /// Registers the value to be released by `release_all`
+    ///
+    /// Registering the same value more than once has no effect.
+    pub fn release_on_pressure(&'static self) {
+        let mut registered = registered();
+        let address = self as *const RegenLazy<T, F> as *const ();
+        if !registered.iter().any(|&other| other as *const dyn Release as *const () == address) {
+            registered.push(self);
+        }
+    }
+}
+
+impl<T, F> Debug for RegenLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.lock().cached {
+            Some(ref val) => f.debug_tuple("RegenLazy").field(val).finish(),
+            None => write!(f, "RegenLazy({{uninitialized}})"),
+        }
+    }
+}
+
+/// Type-erased release of a registered lazy value
+trait Release: Sync {
+    fn release(&self) -> bool;
+}
+
+impl<T, F> Release for RegenLazy<T, F> where T: Send + Sync, F: Sync {
+    fn release(&self) -> bool {
+        RegenLazy::release(self)
+    }
+}
+
+/// The lazy values registered with `RegenLazy::release_on_pressure`
+static REGISTERED: Mutex<Vec<&'static dyn Release>> = Mutex::new(Vec::new());
+
+fn registered() -> MutexGuard<'static, Vec<&'static dyn Release>> {
+    REGISTERED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Releases every lazy value registered with `RegenLazy::release_on_pressure`, returning how many
+/// of them were cached
+pub fn release_all() -> usize {
+    let registered = registered().clone();
+    registered.into_iter().filter(|lazy| lazy.release()).count()
+}
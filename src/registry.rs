@@ -0,0 +1,937 @@
+//! A registry of every thread-safe lazy static declared with the `lazy_mut` macro
+//!
+//! With the `registry` feature enabled, each `static NAME: TY = EXPR;` declared through the
+//! `lazy_mut` macro is collected at link time, so that all of them can be initialized up front
+//! instead of on first access:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     static GREETING: String = format!("Hello, {}!", *NAME);
+//!     static NAME: String = "world".to_string();
+//! }
+//!
+//! # fn main() {
+//! lazy_mut::registry::init_all();
+//!
+//! assert!(GREETING.is_initialized());
+//! assert_eq!(*GREETING, "Hello, world!");
+//! # }
+//! ```
+//!
+//! The registry can also be used to list every lazy static along with whether it has been
+//! initialized yet, for instance to expose on a debugging endpoint:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     static LIMITS: Vec<u32> = vec![10, 100];
+//! }
+//!
+//! # fn main() {
+//! let entry = lazy_mut::registry::entries().find(|entry| entry.name() == "LIMITS").unwrap();
+//! assert_eq!(entry.type_name(), "alloc::vec::Vec<u32>");
+//! assert!(!entry.is_initialized());
+//!
+//! LIMITS.init();
+//! assert!(entry.is_initialized());
+//! # }
+//! ```
+//!
+//! Calling `never_initialized` at shutdown lists the lazy statics that were never used, which
+//! points out dead global state:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     static USED: u32 = 1;
+//!     static UNUSED: u32 = 2;
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(*USED, 1);
+//!
+//! let unused: Vec<_> = lazy_mut::registry::never_initialized()
+//!     .map(|entry| entry.name())
+//!     .collect();
+//! assert!(unused.contains(&"UNUSED"));
+//! assert!(!unused.contains(&"USED"));
+//! # }
+//! ```
+//!
+//! Lazy values are initialized in no particular order. If an initializer uses another lazy value,
+//! that value is initialized first (or waited on, if another thread is initializing it), so
+//! dependencies between lazy values are always respected.
+//!
+//! Statics are never dropped, so values that need to be cleaned up when the program exits (such as
+//! buffered files or open connections) can be dropped with `shutdown` at the end of `main`
+//! instead. This drops them in the reverse of the order they finished initializing in, so a value
+//! is always dropped before the values its initializer used.
+
+use core::any;
+use core::cell::RefCell;
+use core::cmp::Reverse;
+use core::fmt::{self, Write};
+use core::mem;
+use core::ptr;
+use std::error::Error;
+use std::string::String;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+use __inventory as inventory;
+use {LazyState, LazyValue, SyncLazy};
+#[cfg(feature = "metrics")]
+use metrics::InitMetrics;
+#[cfg(feature = "alloc-accounting")]
+use accounting::{self, InitAllocation};
+
+/// A registered lazy static
+pub struct Entry {
+    name: &'static str,
+    module_path: &'static str,
+    lazy: &'static dyn Register,
+}
+
+impl Entry {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, module_path: &'static str, lazy: &'static dyn Register)
+        -> Entry
+    {
+        Entry { name, module_path, lazy }
+    }
+
+    /// Returns the name of the static
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the path of the module that declared the static
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// Returns the name of the type of the value
+    pub fn type_name(&self) -> &'static str {
+        self.lazy.type_name()
+    }
+
+    /// Returns `true` if the value has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.lazy.is_initialized()
+    }
+
+    /// Returns the metrics recorded during initialization, or `None` if the value is uninitialized
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Option<InitMetrics> {
+        self.lazy.metrics()
+    }
+
+    /// Returns the memory allocated by the initializer, or `None` if the value is uninitialized
+    /// or nothing was counted
+    #[cfg(feature = "alloc-accounting")]
+    pub fn allocation(&self) -> Option<InitAllocation> {
+        let allocations = lock(&ALLOCATIONS);
+        let found = allocations.iter().find(|&&(entry, _)| ptr::eq(entry, self));
+        found.map(|&(_, allocation)| allocation).filter(|_| self.is_initialized())
+    }
+
+    /// Initializes the value if it is uninitialized
+    pub fn init(&self) {
+        self.lazy.init()
+    }
+
+    /// Drops the value if it is initialized, so that the next access runs the initializer again
+    ///
+    /// # Safety
+    ///
+    /// See `SyncLazy::reset`.
+    #[cfg(feature = "test-support")]
+    pub unsafe fn reset(&self) {
+        self.lazy.reset()
+    }
+
+    /// Drops the value if it is initialized, so that it can't be used again
+    ///
+    /// # Safety
+    ///
+    /// See `SyncLazy::finalize`.
+    pub unsafe fn finalize(&self) {
+        self.lazy.finalize()
+    }
+
+    /// Returns the address of the lazy static, which identifies it
+    fn address(&self) -> *const () {
+        self.lazy as *const dyn Register as *const ()
+    }
+}
+
+impl fmt::Debug for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("name", &self.name)
+            .field("module_path", &self.module_path)
+            .field("type_name", &self.type_name())
+            .field("is_initialized", &self.is_initialized())
+            .finish()
+    }
+}
+
+inventory::collect!(Entry);
+
+/// The name of a `static mut` declared with the `lazy_mut` macro
+///
+/// These aren't registered lazy statics, since they can't be initialized from other threads, but
+/// they are collected so that an access to one before it is initialized can be reported by name.
+#[doc(hidden)]
+pub struct StaticMutName {
+    name: &'static str,
+    address: fn() -> *const (),
+}
+
+impl StaticMutName {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, address: fn() -> *const ()) -> StaticMutName {
+        StaticMutName { name, address }
+    }
+}
+
+inventory::collect!(StaticMutName);
+
+/// Returns the name of the `static mut` at `address`, if it was declared with the `lazy_mut` macro
+pub(crate) fn static_mut_name(address: *const ()) -> Option<&'static str> {
+    inventory::iter::<StaticMutName>.into_iter()
+        .find(|static_mut| (static_mut.address)() == address)
+        .map(|static_mut| static_mut.name)
+}
+
+/// Type-erased operations on a registered lazy static
+#[doc(hidden)]
+pub trait Register: Sync {
+    /// Returns the name of the type of the value
+    fn type_name(&self) -> &'static str;
+
+    /// Returns `true` if the value has been initialized
+    fn is_initialized(&self) -> bool;
+
+    /// Returns how far the value has been initialized
+    fn state(&self) -> LazyState;
+
+    /// Returns the metrics recorded during initialization
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> Option<InitMetrics>;
+
+    /// Initializes the value if it is uninitialized
+    fn init(&self);
+
+    /// Drops the value if it is initialized
+    #[cfg(feature = "test-support")]
+    unsafe fn reset(&self);
+
+    /// Drops the value if it is initialized, poisoning it
+    unsafe fn finalize(&self);
+}
+
+impl<T> Register for SyncLazy<T> where T: Send + Sync {
+    fn type_name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn is_initialized(&self) -> bool {
+        SyncLazy::is_initialized(self)
+    }
+
+    fn state(&self) -> LazyState {
+        LazyValue::state(self)
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metrics(&self) -> Option<InitMetrics> {
+        SyncLazy::metrics(self)
+    }
+
+    fn init(&self) {
+        SyncLazy::init(self);
+    }
+
+    #[cfg(feature = "test-support")]
+    unsafe fn reset(&self) {
+        SyncLazy::reset(self);
+    }
+
+    unsafe fn finalize(&self) {
+        SyncLazy::finalize(self);
+    }
+}
+
+/// Returns an iterator over every registered lazy static, in no particular order
+pub fn entries() -> impl Iterator<Item = &'static Entry> {
+    inventory::iter::<Entry>.into_iter()
+}
+
+/// Returns an iterator over every registered lazy static that hasn't been initialized yet
+pub fn never_initialized() -> impl Iterator<Item = &'static Entry> {
+    entries().filter(|entry| !entry.is_initialized())
+}
+
+/// Checks that every lazy static with one of the given names is initialized, returning an error
+/// naming all of the ones that aren't
+///
+/// A name that doesn't belong to any registered static is reported as not initialized, so a
+/// misspelled name doesn't silently pass:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// lazy_mut! {
+///     static SESSIONS: Vec<u32> = Vec::new();
+///     static USERS: Vec<u32> = Vec::new();
+/// }
+///
+/// # fn main() {
+/// SESSIONS.init();
+///
+/// let err = lazy_mut::registry::check_initialized(&["SESSIONS", "USERS"]).unwrap_err();
+/// assert_eq!(err.names(), ["USERS"]);
+/// assert_eq!(err.to_string(), "lazy statics are not initialized: `USERS`");
+/// # }
+/// ```
+pub fn check_initialized(names: &[&'static str]) -> Result<(), NotInitialized> {
+    let missing: Vec<_> = names.iter()
+        .filter(|&&name| !entries().any(|entry| entry.name == name && entry.is_initialized()))
+        .copied()
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(NotInitialized { names: missing })
+    }
+}
+
+/// An error from `check_initialized`, naming the lazy statics that aren't initialized
+#[derive(Clone, Debug)]
+pub struct NotInitialized {
+    names: Vec<&'static str>,
+}
+
+impl NotInitialized {
+    /// Returns the names of the lazy statics that aren't initialized
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+}
+
+impl fmt::Display for NotInitialized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "lazy statics are not initialized: ")?;
+        for (i, name) in self.names.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "`{}`", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for NotInitialized {}
+
+/// What to do when a lazy static is initialized after startup has been marked as complete
+#[derive(Clone, Copy, Debug)]
+pub enum LateInit {
+    /// Print a warning (through the `log` crate with the `log` feature, or to stderr otherwise)
+    Warn,
+    /// Panic instead of running the initializer
+    ///
+    /// This poisons the value, unless it was created with `SyncLazy::pure`, which is left
+    /// uninitialized so that the next access panics again.
+    Panic,
+    /// Call a function with the entry before running the initializer
+    Call(fn(&'static Entry)),
+}
+
+/// The action to take for late initialization, or `None` during startup
+static LATE_INIT: Mutex<Option<LateInit>> = Mutex::new(None);
+
+/// Marks startup as complete, so that initializing a registered lazy static afterwards is treated
+/// as a mistake that `action` reports
+///
+/// This finds lazy statics that were meant to be initialized up front (for instance by
+/// `init_all`) but are instead initialized while handling requests:
+///
+/// ```should_panic
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use lazy_mut::registry::{self, LateInit};
+///
+/// lazy_mut! {
+///     static EAGER: u32 = 1;
+///     static FORGOTTEN: u32 = 2;
+/// }
+///
+/// # fn main() {
+/// EAGER.init();
+/// registry::startup_complete(LateInit::Panic);
+///
+/// assert_eq!(*EAGER, 1);
+/// assert_eq!(*FORGOTTEN, 2); // panics
+/// # }
+/// ```
+///
+/// Lazy statics that aren't registered are not checked. Calling this again changes the action.
+pub fn startup_complete(action: LateInit) {
+    *match LATE_INIT.lock() {
+        Ok(late_init) => late_init,
+        Err(err) => err.into_inner(),
+    } = Some(action);
+}
+
+/// Records that `lazy` is about to run its initializer, for `check_late_init` and
+/// `dependency_graph`
+#[doc(hidden)]
+pub fn start_init<L>(lazy: &L) {
+    if let Some(entry) = find(lazy) {
+        check_late_init(entry);
+        INITIALIZING.with(|initializing| {
+            let mut initializing = initializing.borrow_mut();
+            // An initializer that panicked never finished, so it isn't running anymore
+            while let Some(top) = initializing.last() {
+                match top.entry.lazy.state() {
+                    LazyState::Initialized | LazyState::Poisoned => initializing.pop(),
+                    _ => break,
+                };
+            }
+            if let Some(dependent) = initializing.last().map(|running| running.entry) {
+                let mut edges = lock(&EDGES);
+                if !edges.iter().any(|&(a, b)| ptr::eq(a, dependent) && ptr::eq(b, entry)) {
+                    edges.push((dependent, entry));
+                }
+            }
+            initializing.push(Running {
+                entry,
+                #[cfg(feature = "alloc-accounting")]
+                counts: None,
+            });
+            // Anything allocated above is bookkeeping, not part of the initializer
+            #[cfg(feature = "alloc-accounting")]
+            {
+                if let Some(running) = initializing.last_mut() {
+                    running.counts = accounting::counts();
+                }
+            }
+        });
+    }
+}
+
+/// Reports the initialization of `entry` if startup has been marked as complete
+fn check_late_init(entry: &'static Entry) {
+    let action = match LATE_INIT.lock() {
+        Ok(late_init) => *late_init,
+        Err(err) => *err.into_inner(),
+    };
+    let action = match action {
+        Some(action) => action,
+        None => return,
+    };
+    match action {
+        #[cfg(feature = "log")]
+        LateInit::Warn => ::log::warn!(target: "lazy_mut",
+            "lazy static `{}::{}` initialized after startup", entry.module_path, entry.name),
+        #[cfg(not(feature = "log"))]
+        LateInit::Warn => eprintln!("warning: lazy static `{}::{}` initialized after startup",
+            entry.module_path, entry.name),
+        LateInit::Panic => panic!("lazy static `{}::{}` initialized after startup",
+            entry.module_path, entry.name),
+        LateInit::Call(f) => f(entry),
+    }
+}
+
+/// The registered lazy statics sorted by address, so that `find` doesn't have to search them all
+struct Index {
+    /// The first entry in the inventory when the index was built
+    first: Option<&'static Entry>,
+    entries: Vec<(usize, &'static Entry)>,
+}
+
+static INDEX: RwLock<Index> = RwLock::new(Index { first: None, entries: Vec::new() });
+
+impl Index {
+    /// Returns `true` if no entries have been registered since the index was built
+    ///
+    /// Entries are registered by constructors that run before `main`, but an `#[eager]` static can
+    /// be initialized by a constructor that runs before the others. The inventory adds each entry
+    /// to the front of its list, so the index is out of date whenever the first entry changes.
+    fn is_current(&self, first: Option<&'static Entry>) -> bool {
+        match (self.first, first) {
+            (Some(a), Some(b)) => ptr::eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+
+    fn get(&self, address: usize) -> Option<&'static Entry> {
+        self.entries.binary_search_by_key(&address, |&(address, _)| address).ok()
+            .map(|i| self.entries[i].1)
+    }
+}
+
+/// Returns the entry for `lazy` if it is registered
+fn find<L>(lazy: &L) -> Option<&'static Entry> {
+    let address = lazy as *const L as usize;
+    let first = entries().next();
+    {
+        let index = match INDEX.read() {
+            Ok(index) => index,
+            Err(err) => err.into_inner(),
+        };
+        if index.is_current(first) {
+            return index.get(address);
+        }
+    }
+    let mut index = match INDEX.write() {
+        Ok(index) => index,
+        Err(err) => err.into_inner(),
+    };
+    if !index.is_current(first) {
+        let mut entries: Vec<_> = entries()
+            .map(|entry| (entry.address() as usize, entry))
+            .collect();
+        entries.sort_by_key(|&(address, _)| address);
+        *index = Index { first, entries };
+    }
+    index.get(address)
+}
+
+/// The registered lazy statics that have been initialized, in the order they finished
+static INIT_ORDER: Mutex<Vec<&'static Entry>> = Mutex::new(Vec::new());
+
+/// Records that `lazy` finished initializing, if it is registered
+#[doc(hidden)]
+pub fn record_init<L>(lazy: &L) {
+    if let Some(entry) = find(lazy) {
+        INITIALIZING.with(|initializing| {
+            let mut initializing = initializing.borrow_mut();
+            let running = initializing.iter().rposition(|running| ptr::eq(running.entry, entry));
+            if let Some(i) = running {
+                #[cfg(feature = "alloc-accounting")]
+                {
+                    let counts = (initializing[i].counts, accounting::counts());
+                    if let (Some(start), Some(end)) = counts {
+                        let mut allocations = lock(&ALLOCATIONS);
+                        allocations.retain(|&(other, _)| !ptr::eq(other, entry));
+                        allocations.push((entry, InitAllocation::between(start, end)));
+                    }
+                }
+                initializing.truncate(i);
+            }
+        });
+        let mut order = match INIT_ORDER.lock() {
+            Ok(order) => order,
+            Err(err) => err.into_inner(),
+        };
+        order.retain(|&other| !ptr::eq(other, entry));
+        order.push(entry);
+    }
+}
+
+/// Drops every registered lazy static that has been initialized, in the reverse of the order they
+/// were initialized in
+///
+/// Since a lazy static used by the initializer of another always finishes initializing first, it
+/// is dropped after the lazy statics that depend on it:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::sync::Mutex;
+///
+/// static CLOSED: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+///
+/// struct Connection(&'static str);
+///
+/// impl Drop for Connection {
+///     fn drop(&mut self) {
+///         CLOSED.lock().unwrap().push(self.0);
+///     }
+/// }
+///
+/// lazy_mut! {
+///     static POOL: Connection = Connection("pool");
+///     static SESSION: Connection = {
+///         assert_eq!(POOL.0, "pool");
+///         Connection("session")
+///     };
+/// }
+///
+/// # fn main() {
+/// SESSION.init();
+/// unsafe { lazy_mut::shutdown() };
+///
+/// assert_eq!(*CLOSED.lock().unwrap(), ["session", "pool"]);
+/// assert!(!SESSION.is_initialized());
+/// # }
+/// ```
+///
+/// Each value is left poisoned, so accessing it afterwards panics instead of initializing it again.
+/// Lazy statics that aren't registered are not dropped.
+///
+/// # Safety
+///
+/// No references to any registered lazy static may be alive, and no other thread may access one
+/// afterwards. This is usually only true at the very end of `main`.
+pub unsafe fn shutdown() {
+    let order = {
+        let mut order = match INIT_ORDER.lock() {
+            Ok(order) => order,
+            Err(err) => err.into_inner(),
+        };
+        mem::take(&mut *order)
+    };
+    for entry in order.into_iter().rev() {
+        entry.finalize();
+    }
+}
+
+/// Initializes every registered lazy static that is uninitialized
+pub fn init_all() {
+    for entry in entries() {
+        entry.init();
+    }
+}
+
+/// Returns the registered lazy statics that have been initialized, in the order they finished
+/// initializing
+///
+/// Saving the paths of these entries and passing them to `init_in_order` on the next run warms
+/// up the lazy statics in the same order, which makes startup more reproducible:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// lazy_mut! {
+///     static FONTS: Vec<&'static str> = vec!["serif"];
+///     static THEME: String = format!("dark with {}", FONTS[0]);
+/// }
+///
+/// # fn main() {
+/// THEME.init();
+///
+/// let order: Vec<String> = lazy_mut::registry::init_order()
+///     .iter()
+///     .map(|entry| format!("{}::{}", entry.module_path(), entry.name()))
+///     .collect();
+/// assert!(order[0].ends_with("::FONTS"));
+/// assert!(order[1].ends_with("::THEME"));
+///
+/// // On the next run
+/// lazy_mut::registry::init_in_order(order.iter().map(String::as_str));
+/// # }
+/// ```
+pub fn init_order() -> Vec<&'static Entry> {
+    match INIT_ORDER.lock() {
+        Ok(order) => order.clone(),
+        Err(err) => err.into_inner().clone(),
+    }
+}
+
+/// Initializes the registered lazy statics with the given paths in order, and then every other
+/// registered lazy static that is uninitialized
+///
+/// Each path is a module path followed by the name of the static, such as `app::config::LIMITS`.
+/// Paths that don't belong to any registered static are skipped, so an order saved by an older
+/// version of the program can still be used.
+pub fn init_in_order<'a, I>(paths: I) where I: IntoIterator<Item = &'a str> {
+    for path in paths {
+        let (module_path, name) = match path.rfind("::") {
+            Some(i) => (&path[..i], &path[i + 2..]),
+            None => continue,
+        };
+        let found = entries().find(|entry| entry.module_path == module_path && entry.name == name);
+        if let Some(entry) = found {
+            entry.init();
+        }
+    }
+    init_all();
+}
+
+/// How long the initializers of registered lazy statics are expected to take
+static TIMINGS: Mutex<Vec<(&'static Entry, Duration)>> = Mutex::new(Vec::new());
+
+/// Records how long the initializers of registered lazy statics are expected to take, for
+/// `warmup` to plan with
+///
+/// Each path is a module path followed by the name of the static, like with `init_in_order`, and
+/// paths that don't belong to any registered static are skipped. The timings usually come from
+/// `WarmupReport::timings` on a previous run. Timings recorded earlier for the same statics are
+/// replaced.
+pub fn load_timings<'a, I>(timings: I) where I: IntoIterator<Item = (&'a str, Duration)> {
+    let mut recorded = match TIMINGS.lock() {
+        Ok(recorded) => recorded,
+        Err(err) => err.into_inner(),
+    };
+    for (path, duration) in timings {
+        let (module_path, name) = match path.rfind("::") {
+            Some(i) => (&path[..i], &path[i + 2..]),
+            None => continue,
+        };
+        let found = entries().find(|entry| entry.module_path == module_path && entry.name == name);
+        if let Some(entry) = found {
+            recorded.retain(|&(other, _)| !ptr::eq(other, entry));
+            recorded.push((entry, duration));
+        }
+    }
+}
+
+/// Initializes as many registered lazy statics as possible before `deadline`, most expensive
+/// first, and reports which ones are left
+///
+/// The lazy statics are ordered by the timings recorded with `load_timings`, and one that isn't
+/// expected to finish before the deadline is skipped in favor of cheaper ones. Lazy statics
+/// without a recorded timing are initialized last, in no particular order. No initializer is
+/// started after the deadline, but one that takes longer than expected isn't interrupted either.
+///
+/// The report includes how long each initializer took, which can be saved and loaded on the next
+/// run so that the budget is spent on the most expensive lazy statics first:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// use std::time::{Duration, Instant};
+///
+/// lazy_mut! {
+///     static INDEX: Vec<u32> = (0..1000).collect();
+///     static MODEL: Vec<f32> = {
+///         std::thread::sleep(Duration::from_millis(200));
+///         vec![0.5; 16]
+///     };
+/// }
+///
+/// # fn main() {
+/// let model_path = concat!(module_path!(), "::MODEL");
+/// lazy_mut::registry::load_timings(vec![(model_path, Duration::from_secs(60))]);
+///
+/// let report = lazy_mut::registry::warmup(Instant::now() + Duration::from_secs(1));
+/// assert!(INDEX.is_initialized());
+/// assert!(!MODEL.is_initialized());
+/// assert!(report.remaining().iter().any(|entry| entry.name() == "MODEL"));
+///
+/// let saved: Vec<(String, Duration)> = report.timings()
+///     .iter()
+///     .map(|&(entry, duration)| (format!("{}::{}", entry.module_path(), entry.name()), duration))
+///     .collect();
+/// assert!(saved.iter().any(|(path, _)| path.ends_with("::INDEX")));
+/// # }
+/// ```
+pub fn warmup(deadline: Instant) -> WarmupReport {
+    let mut planned: Vec<(&'static Entry, Option<Duration>)> = {
+        let recorded = match TIMINGS.lock() {
+            Ok(recorded) => recorded,
+            Err(err) => err.into_inner(),
+        };
+        never_initialized()
+            .map(|entry| {
+                let expected = recorded.iter().find(|&&(other, _)| ptr::eq(other, entry));
+                (entry, expected.map(|&(_, duration)| duration))
+            })
+            .collect()
+    };
+    // `None` sorts before every `Some`, so reversing puts the longest timings first and the
+    // statics without one last
+    planned.sort_by_key(|&(_, expected)| Reverse(expected));
+
+    let mut timings = Vec::new();
+    for (entry, expected) in planned {
+        let start = Instant::now();
+        if start >= deadline {
+            break;
+        }
+        if entry.is_initialized() || expected.is_some_and(|expected| start + expected > deadline) {
+            continue;
+        }
+        entry.init();
+        timings.push((entry, start.elapsed()));
+    }
+    WarmupReport { timings, remaining: never_initialized().collect() }
+}
+
+/// The result of `warmup`, listing what it initialized and what is left
+#[derive(Debug)]
+pub struct WarmupReport {
+    timings: Vec<(&'static Entry, Duration)>,
+    remaining: Vec<&'static Entry>,
+}
+
+impl WarmupReport {
+    /// Returns the lazy statics that were initialized, in order, with how long each one took
+    ///
+    /// A duration includes the time spent initializing any lazy statics that the initializer
+    /// used.
+    pub fn timings(&self) -> &[(&'static Entry, Duration)] {
+        &self.timings
+    }
+
+    /// Returns the registered lazy statics that are still uninitialized
+    pub fn remaining(&self) -> &[&'static Entry] {
+        &self.remaining
+    }
+
+    /// Returns `true` if every registered lazy static is initialized
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+/// A registered lazy static whose initializer is running
+struct Running {
+    entry: &'static Entry,
+    /// The bytes allocated and freed by the thread before the initializer started
+    #[cfg(feature = "alloc-accounting")]
+    counts: Option<(usize, usize)>,
+}
+
+std::thread_local! {
+    /// The registered lazy statics whose initializers are running on this thread, innermost last
+    static INITIALIZING: RefCell<Vec<Running>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The dependencies that have been observed, as pairs of a lazy static and one that its
+/// initializer initialized
+static EDGES: Mutex<Vec<(&'static Entry, &'static Entry)>> = Mutex::new(Vec::new());
+
+/// The memory allocated by the initializer of each registered lazy static that has been counted
+#[cfg(feature = "alloc-accounting")]
+static ALLOCATIONS: Mutex<Vec<(&'static Entry, InitAllocation)>> = Mutex::new(Vec::new());
+
+fn lock<T>(mutex: &Mutex<T>) -> ::std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(err) => err.into_inner(),
+    }
+}
+
+/// Returns a graph of which registered lazy statics depend on which, as observed so far
+///
+/// A lazy static depends on another if the other one was initialized while its initializer was
+/// running, on the same thread. A dependency that was already initialized is used without
+/// running anything, so it can't be observed. The graph is most complete when taken after the
+/// program has run normally, letting each lazy static be initialized by its first use, and
+/// `DependencyGraph::to_dot` turns it into a picture with Graphviz:
+///
+/// ```
+/// #[macro_use]
+/// extern crate lazy_mut;
+///
+/// lazy_mut! {
+///     static USERS: Vec<String> = vec![format!("admin@{}", *DOMAIN)];
+///     static DOMAIN: String = "example.com".to_string();
+///     static UNUSED: u32 = 0;
+/// }
+///
+/// # fn main() {
+/// assert_eq!(USERS[0], "admin@example.com");
+///
+/// let graph = lazy_mut::registry::dependency_graph();
+/// let users = graph.entries().iter().find(|entry| entry.name() == "USERS").unwrap();
+/// let names: Vec<_> = graph.dependencies(users).map(|entry| entry.name()).collect();
+/// assert_eq!(names, ["DOMAIN"]);
+///
+/// let dot = graph.to_dot();
+/// assert!(dot.starts_with("digraph lazy_statics {"));
+/// assert!(dot.contains("::USERS\" -> \""));
+/// # }
+/// ```
+pub fn dependency_graph() -> DependencyGraph {
+    DependencyGraph { entries: entries().collect(), edges: lock(&EDGES).clone() }
+}
+
+/// The registered lazy statics and the dependencies between them, returned by
+/// `dependency_graph`
+#[derive(Clone, Debug)]
+pub struct DependencyGraph {
+    entries: Vec<&'static Entry>,
+    edges: Vec<(&'static Entry, &'static Entry)>,
+}
+
+impl DependencyGraph {
+    /// Returns every registered lazy static, in no particular order
+    pub fn entries(&self) -> &[&'static Entry] {
+        &self.entries
+    }
+
+    /// Returns every dependency, as pairs of a lazy static and one that it depends on
+    pub fn edges(&self) -> &[(&'static Entry, &'static Entry)] {
+        &self.edges
+    }
+
+    /// Returns an iterator over the lazy statics that `entry` depends on
+    pub fn dependencies<'a>(&'a self, entry: &'a Entry)
+        -> impl Iterator<Item = &'static Entry> + 'a
+    {
+        self.edges.iter().filter(move |&&(a, _)| ptr::eq(a, entry)).map(|&(_, b)| b)
+    }
+
+    /// Returns an iterator over the lazy statics that depend on `entry`
+    pub fn dependents<'a>(&'a self, entry: &'a Entry) -> impl Iterator<Item = &'static Entry> + 'a {
+        self.edges.iter().filter(move |&&(_, b)| ptr::eq(b, entry)).map(|&(a, _)| a)
+    }
+
+    /// Returns the graph in the DOT language of Graphviz, with an arrow from each lazy static to
+    /// the ones it depends on
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph lazy_statics {\n");
+        for entry in &self.entries {
+            let _ = writeln!(dot, "    \"{}::{}\";", entry.module_path, entry.name);
+        }
+        for &(a, b) in &self.edges {
+            let _ = writeln!(dot, "    \"{}::{}\" -> \"{}::{}\";",
+                a.module_path, a.name, b.module_path, b.name);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Initializes every registered lazy static that is uninitialized, using the `rayon` thread pool
+///
+/// Any lazy static used by the initializer of another is initialized first, just like with
+/// `init_all`.
+#[cfg(feature = "rayon")]
+pub fn init_all_parallel() {
+    use rayon::prelude::*;
+    use std::vec::Vec;
+
+    let entries: Vec<&Entry> = entries().collect();
+    entries.par_iter().for_each(|entry| entry.init());
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_register {
+    ($N:ident) => {
+        $crate::__inventory::submit! {
+            $crate::registry::Entry::new(stringify!($N), module_path!(), &$N)
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __lazy_mut_register_static_mut {
+    ($N:ident) => {
+        $crate::__inventory::submit! {
+            $crate::registry::StaticMutName::new(stringify!($N), || {
+                $crate::__addr_of!($N) as *const ()
+            })
+        }
+    };
+}
@@ -0,0 +1,287 @@
+//! Lazy values shared between processes through shared memory
+//!
+//! With the `shared-mem` feature enabled on Unix, a `SharedMemLazy<T>` stores its value in a named
+//! POSIX shared memory object instead of in the process itself. Every process that accesses a
+//! `SharedMemLazy` with the same name maps the same memory, so the initializer only runs once in
+//! total, no matter how many worker processes need the value. This is useful for pre-forking
+//! servers where each worker would otherwise build the same expensive table:
+//!
+//! ```
+//! use lazy_mut::shared_mem::SharedMemLazy;
+//!
+//! static TABLE: SharedMemLazy<[u32; 256]> =
+//!     SharedMemLazy::new("/lazy_mut-doctest-crc", crc_table);
+//!
+//! fn crc_table() -> [u32; 256] {
+//!     let mut table = [0; 256];
+//!     for (i, entry) in table.iter_mut().enumerate() {
+//!         *entry = (0..8).fold(i as u32, |c, _| {
+//!             if c & 1 == 1 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 }
+//!         });
+//!     }
+//!     table
+//! }
+//!
+//! assert_eq!(TABLE[1], 0x7707_3096);
+//! TABLE.unlink().unwrap();
+//! ```
+//!
+//! Since the memory is mapped at a different address in each process, the value must not contain
+//! any pointers. This is checked with the `Pod` trait, which also requires every bit pattern to be
+//! a valid value, so that a shared memory object created by a different program (or for a
+//! different type) can't cause undefined behavior. The value is never dropped, and it can only be
+//! accessed immutably, since other processes may be reading it at the same time.
+//!
+//! The shared memory object outlives the processes that use it, so a value initialized by one run
+//! of a program is still used by the next run. Call `SharedMemLazy::unlink` to remove it, such as
+//! when the program starts before forking its workers. If a process exits while running the
+//! initializer, another process that is waiting for the value notices and runs it instead.
+
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::ffi::CString;
+use std::io;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use libc;
+
+/// The state of a value that isn't initialized and isn't being initialized
+///
+/// While a process is running the initializer, the state is its process ID instead, so that
+/// claiming the value and taking it back from a process that exited are each a single
+/// compare-and-swap.
+const UNINIT: u32 = 0;
+const READY: u32 = u32::MAX;
+
+/// Types that can be stored in memory shared between processes
+///
+/// # Safety
+///
+/// Implementing types must not contain any pointers or references, including indirectly, and must
+/// not rely on being dropped. Copying their bytes to another process must produce the same value,
+/// and every bit pattern must be a valid value of the type.
+pub unsafe trait Pod: Copy + Send + Sync + 'static {}
+
+macro_rules! impl_pod {
+    ($($T:ty),*) => {
+        $(unsafe impl Pod for $T {})*
+    }
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T, const N: usize> Pod for [T; N] where T: Pod {}
+
+/// The header at the start of the shared memory object
+#[repr(C)]
+struct Header {
+    state: AtomicU32,
+}
+
+/// A lazy value stored in shared memory, initialized once for every process that uses it
+///
+/// See the module documentation for more information.
+pub struct SharedMemLazy<T> {
+    name: &'static str,
+    init: fn() -> T,
+    addr: AtomicUsize,
+    opening: Mutex<()>,
+}
+
+impl<T> SharedMemLazy<T> where T: Pod {
+    /// Creates a new uninitialized value with the name of a shared memory object and an
+    /// initializer
+    ///
+    /// The name should start with a `/` and not contain any other slashes.
+    pub const fn new(name: &'static str, init: fn() -> T) -> SharedMemLazy<T> {
+        SharedMemLazy {
+            name,
+            init,
+            addr: AtomicUsize::new(0),
+            opening: Mutex::new(()),
+        }
+    }
+
+    /// Initializes the value if needed and returns a reference to it, panicking if the shared
+    /// memory object can't be opened
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        match self.try_init() {
+            Ok(val) => val,
+            Err(err) => panic!("cannot open shared memory `{}`: {}", self.name, err),
+        }
+    }
+
+    /// Initializes the value if needed and returns a reference to it, returning an error if the
+    /// shared memory object can't be opened
+    ///
+    /// If another thread or process is currently initializing the value, this waits for it to
+    /// finish.
+    pub fn try_init(&self) -> io::Result<&T> {
+        let addr = match self.addr.load(Ordering::Acquire) {
+            0 => self.open()?,
+            addr => addr,
+        };
+        let header = unsafe { &*(addr as *const Header) };
+        loop {
+            match header.state.load(Ordering::Acquire) {
+                READY => break,
+                UNINIT => {
+                    let pid = unsafe { libc::getpid() } as u32;
+                    if header.state.compare_exchange(UNINIT, pid,
+                        Ordering::AcqRel, Ordering::Acquire).is_err()
+                    {
+                        continue;
+                    }
+                    let reset = Reset(header);
+                    let val = ::trace::init(self.init);
+                    unsafe { ptr::write((addr + offset::<T>()) as *mut T, val) };
+                    mem::forget(reset);
+                    header.state.store(READY, Ordering::Release);
+                    break;
+                }
+                // If another process already took the value back and a new one claimed it, the
+                // state no longer matches, so this can't reset a live owner
+                owner if is_dead(owner as libc::pid_t) => {
+                    let _ = header.state.compare_exchange(owner, UNINIT,
+                        Ordering::AcqRel, Ordering::Acquire);
+                }
+                _ => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        Ok(unsafe { &*((addr + offset::<T>()) as *const T) })
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized or the
+    /// shared memory object hasn't been opened by this process yet
+    ///
+    /// Uses associated function syntax (`SharedMemLazy::get(&VAL)`)
+    pub fn get(this: &SharedMemLazy<T>) -> Option<&T> {
+        let addr = this.addr.load(Ordering::Acquire);
+        if addr == 0 {
+            return None;
+        }
+        let header = unsafe { &*(addr as *const Header) };
+        match header.state.load(Ordering::Acquire) {
+            READY => Some(unsafe { &*((addr + offset::<T>()) as *const T) }),
+            _ => None,
+        }
+    }
+
+    /// Removes the shared memory object, so that the next process to open it initializes it again
+    ///
+    /// Processes that already opened it keep using the old value.
+    pub fn unlink(&self) -> io::Result<()> {
+        let name = CString::new(self.name)?;
+        match unsafe { libc::shm_unlink(name.as_ptr()) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn open(&self) -> io::Result<usize> {
+        let _opening = self.opening.lock().unwrap_or_else(PoisonError::into_inner);
+        match self.addr.load(Ordering::Acquire) {
+            0 => {}
+            addr => return Ok(addr),
+        }
+        let name = CString::new(self.name)?;
+        let addr = unsafe { map(&name, size::<T>())? };
+        self.addr.store(addr, Ordering::Release);
+        Ok(addr)
+    }
+}
+
+impl<T> SharedMemLazy<T> {
+    /// Returns the name of the shared memory object
+    ///
+    /// Uses associated function syntax (`SharedMemLazy::name(&VAL)`)
+    #[inline]
+    pub fn name(this: &SharedMemLazy<T>) -> &'static str {
+        this.name
+    }
+}
+
+impl<T> Deref for SharedMemLazy<T> where T: Pod {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T> Debug for SharedMemLazy<T> where T: Pod + Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match SharedMemLazy::get(self) {
+            Some(val) => f.debug_tuple("SharedMemLazy").field(val).finish(),
+            None => write!(f, "SharedMemLazy({{uninitialized}})"),
+        }
+    }
+}
+
+impl<T> Drop for SharedMemLazy<T> {
+    fn drop(&mut self) {
+        match *self.addr.get_mut() {
+            0 => {}
+            addr => unsafe { libc::munmap(addr as *mut libc::c_void, size::<T>()); },
+        }
+    }
+}
+
+/// Resets the state of the value if the initializer panics
+struct Reset<'a>(&'a Header);
+
+impl<'a> Drop for Reset<'a> {
+    fn drop(&mut self) {
+        self.0.state.store(UNINIT, Ordering::Release);
+    }
+}
+
+/// Returns the offset of the value from the start of the shared memory object
+const fn offset<T>() -> usize {
+    mem::size_of::<Header>().next_multiple_of(mem::align_of::<T>())
+}
+
+/// Returns the size of the shared memory object
+const fn size<T>() -> usize {
+    offset::<T>() + mem::size_of::<T>()
+}
+
+/// Opens a shared memory object, creating it if needed, and maps it into memory
+unsafe fn map(name: &CString, size: usize) -> io::Result<usize> {
+    let fd = libc::shm_open(name.as_ptr(), libc::O_RDWR | libc::O_CREAT, 0o600 as libc::mode_t);
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let result = map_fd(fd, size);
+    libc::close(fd);
+    result
+}
+
+unsafe fn map_fd(fd: libc::c_int, size: usize) -> io::Result<usize> {
+    let mut stat: libc::stat = mem::zeroed();
+    if libc::fstat(fd, &mut stat) != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if (stat.st_size as usize) < size && libc::ftruncate(fd, size as libc::off_t) != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let prot = libc::PROT_READ | libc::PROT_WRITE;
+    match libc::mmap(ptr::null_mut(), size, prot, libc::MAP_SHARED, fd, 0) {
+        libc::MAP_FAILED => Err(io::Error::last_os_error()),
+        addr => Ok(addr as usize),
+    }
+}
+
+/// Returns `true` if the process with an ID has exited
+fn is_dead(pid: libc::pid_t) -> bool {
+    let killed = unsafe { libc::kill(pid, 0) };
+    killed != 0 && io::Error::last_os_error().raw_os_error() == Some(libc::ESRCH)
+}
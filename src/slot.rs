@@ -0,0 +1,106 @@
+use core::fmt::{self, Debug};
+use {Initializer, LazyMut};
+
+/// The storage half of a lazy value, without an initializer
+///
+/// This is created empty, or by `LazyMut::split_init`, which separates a lazy value into its
+/// initializer and the slot where its value lives. The initializer is any `Initializer<T>`, so it
+/// can be registered in one place while the slot is owned by another, and the two are brought
+/// together when the value is needed, either by passing the initializer to `Slot::init` or by
+/// rebuilding the lazy value with `Slot::join`:
+///
+/// ```
+/// use lazy_mut::{LazyMut, Slot};
+///
+/// fn default_theme() -> String {
+///     "light".to_string()
+/// }
+///
+/// let (init, mut slot) = LazyMut::split_init(LazyMut::Init(default_theme));
+/// let init = init.unwrap();
+/// assert!(!slot.is_initialized());
+///
+/// // The storage can be filled by any producer, not just the original initializer
+/// assert_eq!(slot.init(|| "dark".to_string()), "dark");
+/// assert_eq!(*slot.join(init), "dark");
+///
+/// // An empty slot rejoined with its initializer is an uninitialized lazy value again
+/// let lazy = Slot::new().join(init);
+/// assert!(!lazy.is_initialized());
+/// ```
+#[derive(Clone)]
+pub struct Slot<T> {
+    value: Option<T>,
+}
+
+impl<T> Slot<T> {
+    /// Creates a new empty slot
+    pub const fn new() -> Slot<T> {
+        Slot { value: None }
+    }
+
+    /// Creates a new slot that already holds a value
+    pub const fn with_value(val: T) -> Slot<T> {
+        Slot { value: Some(val) }
+    }
+
+    /// Fills the slot by running `init` if it is empty, returning a mutable reference to the value
+    #[track_caller]
+    pub fn init<I>(&mut self, init: I) -> &mut T where I: Initializer<T> {
+        if self.value.is_none() {
+            self.value = Some(::trace::init(|| init.run()));
+        }
+        match self.value {
+            Some(ref mut val) => val,
+            None => unreachable!(),
+        }
+    }
+
+    /// Recombines the slot with an initializer, which is only kept if the slot is empty
+    pub fn join(self, init: fn() -> T) -> LazyMut<T> {
+        match self.value {
+            Some(val) => LazyMut::Value(val),
+            None => LazyMut::Init(init),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the slot is empty
+    ///
+    /// Uses associated function syntax (`Slot::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &Slot<T>) -> Option<&T> {
+        this.value.as_ref()
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the slot is empty
+    ///
+    /// Uses associated function syntax (`Slot::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut Slot<T>) -> Option<&mut T> {
+        this.value.as_mut()
+    }
+
+    /// Returns `true` if the slot holds a value
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.value.is_some()
+    }
+}
+
+/// The initializer and storage of a `LazyMut`, as returned by `LazyMut::split_init`
+pub type SplitInit<T> = (Option<fn() -> T>, Slot<T>);
+
+impl<T> Default for Slot<T> {
+    fn default() -> Slot<T> {
+        Slot::new()
+    }
+}
+
+impl<T> Debug for Slot<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Some(ref val) => f.debug_tuple("Slot").field(val).finish(),
+            None => write!(f, "Slot({{uninitialized}})"),
+        }
+    }
+}
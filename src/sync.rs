@@ -0,0 +1,1335 @@
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+use core::cell::Cell;
+use core::cell::UnsafeCell;
+use core::mem::{self, ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use core::panic::Location;
+use core::ptr;
+#[cfg(feature = "std")]
+use std::boxed::Box;
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+use core::any;
+use {AlreadyInitialized, Initializer, LazyState, LazyValue};
+use location::InitLocation;
+#[cfg(feature = "hooks")]
+use hooks;
+#[cfg(feature = "metrics")]
+use metrics::{self, InitMetrics};
+#[cfg(not(all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+use core::sync::atomic::AtomicU8;
+#[cfg(not(all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+use core::sync::atomic::Ordering::{Acquire, Release};
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const READY: u8 = 2;
+const POISONED: u8 = 3;
+
+/// The initialization state of a `SyncLazy`
+#[cfg(not(all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+struct State(AtomicU8);
+
+#[cfg(not(all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+impl State {
+    const fn new(state: u8) -> State {
+        State(AtomicU8::new(state))
+    }
+
+    #[inline]
+    fn load(&self) -> u8 {
+        self.0.load(Acquire)
+    }
+
+    fn store(&self, state: u8) {
+        self.0.store(state, Release)
+    }
+
+    fn get_mut(&mut self) -> &mut u8 {
+        self.0.get_mut()
+    }
+
+    /// Changes the state from `UNINIT` to `RUNNING`, returning the current state on failure
+    #[cfg(not(feature = "critical-section"))]
+    fn start(&self) -> Result<u8, u8> {
+        self.0.compare_exchange_weak(UNINIT, RUNNING, Acquire, Acquire)
+    }
+}
+
+/// The initialization state of a `SyncLazy`
+///
+/// WebAssembly without the `atomics` target feature can't have multiple threads, so there is no
+/// need for atomic operations.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+struct State(Cell<u8>);
+
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+impl State {
+    const fn new(state: u8) -> State {
+        State(Cell::new(state))
+    }
+
+    #[inline]
+    fn load(&self) -> u8 {
+        self.0.get()
+    }
+
+    fn store(&self, state: u8) {
+        self.0.set(state)
+    }
+
+    fn get_mut(&mut self) -> &mut u8 {
+        self.0.get_mut()
+    }
+
+    /// Changes the state from `UNINIT` to `RUNNING`, returning the current state on failure
+    #[cfg(not(feature = "critical-section"))]
+    fn start(&self) -> Result<u8, u8> {
+        match self.0.get() {
+            UNINIT => Ok(self.0.replace(RUNNING)),
+            state => Err(state),
+        }
+    }
+}
+
+/// A thread-safe lazy value that is initialized on first access
+///
+/// Unlike `LazyMut`, this can be used in a `static` without `static mut`, since it is initialized
+/// through a shared reference. If several threads access it at once, one of them runs the
+/// initializer and the others wait for it to finish. If the initializer panics, the value is
+/// poisoned and any later access will panic as well.
+///
+/// Once initialized, an access is a single acquire load of the state (a plain load on x86) and a
+/// branch that always goes the same way. Initialization itself happens out of line, so accesses
+/// stay small enough to be inlined.
+///
+/// Without the `std` feature, this is available with the `spin` feature, in which case waiting
+/// threads spin instead of yielding to the operating system.
+///
+/// With the `critical-section` feature, initialization instead runs inside of a critical section
+/// from the `critical-section` crate. This makes it safe to share between interrupt handlers and
+/// the main loop on single-core microcontrollers, including those without compare-and-swap
+/// instructions. Interrupts are blocked for as long as the initializer runs.
+///
+/// On WebAssembly targets without the `atomics` target feature, there is only ever one thread, so
+/// a cheaper single-threaded implementation is used.
+///
+/// The initializer is a `fn() -> T` by default, but any `Initializer<T>` can be used instead, such
+/// as a closure or an `extern "C" fn() -> T`. The initializer is dropped once it has been run.
+///
+/// With the `metrics` feature, the time of the first access and how long the initializer took are
+/// recorded as well (see `SyncLazy::metrics`).
+///
+/// In debug builds, the location of the call that initialized the value is tracked (see
+/// `SyncLazy::initialized_at`).
+pub struct SyncLazy<T, F = fn() -> T> {
+    state: State,
+    init: UnsafeCell<MaybeUninit<F>>,
+    value: UnsafeCell<MaybeUninit<T>>,
+    #[cfg(feature = "metrics")]
+    metrics: UnsafeCell<Option<InitMetrics>>,
+    location: UnsafeCell<InitLocation>,
+    name: Option<&'static str>,
+    #[cfg(not(feature = "critical-section"))]
+    pure: bool,
+    #[cfg(feature = "test-support")]
+    has_init: bool,
+}
+
+unsafe impl<T, F> Sync for SyncLazy<T, F> where T: Send + Sync, F: Send {}
+
+impl<T, F> SyncLazy<T, F> where F: Initializer<T> {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> SyncLazy<T, F> {
+        SyncLazy {
+            state: State::new(UNINIT),
+            init: UnsafeCell::new(MaybeUninit::new(init)),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            #[cfg(feature = "metrics")]
+            metrics: UnsafeCell::new(None),
+            location: UnsafeCell::new(InitLocation::new()),
+            name: None,
+            #[cfg(not(feature = "critical-section"))]
+            pure: false,
+            #[cfg(feature = "test-support")]
+            has_init: true,
+        }
+    }
+
+    /// Creates a new uninitialized value with a name and an initializer
+    ///
+    /// The name is shown by `Debug` and in panics, and is recorded in the trace of the initializer
+    /// with the `tracing` feature. Statics declared with the `lazy_mut` macro are named after
+    /// themselves:
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    ///
+    /// static CONFIG: SyncLazy<u32> = SyncLazy::named("CONFIG", || 5);
+    ///
+    /// assert_eq!(SyncLazy::name(&CONFIG), Some("CONFIG"));
+    /// assert_eq!(format!("{:?}", CONFIG), "CONFIG({uninitialized})");
+    /// CONFIG.init();
+    /// assert_eq!(format!("{:?}", CONFIG), "CONFIG(5)");
+    /// ```
+    pub const fn named(name: &'static str, init: F) -> SyncLazy<T, F> {
+        let mut lazy = SyncLazy::new(init);
+        lazy.name = Some(name);
+        lazy
+    }
+
+    /// Creates a new uninitialized value with an initializer that has side effects, so it must be
+    /// run strictly once
+    ///
+    /// This is the same as `SyncLazy::new`, but states the intent at the declaration, next to
+    /// values created with `SyncLazy::pure`.
+    pub const fn effectful(init: F) -> SyncLazy<T, F> {
+        SyncLazy::new(init)
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        match SyncLazy::into_inner(self) {
+            Ok(val) => val,
+            Err(init) => ::trace::init(|| init.run()),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized, returning a reference to it
+    ///
+    /// If another thread is currently initializing the value, this waits for it to finish.
+    #[inline]
+    #[track_caller]
+    pub fn init(&self) -> &T {
+        if self.state.load() != READY {
+            self.initialize();
+        }
+        unsafe { &*(*self.value.get()).as_ptr() }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized, returning a reference to it, unless
+    /// another thread is initializing it and doesn't finish within `timeout`
+    ///
+    /// In that case, `provisional` is returned instead, and the value isn't changed, so a later
+    /// call still returns the real value once it is ready. If this thread starts the initializer,
+    /// it runs to completion however long it takes. This suits code that would rather serve a
+    /// default than stall while another thread warms up the value:
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// static BANNER: SyncLazy<String> = SyncLazy::new(|| {
+    ///     thread::sleep(Duration::from_millis(200));
+    ///     "Welcome back!".to_string()
+    /// });
+    ///
+    /// let warm_up = thread::spawn(|| BANNER.init());
+    /// # use lazy_mut::{LazyState, LazyValue};
+    /// # while BANNER.state() == LazyState::Uninitialized { thread::yield_now(); }
+    ///
+    /// let default = "Welcome!".to_string();
+    /// # #[cfg(not(feature = "critical-section"))]
+    /// assert_eq!(BANNER.get_or_init_with_timeout(Duration::from_millis(1), &default), "Welcome!");
+    ///
+    /// warm_up.join().unwrap();
+    /// assert_eq!(BANNER.get_or_init_with_timeout(Duration::ZERO, &default), "Welcome back!");
+    /// ```
+    ///
+    /// With the `critical-section` feature, the initializer runs inside of a critical section, so
+    /// this waits for it like any other access.
+    ///
+    /// Only available with the `std` feature.
+    #[cfg(feature = "std")]
+    #[track_caller]
+    pub fn get_or_init_with_timeout<'a>(&'a self, timeout: Duration, provisional: &'a T) -> &'a T {
+        if self.state.load() != READY {
+            let start = Instant::now();
+            if self.initialize_until(&|| start.elapsed() >= timeout).is_none() {
+                return provisional;
+            }
+        }
+        unsafe { &*(*self.value.get()).as_ptr() }
+    }
+
+    /// Initializes the wrapped value, panicking if it was already initialized
+    ///
+    /// See `SyncLazy::try_init_once` for how this behaves when called from several threads.
+    #[track_caller]
+    pub fn init_once(&self) -> &T {
+        match self.try_init_once() {
+            Ok(val) => val,
+            Err(err) => ::already_initialized(err.location()),
+        }
+    }
+
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    ///
+    /// Out of any number of calls, from any number of threads, exactly one succeeds: the one that
+    /// runs the initializer. Every other call returns `AlreadyInitialized`, including calls made
+    /// while the initializer is still running, which wait for it to finish first. So once this
+    /// returns, the value is always initialized, whichever way it returned. If the initializer
+    /// panics, the panic unwinds through the call that ran it and the calls waiting on it panic as
+    /// well, since the value is poisoned.
+    ///
+    /// Initializing the value in any other way, such as through `SyncLazy::init` or dereferencing,
+    /// counts as the one successful initialization.
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::thread;
+    ///
+    /// static RUNS: AtomicUsize = AtomicUsize::new(0);
+    /// static CONFIG: SyncLazy<u32> = SyncLazy::new(|| {
+    ///     RUNS.fetch_add(1, Ordering::SeqCst);
+    ///     5
+    /// });
+    ///
+    /// let threads: Vec<_> = (0..8).map(|_| thread::spawn(|| {
+    ///     let result = CONFIG.try_init_once().map(|val| *val);
+    ///     assert!(CONFIG.is_initialized());
+    ///     result
+    /// })).collect();
+    ///
+    /// let results: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    /// assert_eq!(results.iter().filter(|result| **result == Ok(5)).count(), 1);
+    /// assert_eq!(results.iter().filter(|result| result.is_err()).count(), 7);
+    /// assert_eq!(RUNS.load(Ordering::SeqCst), 1);
+    /// assert!(CONFIG.try_init_once().is_err());
+    /// ```
+    #[track_caller]
+    pub fn try_init_once(&self) -> Result<&T, AlreadyInitialized> {
+        if self.state.load() == READY || !self.initialize() {
+            return Err(AlreadyInitialized { location: SyncLazy::initialized_at(self) });
+        }
+        Ok(unsafe { &*(*self.value.get()).as_ptr() })
+    }
+
+    /// Runs the initializer if needed, waiting for it if another thread is running it, and
+    /// returns `true` if this call ran it
+    ///
+    /// Only taking the initializer and storing the value depend on `T` and `F`, so the rest of the
+    /// machinery is shared between every `SyncLazy`.
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) -> bool {
+        self.initialize_until(&|| false) == Some(true)
+    }
+
+    /// Runs the initializer if needed like `SyncLazy::initialize`, but gives up and returns `None`
+    /// if `expired` returns `true` while waiting for another thread
+    #[track_caller]
+    fn initialize_until(&self, expired: &dyn Fn() -> bool) -> Option<bool> {
+        let mut location = InitLocation::new();
+        location.set();
+        #[cfg(not(feature = "critical-section"))]
+        let ran = if self.pure { Some(self.race(location)) } else { self.once(location, expired) };
+        #[cfg(feature = "critical-section")]
+        let ran = self.once(location, expired);
+        #[cfg(feature = "hooks")]
+        {
+            if ran == Some(true) {
+                unsafe { hooks::initialized(self.key(), self.value.get() as *const ()) };
+            }
+        }
+        ran
+    }
+
+    /// Runs the initializer in this thread while the others wait for it
+    #[track_caller]
+    fn once(&self, location: InitLocation, expired: &dyn Fn() -> bool) -> Option<bool> {
+        self.state.call_once(self.name, expired, &mut || {
+            // The initializer is taken first, so that it is dropped if `start_init` panics
+            let init = unsafe { ptr::read((*self.init.get()).as_ptr()) };
+            #[cfg(feature = "registry")]
+            ::registry::start_init(self);
+            let computed = self.compute(init);
+            unsafe { self.store(location, computed) };
+        })
+    }
+
+    /// Runs a copy of the initializer without claiming the value, then stores the result unless
+    /// another thread stored its own result first
+    #[cfg(not(feature = "critical-section"))]
+    #[track_caller]
+    fn race(&self, location: InitLocation) -> bool {
+        match self.state.load() {
+            READY => return false,
+            POISONED => poisoned(self.name),
+            _ => {}
+        }
+        #[cfg(feature = "registry")]
+        ::registry::start_init(self);
+        // Pure values have a `Copy` and `Sync` initializer, so every thread can read its own copy
+        let init = unsafe { ptr::read((*self.init.get()).as_ptr()) };
+        let computed = self.compute(init);
+        loop {
+            match self.state.start() {
+                Ok(_) => {
+                    unsafe { self.store(location, computed) };
+                    self.state.store(READY);
+                    return true;
+                }
+                Err(UNINIT) => {}
+                Err(READY) => return false,
+                Err(POISONED) => poisoned(self.name),
+                Err(_) => wait(),
+            }
+        }
+    }
+
+    /// Runs the initializer, measuring how long it takes with the `metrics` feature
+    fn compute(&self, init: F) -> Computed<T> {
+        let run = || {
+            if let Some(name) = self.name {
+                ::trace::name(name);
+            }
+            init.run()
+        };
+        #[cfg(not(feature = "metrics"))]
+        return Computed { val: ::trace::init(run) };
+        #[cfg(feature = "metrics")]
+        {
+            let (val, metrics) = metrics::measure(|| ::trace::init(run));
+            Computed { val, metrics }
+        }
+    }
+
+    /// Stores the result of the initializer
+    ///
+    /// The state must have been set to `RUNNING` by this thread.
+    unsafe fn store(&self, location: InitLocation, computed: Computed<T>) {
+        *self.location.get() = location;
+        #[cfg(feature = "metrics")]
+        {
+            *self.metrics.get() = Some(computed.metrics);
+        }
+        (*self.value.get()).as_mut_ptr().write(computed.val);
+        #[cfg(feature = "registry")]
+        ::registry::record_init(self);
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl<T, F> SyncLazy<T, F> where T: 'static {
+    /// Registers a function to be called with the value once it has been initialized
+    ///
+    /// The function is called by the thread that ran the initializer, right after it finished. If
+    /// the value has already been initialized, the function is called right away instead.
+    ///
+    /// Since the value lives in the static, the function receives a `&'static T`, which it can
+    /// keep. This allows intrusive patterns where a value is registered by its address once it
+    /// exists:
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    /// use std::sync::Mutex;
+    ///
+    /// struct Device {
+    ///     name: &'static str,
+    /// }
+    ///
+    /// static DEVICES: Mutex<Vec<&'static Device>> = Mutex::new(Vec::new());
+    /// static DISK: SyncLazy<Device> = SyncLazy::new(|| Device { name: "disk" });
+    ///
+    /// DISK.on_init(|device| DEVICES.lock().unwrap().push(device));
+    ///
+    /// DISK.init();
+    /// assert_eq!(DEVICES.lock().unwrap()[0].name, "disk");
+    /// ```
+    ///
+    /// A kept reference must not be used after the value is dropped by `SyncLazy::reset` or
+    /// `registry::shutdown`. Only available with the `hooks` feature.
+    pub fn on_init<H>(&'static self, hook: H) where H: Fn(&'static T) + Send + Sync + 'static {
+        let hook = unsafe { hooks::erase::<T, H>(hook) };
+        if hooks::register(self.key(), hook.clone()) {
+            hook(self.value.get() as *const ());
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> SyncLazy<T, F> where T: Send + Sync, F: Initializer<T> + Send {
+    /// Starts running the initializer on a new thread, returning a handle to the thread
+    ///
+    /// Accessing the value waits for the initializer to finish, while `SyncLazy::get` returns
+    /// `None` until it has, so expensive values can be warmed up during startup without blocking
+    /// the main thread:
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    ///
+    /// static SQUARES: SyncLazy<Vec<u64>> = SyncLazy::new(|| (0..1000).map(|n| n * n).collect());
+    ///
+    /// let handle = SQUARES.start_init_in_background();
+    ///
+    /// // ... the rest of startup runs here ...
+    ///
+    /// assert_eq!(SQUARES[12], 144);
+    /// handle.join().unwrap();
+    /// ```
+    ///
+    /// If the value is already being initialized, the thread waits for it like any other access.
+    /// Only available with the `std` feature.
+    pub fn start_init_in_background(&'static self) -> thread::JoinHandle<()> {
+        thread::Builder::new()
+            .name("lazy_mut-init".into())
+            .spawn(move || {
+                self.init();
+            })
+            .expect("failed to spawn thread")
+    }
+
+    /// Starts running the initializer with a function that spawns it, such as on a thread pool
+    ///
+    /// `spawn` is called right away with a task that initializes the value. Only available with
+    /// the `std` feature.
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    /// use std::thread;
+    ///
+    /// static GREETING: SyncLazy<String> = SyncLazy::new(|| "hello".to_string());
+    ///
+    /// GREETING.start_init_with(|task| {
+    ///     thread::spawn(task);
+    /// });
+    /// assert_eq!(*GREETING, "hello");
+    /// ```
+    pub fn start_init_with<S>(&'static self, spawn: S) where S: FnOnce(Box<dyn FnOnce() + Send>) {
+        spawn(Box::new(move || {
+            self.init();
+        }));
+    }
+}
+
+impl<T, F> SyncLazy<T, F> where F: Initializer<T> + Copy {
+    /// Creates a new uninitialized value with an initializer that has no side effects, so it may
+    /// be run more than once
+    ///
+    /// Instead of waiting while one thread runs the initializer, every thread that finds the value
+    /// uninitialized runs its own copy of the initializer, and the first result to be stored is
+    /// kept while the others are dropped. No thread ever waits on another thread's initializer, and
+    /// a panic in the initializer doesn't poison the value, since the next access just runs it
+    /// again. This is only correct for initializers that don't open files, spawn threads or have
+    /// any other effect that must happen once, which should use `SyncLazy::effectful` instead.
+    /// Since every thread copies the initializer out of the shared value, it must be `Sync`.
+    ///
+    /// With the `critical-section` feature, the initializer still runs inside of a critical
+    /// section, so it is only ever run once.
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    /// use std::thread;
+    ///
+    /// static PRIMES: SyncLazy<Vec<u32>> = SyncLazy::pure(|| {
+    ///     (2..50).filter(|n| (2..*n).all(|d| n % d != 0)).collect()
+    /// });
+    ///
+    /// let threads: Vec<_> = (0..4).map(|_| thread::spawn(|| PRIMES.len())).collect();
+    /// for thread in threads {
+    ///     assert_eq!(thread.join().unwrap(), 15);
+    /// }
+    /// ```
+    pub const fn pure(init: F) -> SyncLazy<T, F> where F: Sync {
+        #[cfg_attr(feature = "critical-section", allow(unused_mut))]
+        let mut lazy = SyncLazy::new(init);
+        #[cfg(not(feature = "critical-section"))]
+        {
+            lazy.pure = true;
+        }
+        lazy
+    }
+
+    /// Runs the initializer and returns its result, leaving the value uninitialized
+    ///
+    /// This is meant for checking ahead of time that the initializer works, without changing when
+    /// the value is actually initialized. If another thread starts initializing the value at the
+    /// same time, both run the initializer, but only the other thread's result is stored. This
+    /// panics if the value has already started being initialized, or was created with a value.
+    /// Since the initializer is copied out of the shared value, it must be `Sync`.
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    ///
+    /// static LIMIT: SyncLazy<u32> = SyncLazy::new(|| "16".parse().unwrap());
+    ///
+    /// assert_eq!(LIMIT.peek_init(), 16);
+    /// assert!(!LIMIT.is_initialized());
+    /// ```
+    #[track_caller]
+    pub fn peek_init(&self) -> T where F: Sync {
+        if self.state.load() != UNINIT {
+            ::already_peeked();
+        }
+        // Since `F` is `Copy`, the initializer stays in place even if another thread takes it now
+        let init = unsafe { ptr::read((*self.init.get()).as_ptr()) };
+        init.run()
+    }
+}
+
+#[cfg(feature = "test-support")]
+impl<T, F> SyncLazy<T, F> where F: Initializer<T> + Copy {
+    /// Drops the value if it is initialized, so that the next access runs the initializer again
+    ///
+    /// This also clears a poisoned value. A value created with `SyncLazy::with_value` has no
+    /// initializer to run again, so it is left as it is:
+    ///
+    /// ```
+    /// use lazy_mut::SyncLazy;
+    ///
+    /// static STARTED: SyncLazy<u32> = SyncLazy::new(|| 5);
+    /// static DEFAULT: SyncLazy<u32> = SyncLazy::with_value(7);
+    ///
+    /// STARTED.init();
+    /// unsafe {
+    ///     STARTED.reset();
+    ///     DEFAULT.reset();
+    /// }
+    /// assert!(!STARTED.is_initialized());
+    /// assert_eq!(*DEFAULT, 7);
+    /// ```
+    ///
+    /// Only available with the `test-support` feature.
+    ///
+    /// # Safety
+    ///
+    /// No references to the value may be alive, and no other thread may access it until this
+    /// returns.
+    pub unsafe fn reset(&self) {
+        if !self.has_init {
+            return;
+        }
+        // Since `F` is `Copy`, running the initializer left it in place, so it can be run again
+        if self.state.load() == READY {
+            ptr::drop_in_place((*self.value.get()).as_mut_ptr());
+        }
+        #[cfg(feature = "metrics")]
+        {
+            *self.metrics.get() = None;
+        }
+        *self.location.get() = InitLocation::new();
+        #[cfg(feature = "hooks")]
+        hooks::reset(self.key());
+        self.state.store(UNINIT);
+    }
+}
+
+#[cfg(feature = "registry")]
+impl<T, F> SyncLazy<T, F> {
+    /// Drops the value if it is initialized, leaving it poisoned so that it can't be used again
+    ///
+    /// This is what `registry::shutdown` does to each registered lazy static. Only available with
+    /// the `registry` feature.
+    ///
+    /// # Safety
+    ///
+    /// No references to the value may be alive, and no other thread may access it until this
+    /// returns.
+    pub unsafe fn finalize(&self) {
+        if self.state.load() == READY {
+            self.state.store(POISONED);
+            ptr::drop_in_place((*self.value.get()).as_mut_ptr());
+        }
+    }
+}
+
+#[cfg(feature = "test-support")]
+impl<T, F> SyncLazy<T, F> {
+    /// Replaces the value with `value` until the returned guard is dropped
+    ///
+    /// When the guard is dropped, the value is put back the way it was before, so an uninitialized
+    /// value will run its initializer on the next access. Only available with the `test-support`
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// No references to the value may be alive when this is called or when the guard is dropped,
+    /// and no other thread may access it during either call.
+    pub unsafe fn override_for_test(&self, value: T) -> Override<'_, T, F> {
+        let state = self.state.load();
+        let previous = match state {
+            READY => Some(ptr::read((*self.value.get()).as_ptr())),
+            _ => None,
+        };
+        (*self.value.get()).as_mut_ptr().write(value);
+        self.state.store(READY);
+        Override { lazy: self, state, previous }
+    }
+}
+
+/// A guard that restores a `SyncLazy` when dropped, returned by `SyncLazy::override_for_test`
+#[cfg(feature = "test-support")]
+pub struct Override<'a, T: 'a, F: 'a = fn() -> T> {
+    lazy: &'a SyncLazy<T, F>,
+    state: u8,
+    previous: Option<T>,
+}
+
+#[cfg(feature = "test-support")]
+impl<'a, T, F> Drop for Override<'a, T, F> {
+    fn drop(&mut self) {
+        let lazy = self.lazy;
+        unsafe {
+            if lazy.state.load() == READY {
+                ptr::drop_in_place((*lazy.value.get()).as_mut_ptr());
+            }
+            if let Some(previous) = self.previous.take() {
+                (*lazy.value.get()).as_mut_ptr().write(previous);
+            }
+        }
+        lazy.state.store(self.state);
+    }
+}
+
+impl<T, F> SyncLazy<T, F> {
+    /// Creates a new value that is already initialized
+    pub const fn with_value(val: T) -> SyncLazy<T, F> {
+        SyncLazy {
+            state: State::new(READY),
+            init: UnsafeCell::new(MaybeUninit::uninit()),
+            value: UnsafeCell::new(MaybeUninit::new(val)),
+            #[cfg(feature = "metrics")]
+            metrics: UnsafeCell::new(None),
+            location: UnsafeCell::new(InitLocation::new()),
+            name: None,
+            #[cfg(not(feature = "critical-section"))]
+            pure: false,
+            #[cfg(feature = "test-support")]
+            has_init: false,
+        }
+    }
+
+    /// Returns the name given with `SyncLazy::named`, if there is one
+    ///
+    /// Uses associated function syntax (`SyncLazy::name(&VAL)`)
+    #[inline]
+    pub fn name(this: &SyncLazy<T, F>) -> Option<&'static str> {
+        this.name
+    }
+
+    /// Returns the wrapped value, or the initializer if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazy::into_inner(VAL)`)
+    #[track_caller]
+    pub fn into_inner(this: SyncLazy<T, F>) -> Result<T, F> {
+        let this = ManuallyDrop::new(this);
+        match this.state.load() {
+            READY => Ok(unsafe { ptr::read((*this.value.get()).as_ptr()) }),
+            POISONED => poisoned(this.name),
+            _ => Err(unsafe { ptr::read((*this.init.get()).as_ptr()) }),
+        }
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &SyncLazy<T, F>) -> Option<&T> {
+        if this.state.load() == READY {
+            Some(unsafe { &*(*this.value.get()).as_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazy::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut SyncLazy<T, F>) -> Option<&mut T> {
+        if *this.state.get_mut() == READY {
+            Some(unsafe { &mut *(*this.value.get()).as_mut_ptr() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.state.load() == READY
+    }
+
+    /// Returns the key of this value in the table of hooks
+    #[cfg(feature = "hooks")]
+    fn key(&self) -> usize {
+        hooks::key(self)
+    }
+
+    /// Returns the location of the call that initialized the value
+    ///
+    /// This is only tracked in debug builds, so it always returns `None` in release builds.
+    ///
+    /// Uses associated function syntax (`SyncLazy::initialized_at(&VAL)`)
+    pub fn initialized_at(this: &SyncLazy<T, F>) -> Option<&'static Location<'static>> {
+        if this.state.load() == READY {
+            unsafe { (*this.location.get()).get() }
+        } else {
+            None
+        }
+    }
+
+    /// Returns the metrics recorded during initialization, or `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazy::metrics(&VAL)`)
+    #[cfg(feature = "metrics")]
+    pub fn metrics(this: &SyncLazy<T, F>) -> Option<InitMetrics> {
+        if this.state.load() == READY {
+            unsafe { *this.metrics.get() }
+        } else {
+            None
+        }
+    }
+}
+
+impl State {
+    #[cfg(not(feature = "critical-section"))]
+    #[track_caller]
+    fn call_once(&self, name: Option<&'static str>, expired: &dyn Fn() -> bool,
+        init: &mut dyn FnMut()) -> Option<bool>
+    {
+        loop {
+            match self.start() {
+                Ok(_) => {
+                    self.run(init);
+                    return Some(true);
+                }
+                Err(READY) => return Some(false),
+                Err(POISONED) => poisoned(name),
+                Err(UNINIT) => {}
+                Err(_) if expired() => return None,
+                Err(_) => wait(),
+            }
+        }
+    }
+
+    /// Runs the initializer inside of a critical section, so it can't be interrupted
+    ///
+    /// Another thread can't be running the initializer, so this never waits and `expired` is never
+    /// called.
+    #[cfg(feature = "critical-section")]
+    #[track_caller]
+    fn call_once(&self, name: Option<&'static str>, _expired: &dyn Fn() -> bool,
+        init: &mut dyn FnMut()) -> Option<bool>
+    {
+        ::critical_section::with(|_| {
+            match self.load() {
+                UNINIT => {
+                    self.store(RUNNING);
+                    self.run(init);
+                    Some(true)
+                }
+                READY => Some(false),
+                POISONED => poisoned(name),
+                _ => panic!("lazy value accessed during its own initialization"),
+            }
+        })
+    }
+
+    /// Changes the state from `UNINIT` to `RUNNING` for `InitDriver`, returning `false` if it was
+    /// in any other state
+    #[cfg(not(feature = "critical-section"))]
+    fn begin(&self) -> bool {
+        loop {
+            match self.start() {
+                Ok(_) => return true,
+                Err(UNINIT) => {}
+                Err(_) => return false,
+            }
+        }
+    }
+
+    /// Changes the state from `UNINIT` to `RUNNING` for `InitDriver`, returning `false` if it was
+    /// in any other state
+    #[cfg(feature = "critical-section")]
+    fn begin(&self) -> bool {
+        ::critical_section::with(|_| {
+            if self.load() == UNINIT {
+                self.store(RUNNING);
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Runs the initializer, after the state has been set to `RUNNING` by this thread
+    fn run(&self, init: &mut dyn FnMut()) {
+        let poison = Poison(self);
+        init();
+        mem::forget(poison);
+        self.store(READY);
+    }
+}
+
+/// The result of an initializer, which isn't stored until the value has been claimed
+struct Computed<T> {
+    val: T,
+    #[cfg(feature = "metrics")]
+    metrics: InitMetrics,
+}
+
+/// Marks a value as poisoned if its initializer unwinds
+struct Poison<'a>(&'a State);
+
+impl<'a> Drop for Poison<'a> {
+    fn drop(&mut self) {
+        self.0.store(POISONED);
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn poisoned(name: Option<&'static str>) -> ! {
+    match name {
+        Some(name) => panic!("initializer of lazy value `{}` previously panicked", name),
+        None => panic!("initializer of lazy value previously panicked"),
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "critical-section"),
+    not(all(target_arch = "wasm32", not(target_feature = "atomics")))))]
+fn wait() {
+    ::std::thread::yield_now();
+}
+
+#[cfg(all(not(any(feature = "std", feature = "critical-section")),
+    not(all(target_arch = "wasm32", not(target_feature = "atomics")))))]
+fn wait() {
+    core::hint::spin_loop();
+}
+
+/// Since there is only one thread, another initializer can only be running if it is this one
+#[cfg(all(not(feature = "critical-section"),
+    all(target_arch = "wasm32", not(target_feature = "atomics"))))]
+#[track_caller]
+fn wait() {
+    panic!("lazy value accessed during its own initialization")
+}
+
+impl<T, F> Drop for SyncLazy<T, F> {
+    fn drop(&mut self) {
+        match *self.state.get_mut() {
+            UNINIT => unsafe { ptr::drop_in_place((*self.init.get()).as_mut_ptr()) },
+            READY => unsafe { ptr::drop_in_place((*self.value.get()).as_mut_ptr()) },
+            _ => {}
+        }
+    }
+}
+
+impl<T, F> Deref for SyncLazy<T, F> where F: Initializer<T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.init()
+    }
+}
+
+impl<T, F> DerefMut for SyncLazy<T, F> where F: Initializer<T> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        if *self.state.get_mut() != READY {
+            ::trace::implicit_init::<T>();
+        }
+        self.init();
+        match SyncLazy::get_mut(self) {
+            Some(val) => val,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl<T, F> SyncLazy<T, F> {
+    /// Returns how far the value has been initialized
+    fn lazy_state(&self) -> LazyState {
+        match self.state.load() {
+            UNINIT => LazyState::Uninitialized,
+            RUNNING => LazyState::Initializing,
+            READY => LazyState::Initialized,
+            _ => LazyState::Poisoned,
+        }
+    }
+
+    /// Writes a one-line summary of the state of the value, used by the alternate `Display` format
+    fn summary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self.lazy_state() {
+            LazyState::Uninitialized => "uninitialized",
+            LazyState::Initializing => "initializing",
+            LazyState::Initialized => "initialized",
+            LazyState::Poisoned => "poisoned",
+        };
+        write!(f, "{}: {}", self.name.unwrap_or_else(any::type_name::<T>), state)?;
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(metrics) = SyncLazy::metrics(self) {
+                write!(f, " in {:?}", metrics.duration())?;
+                if let Ok(elapsed) = metrics.first_access().elapsed() {
+                    write!(f, ", first accessed {:?} ago", elapsed)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// In the alternate format (`{:#?}`), the name and state of the value are shown as well, along
+/// with its metrics when the `metrics` feature is enabled.
+impl<T, F> Debug for SyncLazy<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name.unwrap_or("SyncLazy");
+        if f.alternate() {
+            let mut debug = f.debug_struct(name);
+            debug.field("state", &self.lazy_state());
+            if let Some(val) = SyncLazy::get(self) {
+                debug.field("value", val);
+            }
+            #[cfg(feature = "metrics")]
+            {
+                if let Some(metrics) = SyncLazy::metrics(self) {
+                    debug.field("duration", &metrics.duration());
+                    debug.field("first_access", &metrics.first_access());
+                }
+            }
+            return debug.finish();
+        }
+        match SyncLazy::get(self) {
+            Some(val) => f.debug_tuple(name).field(val).finish(),
+            None => write!(f, "{}({{uninitialized}})", name),
+        }
+    }
+}
+
+/// In the alternate format (`{:#}`), a one-line summary of the state of the value is written
+/// instead of the value itself, which includes how long it took to initialize and when it was
+/// first accessed when the `metrics` feature is enabled:
+///
+/// ```
+/// use lazy_mut::SyncLazy;
+///
+/// static CONFIG: SyncLazy<u32> = SyncLazy::named("CONFIG", || 5);
+///
+/// assert_eq!(format!("{:#}", CONFIG), "CONFIG: uninitialized");
+/// CONFIG.init();
+/// assert_eq!(format!("{}", CONFIG), "5");
+/// assert!(format!("{:#}", CONFIG).starts_with("CONFIG: initialized"));
+/// ```
+impl<T, F> Display for SyncLazy<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            return self.summary(f);
+        }
+        match SyncLazy::get(self) {
+            Some(val) => val.fmt(f),
+            None => write!(f, "{{uninitialized}}"),
+        }
+    }
+}
+
+impl<T, F> LazyValue for SyncLazy<T, F> where F: Initializer<T> {
+    fn name(&self) -> &'static str {
+        self.name.unwrap_or_else(any::type_name::<T>)
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        self.lazy_state()
+    }
+}
+
+/// A `SyncLazy` that is known to be initialized
+///
+/// A reference to one is obtained from `SyncLazy::initialized` or `SyncLazy::as_initialized`, which
+/// check that the value is initialized once, after which accessing the value through the reference
+/// doesn't check anything. Code that runs after startup can take `&'static Initialized<T>` instead
+/// of `&'static SyncLazy<T>`, so that the type system guarantees the value was initialized:
+///
+/// ```
+/// use lazy_mut::{Initialized, SyncLazy};
+///
+/// static WORDS: SyncLazy<Vec<&str>> = SyncLazy::new(|| vec!["lazy", "mut"]);
+///
+/// fn count_letters(words: &Initialized<Vec<&str>>) -> usize {
+///     words.iter().map(|word| word.len()).sum()
+/// }
+///
+/// assert!(SyncLazy::as_initialized(&WORDS).is_none());
+/// let words: &'static Initialized<Vec<&str>> = WORDS.initialized();
+/// assert_eq!(count_letters(words), 7);
+/// ```
+#[repr(transparent)]
+pub struct Initialized<T, F = fn() -> T>(SyncLazy<T, F>);
+
+impl<T, F> SyncLazy<T, F> where F: Initializer<T> {
+    /// Initializes the wrapped value if it is uninitialized, returning a reference that can access
+    /// it without checking again
+    #[inline]
+    #[track_caller]
+    pub fn initialized(&self) -> &Initialized<T, F> {
+        self.init();
+        unsafe { Initialized::new_unchecked(self) }
+    }
+}
+
+impl<T, F> SyncLazy<T, F> {
+    /// Tries to get a reference that can access the value without checking again, returns `None`
+    /// if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazy::as_initialized(&VAL)`)
+    #[inline]
+    pub fn as_initialized(this: &SyncLazy<T, F>) -> Option<&Initialized<T, F>> {
+        if this.state.load() == READY {
+            Some(unsafe { Initialized::new_unchecked(this) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, F> Initialized<T, F> {
+    /// Treats a `SyncLazy` as initialized without checking that it is
+    ///
+    /// # Safety
+    ///
+    /// The value must be initialized, and stay initialized for as long as the reference is alive.
+    #[inline]
+    pub unsafe fn new_unchecked(lazy: &SyncLazy<T, F>) -> &Initialized<T, F> {
+        &*(lazy as *const SyncLazy<T, F> as *const Initialized<T, F>)
+    }
+
+    /// Returns a reference to the value
+    ///
+    /// Uses associated function syntax (`Initialized::get(VAL)`)
+    #[inline]
+    pub fn get(this: &Initialized<T, F>) -> &T {
+        unsafe { &*(*this.0.value.get()).as_ptr() }
+    }
+
+    /// Returns the `SyncLazy` that this refers to
+    ///
+    /// Uses associated function syntax (`Initialized::lazy(VAL)`)
+    #[inline]
+    pub fn lazy(this: &Initialized<T, F>) -> &SyncLazy<T, F> {
+        &this.0
+    }
+}
+
+impl<T, F> Deref for Initialized<T, F> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        Initialized::get(self)
+    }
+}
+
+impl<T, F> Debug for Initialized<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Initialized").field(&**self).finish()
+    }
+}
+
+impl<T, F> Display for Initialized<T, F> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Initialized::get(self).fmt(f)
+    }
+}
+
+/// A step of the state machine of a `SyncLazy`
+///
+/// Every `SyncLazy` moves through its states like this, where each arrow is a step:
+///
+/// ```text
+///                  Begin                  Complete
+/// Uninitialized ----------> Initializing ----------> Initialized
+///                                |
+///                                |  Panic
+///                                v
+///                             Poisoned
+/// ```
+///
+/// An access in the `Uninitialized` state begins initializing the value and then completes it,
+/// or panics if the initializer does. An access in the `Initializing` state waits for another
+/// thread to leave it, one in the `Initialized` state returns the value, and one in the `Poisoned`
+/// state panics. No other steps are possible, so `Initialized` and `Poisoned` are final.
+///
+/// `InitStep::apply` computes these transitions without touching a value, to serve as a model,
+/// while `InitDriver` takes a real `SyncLazy` through them one step at a time, so fuzzers and
+/// model checkers can compare the two after every step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InitStep {
+    /// Claims the value, as the thread that will run the initializer
+    Begin,
+    /// Runs the initializer and stores the value
+    Complete,
+    /// Gives up on initializing the value, as if the initializer panicked
+    Panic,
+}
+
+impl InitStep {
+    /// Returns the state that this step leads to from `state`, or an error if it isn't possible
+    pub fn apply(self, state: LazyState) -> Result<LazyState, InvalidStep> {
+        match (state, self) {
+            (LazyState::Uninitialized, InitStep::Begin) => Ok(LazyState::Initializing),
+            (LazyState::Initializing, InitStep::Complete) => Ok(LazyState::Initialized),
+            (LazyState::Initializing, InitStep::Panic) => Ok(LazyState::Poisoned),
+            (state, step) => Err(InvalidStep { state, step }),
+        }
+    }
+}
+
+/// An error from taking a step that isn't possible in the current state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidStep {
+    state: LazyState,
+    step: InitStep,
+}
+
+impl InvalidStep {
+    /// Returns the state that the step was taken in
+    pub fn state(&self) -> LazyState {
+        self.state
+    }
+
+    /// Returns the step that was taken
+    pub fn step(&self) -> InitStep {
+        self.step
+    }
+}
+
+impl Display for InvalidStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "can't take step {:?} in state {:?}", self.step, self.state)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for InvalidStep {}
+
+/// Takes a `SyncLazy` through its states one step at a time
+///
+/// Each call to `InitDriver::step` takes exactly one of the steps described by `InitStep`, on the
+/// real value, so the order of steps is up to the caller instead of the scheduler:
+///
+/// ```
+/// use lazy_mut::{InitDriver, InitStep, LazyState, SyncLazy};
+///
+/// let lazy: SyncLazy<u32> = SyncLazy::new(|| 5);
+/// let mut driver = InitDriver::new(&lazy);
+///
+/// assert_eq!(driver.step(InitStep::Begin), Ok(LazyState::Initializing));
+/// assert_eq!(SyncLazy::get(&lazy), None);
+/// assert!(driver.step(InitStep::Begin).is_err());
+/// assert_eq!(driver.step(InitStep::Complete), Ok(LazyState::Initialized));
+/// assert_eq!(SyncLazy::get(&lazy), Some(&5));
+/// ```
+///
+/// While the driver holds the value in the `Initializing` state, other threads that access it
+/// wait, just as they would for a running initializer. With the `critical-section` feature,
+/// nothing ever waits for an initializer, since it runs entirely inside of a critical section, so
+/// an access in that state panics instead, as if it came from the initializer itself:
+///
+/// ```
+/// # #[cfg(feature = "critical-section")]
+/// # fn main() {
+/// use lazy_mut::{InitDriver, InitStep, SyncLazy};
+/// use std::panic;
+///
+/// let lazy: SyncLazy<u32> = SyncLazy::new(|| 5);
+/// let mut driver = InitDriver::new(&lazy);
+/// driver.step(InitStep::Begin).unwrap();
+///
+/// assert!(panic::catch_unwind(panic::AssertUnwindSafe(|| *lazy)).is_err());
+/// # }
+/// # #[cfg(not(feature = "critical-section"))]
+/// # fn main() {}
+/// ```
+///
+/// If the driver is dropped in the `Initializing` state, the value is poisoned, as if the
+/// initializer had panicked. If the initializer panics during `InitStep::Complete`, the value is
+/// poisoned and the panic is propagated.
+pub struct InitDriver<'a, T: 'a, F: 'a = fn() -> T> {
+    lazy: &'a SyncLazy<T, F>,
+    init: Option<F>,
+}
+
+impl<'a, T, F> InitDriver<'a, T, F> where F: Initializer<T> {
+    /// Creates a driver for a value
+    pub fn new(lazy: &'a SyncLazy<T, F>) -> InitDriver<'a, T, F> {
+        InitDriver { lazy, init: None }
+    }
+
+    /// Returns the current state of the value
+    pub fn state(&self) -> LazyState {
+        self.lazy.lazy_state()
+    }
+
+    /// Takes a step, returning the new state of the value
+    ///
+    /// This returns an error without changing anything if the step isn't possible in the current
+    /// state, or if the value is `Initializing` because of another thread or driver, which this
+    /// driver can't complete.
+    #[track_caller]
+    pub fn step(&mut self, step: InitStep) -> Result<LazyState, InvalidStep> {
+        let state = self.state();
+        let next = step.apply(state)?;
+        let invalid = InvalidStep { state, step };
+        match step {
+            InitStep::Begin => {
+                if !self.lazy.state.begin() {
+                    return Err(invalid);
+                }
+                self.init = Some(unsafe { ptr::read((*self.lazy.init.get()).as_ptr()) });
+            }
+            InitStep::Complete => {
+                let mut init = Some(self.init.take().ok_or(invalid)?);
+                let mut location = InitLocation::new();
+                location.set();
+                let lazy = self.lazy;
+                lazy.state.run(&mut || {
+                    #[cfg(feature = "registry")]
+                    ::registry::start_init(lazy);
+                    let computed = lazy.compute(init.take().unwrap());
+                    unsafe { lazy.store(location, computed) };
+                });
+                #[cfg(feature = "hooks")]
+                unsafe { hooks::initialized(self.lazy.key(), self.lazy.value.get() as *const ()) };
+            }
+            InitStep::Panic => {
+                self.init.take().ok_or(invalid)?;
+                self.lazy.state.store(POISONED);
+            }
+        }
+        Ok(next)
+    }
+}
+
+impl<'a, T, F> Drop for InitDriver<'a, T, F> {
+    fn drop(&mut self) {
+        if self.init.take().is_some() {
+            self.lazy.state.store(POISONED);
+        }
+    }
+}
+
+#[cfg(feature = "embedded-logging")]
+impl<T, F> ::defmt::Format for SyncLazy<T, F> where T: ::defmt::Format {
+    fn format(&self, f: ::defmt::Formatter) {
+        match SyncLazy::get(self) {
+            Some(val) => val.format(f),
+            None => ::defmt::write!(f, "{{uninitialized}}"),
+        }
+    }
+}
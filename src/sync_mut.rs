@@ -0,0 +1,198 @@
+use core::mem;
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+use core::any;
+use {Initializer, LazyState, LazyValue, SyncLazy};
+#[cfg(feature = "hooks")]
+use hooks;
+
+/// A thread-safe lazy value that can be modified through a shared reference
+///
+/// The value is stored behind a `RwLock` that is created along with it on first access, so it can
+/// be used in a `static` to hold global state that changes over time, such as a policy that can be
+/// reloaded. A panic while holding a write guard does not poison the value; later accesses see
+/// whatever the panicking thread left behind.
+///
+/// `swap_scoped` temporarily replaces the value, putting the previous one back when the returned
+/// guard is dropped, even if the scope is left by panicking:
+///
+/// ```
+/// use lazy_mut::SyncLazyMut;
+///
+/// static RATE_LIMIT: SyncLazyMut<u32> = SyncLazyMut::new(|| 100);
+///
+/// {
+///     let _guard = RATE_LIMIT.swap_scoped(10);
+///     assert_eq!(*RATE_LIMIT.read(), 10);
+/// }
+/// assert_eq!(*RATE_LIMIT.read(), 100);
+///
+/// *RATE_LIMIT.write() += 1;
+/// assert_eq!(*RATE_LIMIT.read(), 101);
+/// ```
+///
+/// Only available with the `std` feature.
+pub struct SyncLazyMut<T, F = fn() -> T> {
+    lazy: SyncLazy<RwLock<T>, Locked<F>>,
+}
+
+/// An initializer that wraps the value from another initializer in a lock
+struct Locked<F>(F);
+
+impl<T, F> Initializer<RwLock<T>> for Locked<F> where F: Initializer<T> {
+    fn run(self) -> RwLock<T> {
+        RwLock::new(self.0.run())
+    }
+}
+
+impl<T, F> SyncLazyMut<T, F> where F: Initializer<T> {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> SyncLazyMut<T, F> {
+        SyncLazyMut { lazy: SyncLazy::new(Locked(init)) }
+    }
+
+    /// Returns the wrapped value, initializing if needed
+    pub fn unwrap(self) -> T {
+        match self.lazy.unwrap().into_inner() {
+            Ok(val) => val,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized
+    #[inline]
+    #[track_caller]
+    pub fn init(&self) -> &SyncLazyMut<T, F> {
+        self.lazy.init();
+        self
+    }
+
+    /// Locks the value for reading, initializing it if needed
+    ///
+    /// This blocks while another thread holds a write guard.
+    #[track_caller]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        match self.lazy.init().read() {
+            Ok(guard) => guard,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Locks the value for writing, initializing it if needed
+    ///
+    /// This blocks while any other guard is held.
+    #[track_caller]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        match self.lazy.init().write() {
+            Ok(guard) => guard,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Replaces the value, returning the previous one
+    #[track_caller]
+    pub fn replace(&self, value: T) -> T {
+        let previous = mem::replace(&mut *self.write(), value);
+        #[cfg(feature = "hooks")]
+        {
+            if let Some(lock) = SyncLazy::get(&self.lazy) {
+                let val = lock as *const RwLock<T> as *const ();
+                unsafe { hooks::replaced(hooks::key(&self.lazy), val) };
+            }
+        }
+        previous
+    }
+
+    /// Replaces the value until the returned guard is dropped, when the previous value is put back
+    #[track_caller]
+    pub fn swap_scoped(&self, value: T) -> RestoreGuard<'_, T, F> {
+        RestoreGuard { lazy: self, previous: Some(self.replace(value)) }
+    }
+}
+
+#[cfg(feature = "hooks")]
+impl<T, F> SyncLazyMut<T, F> where T: 'static {
+    /// Registers a function to be called with the value once it has been initialized, and again
+    /// every time it is replaced
+    ///
+    /// Modifications made through `write` don't call the function, only `replace` and
+    /// `swap_scoped` (including when the previous value is put back) do. If the value has already
+    /// been initialized, the function is called right away. Only available with the `hooks`
+    /// feature.
+    pub fn on_change<H>(&'static self, hook: H) where H: Fn(&T) + Send + Sync + 'static {
+        self.lazy.on_init(move |lock: &RwLock<T>| match lock.read() {
+            Ok(val) => hook(&val),
+            Err(err) => hook(&err.into_inner()),
+        });
+    }
+}
+
+impl<T, F> SyncLazyMut<T, F> {
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`SyncLazyMut::get_mut(&mut VAL)`)
+    pub fn get_mut(this: &mut SyncLazyMut<T, F>) -> Option<&mut T> {
+        SyncLazy::get_mut(&mut this.lazy).map(|lock| match lock.get_mut() {
+            Ok(val) => val,
+            Err(err) => err.into_inner(),
+        })
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.lazy.is_initialized()
+    }
+}
+
+impl<T, F> LazyValue for SyncLazyMut<T, F> where F: Initializer<T> {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        self.lazy.state()
+    }
+}
+
+impl<T, F> Debug for SyncLazyMut<T, F> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match SyncLazy::get(&self.lazy).map(RwLock::try_read) {
+            Some(Ok(val)) => f.debug_tuple("SyncLazyMut").field(&*val).finish(),
+            Some(Err(TryLockError::Poisoned(err))) => {
+                f.debug_tuple("SyncLazyMut").field(&*err.into_inner()).finish()
+            }
+            Some(Err(TryLockError::WouldBlock)) => write!(f, "SyncLazyMut({{locked}})"),
+            None => write!(f, "SyncLazyMut({{uninitialized}})"),
+        }
+    }
+}
+
+/// A guard that puts back the previous value of a `SyncLazyMut` when dropped
+///
+/// Returned by `SyncLazyMut::swap_scoped`. The temporary value can be accessed through the
+/// `SyncLazyMut` as usual, or through the guard itself.
+pub struct RestoreGuard<'a, T: 'a, F: 'a = fn() -> T> where F: Initializer<T> {
+    lazy: &'a SyncLazyMut<T, F>,
+    previous: Option<T>,
+}
+
+impl<'a, T, F> Deref for RestoreGuard<'a, T, F> where F: Initializer<T> {
+    type Target = SyncLazyMut<T, F>;
+    fn deref(&self) -> &SyncLazyMut<T, F> {
+        self.lazy
+    }
+}
+
+impl<'a, T, F> Drop for RestoreGuard<'a, T, F> where F: Initializer<T> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.lazy.replace(previous);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Helpers for keeping lazy statics from leaking state between tests
+//!
+//! With the `test-support` feature enabled, every lazy static in the registry can be reset to its
+//! uninitialized state, so that each test sees them exactly as a fresh process would. `isolated`
+//! returns a guard that resets them when a test starts and again when it ends, and makes tests
+//! that use it run one at a time.
+//!
+//! Values are not snapshotted and restored: resetting drops a value and the next access runs its
+//! initializer again, so a value that was initialized before the guard was created is created
+//! anew rather than put back. A `SyncLazy` created with a value instead of an initializer has
+//! nothing to run again, so it is never reset, and changes made to it through interior mutability
+//! are kept between tests:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! use lazy_mut::test_support;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! static RUNS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! lazy_mut! {
+//!     static CONFIG: usize = RUNS.fetch_add(1, Ordering::SeqCst);
+//! }
+//!
+//! # fn main() {
+//! for expected in 0..2 {
+//!     let _guard = unsafe { test_support::isolated() };
+//!     assert_eq!(*CONFIG, expected);
+//! }
+//! assert!(!CONFIG.is_initialized());
+//! # }
+//! ```
+//!
+//! Individual values can also be replaced for the duration of a test, for instance to inject a
+//! fake connection pool into code that reads a global:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate lazy_mut;
+//!
+//! lazy_mut! {
+//!     static DATABASE_URL: String = "postgres://prod.example.com".to_string();
+//! }
+//!
+//! # fn main() {
+//! {
+//!     let _guard = unsafe { DATABASE_URL.override_for_test("sqlite::memory:".to_string()) };
+//!     assert_eq!(*DATABASE_URL, "sqlite::memory:");
+//! }
+//! assert!(!DATABASE_URL.is_initialized());
+//! assert_eq!(*DATABASE_URL, "postgres://prod.example.com");
+//! # }
+//! ```
+
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use std::sync::{Mutex, MutexGuard};
+use {registry, LazyMut};
+
+pub use sync::Override;
+
+/// Makes isolated tests run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Resets every registered lazy static to its uninitialized state
+///
+/// # Safety
+///
+/// No references to any registered lazy static may be alive, and no other thread may access one
+/// until this returns.
+pub unsafe fn reset_all() {
+    for entry in registry::entries() {
+        entry.reset();
+    }
+}
+
+/// Resets every registered lazy static, returning a guard that resets them again when dropped
+///
+/// Only one guard can exist at a time, so tests that hold one don't run concurrently with each
+/// other. Tests that access lazy statics without holding a guard can still run concurrently.
+///
+/// # Safety
+///
+/// While the guard is alive, lazy statics may only be accessed by the thread that holds it (or by
+/// threads it joins before dropping the guard), and no references to them may outlive the guard.
+pub unsafe fn isolated() -> Isolated {
+    let lock = match LOCK.lock() {
+        Ok(lock) => lock,
+        Err(err) => err.into_inner(),
+    };
+    reset_all();
+    Isolated { _lock: lock }
+}
+
+/// A guard that resets every registered lazy static when dropped, even if the test panics
+pub struct Isolated {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for Isolated {
+    fn drop(&mut self) {
+        unsafe { reset_all() }
+    }
+}
+
+impl<T> LazyMut<T> {
+    /// Replaces the value with `value` until the returned guard is dropped
+    ///
+    /// When the guard is dropped, the value is put back the way it was before, including the
+    /// initializer if it was uninitialized. Only available with the `test-support` feature.
+    pub fn override_for_test(&mut self, value: T) -> OverrideMut<'_, T> {
+        let previous = mem::replace(self, LazyMut::Value(value));
+        OverrideMut { lazy: self, previous: Some(previous) }
+    }
+}
+
+/// A guard that restores a `LazyMut` when dropped, returned by `LazyMut::override_for_test`
+///
+/// The overridden value can be accessed through the guard.
+pub struct OverrideMut<'a, T: 'a> {
+    lazy: &'a mut LazyMut<T>,
+    previous: Option<LazyMut<T>>,
+}
+
+impl<'a, T> Deref for OverrideMut<'a, T> {
+    type Target = LazyMut<T>;
+    fn deref(&self) -> &LazyMut<T> {
+        self.lazy
+    }
+}
+
+impl<'a, T> DerefMut for OverrideMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut LazyMut<T> {
+        self.lazy
+    }
+}
+
+impl<'a, T> Drop for OverrideMut<'a, T> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            *self.lazy = previous;
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Instrumentation of initializers with the `tracing` and `log` crates
+//!
+//! Every function here compiles to nothing unless the `tracing` or `log` feature is enabled.
+
+#[cfg(any(feature = "tracing", feature = "log"))]
+use core::any;
+#[cfg(feature = "log")]
+use core::cell::Cell;
+
+#[cfg(feature = "log")]
+thread_local! {
+    /// The type of the value about to be initialized implicitly on this thread, if any
+    static IMPLICIT: Cell<Option<&'static str>> = const { Cell::new(None) };
+
+    /// The name of the variable whose initializer is running on this thread, if it is known
+    static NAME: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Runs an initializer inside of a span, so that slow initializers show up in traces
+///
+/// The span records the type of the value, as well as the name of the variable if it was declared
+/// with the `lazy_mut` macro. With the `log` feature, a warning is logged afterwards if the
+/// initializer was started implicitly through `DerefMut`.
+#[inline]
+pub fn init<T, F>(init: F) -> T where F: FnOnce() -> T {
+    #[cfg(feature = "tracing")]
+    let _span = ::__tracing::info_span!(target: "lazy_mut", "init",
+        type_name = any::type_name::<T>(),
+        name = ::__tracing::field::Empty,
+    ).entered();
+    #[cfg(feature = "log")]
+    let (implicit, outer) = (IMPLICIT.with(|c| c.take()), NAME.with(|c| c.take()));
+    let val = init();
+    #[cfg(feature = "log")]
+    {
+        let name = NAME.with(|c| c.replace(outer));
+        match (implicit, name) {
+            (Some(_), Some(name)) => ::log::warn!(target: "lazy_mut",
+                "lazy value `{}` was initialized implicitly through `DerefMut`", name),
+            (Some(type_name), None) => ::log::warn!(target: "lazy_mut",
+                "lazy value of type `{}` was initialized implicitly through `DerefMut`", type_name),
+            (None, _) => {}
+        }
+    }
+    val
+}
+
+/// Reports that a value of type `T` is about to be initialized implicitly through `DerefMut`
+#[inline]
+pub fn implicit_init<T: ?Sized>() {
+    #[cfg(feature = "tracing")]
+    ::__tracing::debug!(target: "lazy_mut", type_name = any::type_name::<T>(),
+        "initializing lazy value implicitly through `DerefMut`");
+    #[cfg(feature = "log")]
+    IMPLICIT.with(|c| c.set(Some(any::type_name::<T>())));
+}
+
+/// Records the name of the variable whose initializer is running
+#[doc(hidden)]
+#[inline]
+pub fn name(name: &'static str) {
+    #[cfg(feature = "tracing")]
+    ::__tracing::Span::current().record("name", name);
+    #[cfg(feature = "log")]
+    NAME.with(|c| c.set(Some(name)));
+    let _ = name;
+}
@@ -0,0 +1,152 @@
+use core::ops::Deref;
+use core::fmt::{self, Debug};
+use std::sync::OnceLock;
+
+/// A thread-safe lazy value that can be used in a cheaper partial form until it is complete
+///
+/// The first initializer builds a partial value that is usable but incomplete, and the second
+/// builds the complete value from it. Readers that can make do with the partial value call
+/// `TwoPhaseLazy::init_partial`, which returns the complete value if it is ready and the partial
+/// value otherwise, so they don't have to wait for the expensive phase. Dereferencing always
+/// returns the complete value, running both phases if needed:
+///
+/// ```
+/// use lazy_mut::{Phase, TwoPhaseLazy};
+///
+/// static INDEX: TwoPhaseLazy<Vec<&str>> = TwoPhaseLazy::new(
+///     || vec!["lazy", "mut"],
+///     |partial| {
+///         let mut index = partial.clone();
+///         index.extend(["once", "static"]);
+///         index
+///     },
+/// );
+///
+/// assert_eq!(INDEX.phase(), Phase::Uninitialized);
+/// assert_eq!(INDEX.init_partial().len(), 2);
+/// assert_eq!(INDEX.phase(), Phase::Partial);
+///
+/// assert_eq!(INDEX.complete().len(), 4);
+/// assert_eq!(INDEX.phase(), Phase::Complete);
+/// assert_eq!(INDEX.init_partial().len(), 4);
+/// ```
+///
+/// Both values are kept once the value is complete, since readers may still be using the partial
+/// one. If another thread is running either phase, the methods that need its result wait for it.
+///
+/// Only available with the `std` feature.
+pub struct TwoPhaseLazy<T> {
+    init: fn() -> T,
+    finish: fn(&T) -> T,
+    partial: OnceLock<T>,
+    complete: OnceLock<T>,
+}
+
+/// How far a `TwoPhaseLazy` has been initialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Neither phase has finished
+    Uninitialized,
+    /// The partial value is ready, but the complete value isn't
+    Partial,
+    /// The complete value is ready
+    Complete,
+}
+
+impl<T> TwoPhaseLazy<T> {
+    /// Creates a new uninitialized value with an initializer for the partial value and a function
+    /// that completes it
+    pub const fn new(init: fn() -> T, finish: fn(&T) -> T) -> TwoPhaseLazy<T> {
+        TwoPhaseLazy {
+            init,
+            finish,
+            partial: OnceLock::new(),
+            complete: OnceLock::new(),
+        }
+    }
+
+    /// Returns the complete value if it is ready, otherwise initializing the partial value if
+    /// needed and returning it
+    #[track_caller]
+    pub fn init_partial(&self) -> &T {
+        match self.complete.get() {
+            Some(val) => val,
+            None => self.partial(),
+        }
+    }
+
+    /// Runs both phases if needed, returning a reference to the complete value
+    #[track_caller]
+    pub fn complete(&self) -> &T {
+        match self.complete.get() {
+            Some(val) => val,
+            None => self.initialize(),
+        }
+    }
+
+    #[track_caller]
+    fn partial(&self) -> &T {
+        self.partial.get_or_init(|| ::trace::init(self.init))
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&self) -> &T {
+        let partial = self.partial();
+        self.complete.get_or_init(|| ::trace::init(|| (self.finish)(partial)))
+    }
+
+    /// Returns how far the value has been initialized
+    pub fn phase(&self) -> Phase {
+        if self.complete.get().is_some() {
+            Phase::Complete
+        } else if self.partial.get().is_some() {
+            Phase::Partial
+        } else {
+            Phase::Uninitialized
+        }
+    }
+
+    /// Tries to get a reference to the complete value, returns `None` if it isn't ready
+    ///
+    /// Uses associated function syntax (`TwoPhaseLazy::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &TwoPhaseLazy<T>) -> Option<&T> {
+        this.complete.get()
+    }
+
+    /// Tries to get a reference to the complete value, or the partial value if it isn't ready,
+    /// returns `None` if neither is ready
+    ///
+    /// Uses associated function syntax (`TwoPhaseLazy::get_partial(&VAL)`)
+    #[inline]
+    pub fn get_partial(this: &TwoPhaseLazy<T>) -> Option<&T> {
+        this.complete.get().or_else(|| this.partial.get())
+    }
+
+    /// Returns `true` if the complete value is ready
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.complete.get().is_some()
+    }
+}
+
+impl<T> Deref for TwoPhaseLazy<T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        self.complete()
+    }
+}
+
+impl<T> Debug for TwoPhaseLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.complete.get(), self.partial.get()) {
+            (Some(val), _) => f.debug_tuple("Complete").field(val).finish(),
+            (None, Some(val)) => f.debug_tuple("Partial").field(val).finish(),
+            (None, None) => write!(f, "Init(..)"),
+        }
+    }
+}
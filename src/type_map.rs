@@ -0,0 +1,152 @@
+use core::any::{self, Any, TypeId};
+use core::fmt::{self, Debug};
+use std::boxed::Box;
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+/// Types that can be created on demand by a `LazyTypeMap`
+pub trait LazyDefault: Send + Sync + 'static {
+    /// Creates the value stored for this type
+    fn lazy_default() -> Self;
+}
+
+/// A thread-safe container that lazily creates one value of each type
+///
+/// Each type's value is created the first time it is requested, with `LazyDefault::lazy_default`
+/// or a given initializer, and the same value is returned from then on. One `LazyTypeMap` can
+/// replace many separate lazy statics that each hold a single service:
+///
+/// ```
+/// use lazy_mut::{LazyDefault, LazyTypeMap};
+///
+/// static SERVICES: LazyTypeMap = LazyTypeMap::new();
+///
+/// struct Clock {
+///     offset: i64,
+/// }
+///
+/// impl LazyDefault for Clock {
+///     fn lazy_default() -> Clock {
+///         Clock { offset: 0 }
+///     }
+/// }
+///
+/// struct Mailer {
+///     clock_offset: i64,
+/// }
+///
+/// impl LazyDefault for Mailer {
+///     fn lazy_default() -> Mailer {
+///         Mailer { clock_offset: SERVICES.get::<Clock>().offset }
+///     }
+/// }
+///
+/// assert!(!SERVICES.contains::<Clock>());
+/// assert_eq!(SERVICES.get::<Mailer>().clock_offset, 0);
+/// assert!(SERVICES.contains::<Clock>());
+/// ```
+///
+/// The value of one type can be created while the value of another is being created, as above,
+/// and if multiple threads request the same type at once only one of them runs its initializer.
+///
+/// Only available with the `std` feature.
+pub struct LazyTypeMap {
+    slots: RwLock<BTreeMap<TypeId, Slot>>,
+}
+
+/// A boxed `OnceLock<T>` for some type `T`, along with the name of `T`
+struct Slot {
+    name: &'static str,
+    lock: Box<dyn Any + Send + Sync>,
+}
+
+impl LazyTypeMap {
+    /// Creates a new empty container
+    pub const fn new() -> LazyTypeMap {
+        LazyTypeMap { slots: RwLock::new(BTreeMap::new()) }
+    }
+
+    /// Returns the value of a type, creating it with `LazyDefault::lazy_default` if needed
+    #[track_caller]
+    pub fn get<T>(&self) -> &T where T: LazyDefault {
+        self.get_or_init(T::lazy_default)
+    }
+
+    /// Returns the value of a type, creating it with an initializer if needed
+    #[track_caller]
+    pub fn get_or_init<T, F>(&self, init: F) -> &T
+        where T: Send + Sync + 'static, F: FnOnce() -> T
+    {
+        self.slot::<T>().get_or_init(|| ::trace::init(init))
+    }
+
+    /// Returns the value of a type if it has been created
+    pub fn try_get<T>(&self) -> Option<&T> where T: Send + Sync + 'static {
+        let slots = self.slots.read().unwrap_or_else(PoisonError::into_inner);
+        let lock = lock::<T>(slots.get(&TypeId::of::<T>())?);
+        // Slots are only removed through `&mut self`, so the box outlives this borrow
+        unsafe { (*lock).get() }
+    }
+
+    /// Returns a mutable reference to the value of a type if it has been created
+    pub fn get_mut<T>(&mut self) -> Option<&mut T> where T: Send + Sync + 'static {
+        let slots = self.slots.get_mut().unwrap_or_else(PoisonError::into_inner);
+        slots.get_mut(&TypeId::of::<T>())?.lock.downcast_mut::<OnceLock<T>>()?.get_mut()
+    }
+
+    /// Removes the value of a type, so that it is created again the next time it is requested
+    pub fn remove<T>(&mut self) -> Option<T> where T: Send + Sync + 'static {
+        let slots = self.slots.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let slot = slots.remove(&TypeId::of::<T>())?;
+        slot.lock.downcast::<OnceLock<T>>().ok()?.into_inner()
+    }
+
+    /// Returns `true` if the value of a type has been created
+    pub fn contains<T>(&self) -> bool where T: Send + Sync + 'static {
+        self.try_get::<T>().is_some()
+    }
+
+    fn slot<T>(&self) -> &OnceLock<T> where T: Send + Sync + 'static {
+        let id = TypeId::of::<T>();
+        let found = self.slots.read().unwrap_or_else(PoisonError::into_inner).get(&id).map(lock);
+        let lock = match found {
+            Some(lock) => lock,
+            None => {
+                let mut slots = self.slots.write().unwrap_or_else(PoisonError::into_inner);
+                lock(slots.entry(id).or_insert_with(Slot::new::<T>))
+            }
+        };
+        // Slots are only removed through `&mut self`, so the box outlives this borrow
+        unsafe { &*lock }
+    }
+}
+
+impl Slot {
+    fn new<T>() -> Slot where T: Send + Sync + 'static {
+        Slot {
+            name: any::type_name::<T>(),
+            lock: Box::new(OnceLock::<T>::new()),
+        }
+    }
+}
+
+/// Returns a pointer to the `OnceLock<T>` in a slot for `T`
+fn lock<T>(slot: &Slot) -> *const OnceLock<T> where T: 'static {
+    match slot.lock.downcast_ref::<OnceLock<T>>() {
+        Some(lock) => lock,
+        None => unreachable!(),
+    }
+}
+
+impl Default for LazyTypeMap {
+    fn default() -> LazyTypeMap {
+        LazyTypeMap::new()
+    }
+}
+
+impl Debug for LazyTypeMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let slots = self.slots.read().unwrap_or_else(PoisonError::into_inner);
+        f.debug_set().entries(slots.values().map(|slot| slot.name)).finish()
+    }
+}
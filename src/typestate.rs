@@ -0,0 +1,141 @@
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Debug, Display};
+use {Initializer, LazyMut};
+
+/// A lazy value that is known not to be initialized yet, which must be turned into a `Ready` to
+/// be used
+///
+/// The other lazy types check whether they are initialized on every access, and panic if they
+/// can't initialize themselves. When the point at which a value is initialized is known ahead of
+/// time, such as a setup step that runs once before the main loop, this holds the initializer in
+/// a type of its own instead. `Uninit::init` consumes it and returns a `Ready` holding the value,
+/// so using the value before it is initialized doesn't compile:
+///
+/// ```
+/// use lazy_mut::{Ready, Uninit};
+///
+/// fn serve(routes: &Ready<Vec<&'static str>>) -> usize {
+///     routes.len()
+/// }
+///
+/// let routes = Uninit::new(|| vec!["/", "/about"]);
+/// let routes = routes.init();
+///
+/// assert_eq!(serve(&routes), 2);
+/// ```
+///
+/// ```compile_fail
+/// use lazy_mut::Uninit;
+///
+/// let routes: Uninit<Vec<&str>> = Uninit::new(|| vec!["/", "/about"]);
+/// assert_eq!(routes.len(), 2);
+/// ```
+///
+/// A `LazyMut` can be split into one of the two with `LazyMut::into_state`, and either of them
+/// turned back into a `LazyMut` with `into`, for code where the lifecycle is only known in part.
+pub struct Uninit<T, F = fn() -> T> {
+    init: F,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T, F> Uninit<T, F> where F: Initializer<T> {
+    /// Creates a new uninitialized value with an initializer
+    pub const fn new(init: F) -> Uninit<T, F> {
+        Uninit { init, marker: PhantomData }
+    }
+
+    /// Runs the initializer, returning the initialized value
+    #[track_caller]
+    pub fn init(self) -> Ready<T> {
+        let init = self.init;
+        Ready { value: ::trace::init(|| init.run()) }
+    }
+
+    /// Returns the initializer without running it
+    pub fn into_init(self) -> F {
+        self.init
+    }
+}
+
+impl<T, F> Clone for Uninit<T, F> where F: Clone {
+    fn clone(&self) -> Uninit<T, F> {
+        Uninit { init: self.init.clone(), marker: PhantomData }
+    }
+}
+
+impl<T, F> Copy for Uninit<T, F> where F: Copy {}
+
+impl<T, F> Debug for Uninit<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Uninit({{uninitialized}})")
+    }
+}
+
+/// A lazy value that is known to be initialized, created by `Uninit::init`
+///
+/// This is just the value, so accessing it never checks anything. See `Uninit` for more
+/// information.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ready<T> {
+    value: T,
+}
+
+impl<T> Ready<T> {
+    /// Creates a value that is already initialized
+    pub const fn new(val: T) -> Ready<T> {
+        Ready { value: val }
+    }
+
+    /// Returns the value
+    ///
+    /// Uses associated function syntax (`Ready::into_inner(VAL)`)
+    pub fn into_inner(this: Ready<T>) -> T {
+        this.value
+    }
+}
+
+impl<T> Deref for Ready<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Ready<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Display for Ready<T> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> LazyMut<T> {
+    /// Splits the value into a `Ready` if it is initialized, or an `Uninit` otherwise
+    ///
+    /// Uses associated function syntax (`LazyMut::into_state(VAL)`)
+    pub fn into_state(this: LazyMut<T>) -> Result<Ready<T>, Uninit<T>> {
+        match this {
+            LazyMut::Init(init) => Err(Uninit::new(init)),
+            LazyMut::Value(val) => Ok(Ready::new(val)),
+        }
+    }
+}
+
+impl<T> From<Uninit<T>> for LazyMut<T> {
+    fn from(uninit: Uninit<T>) -> LazyMut<T> {
+        LazyMut::Init(uninit.init)
+    }
+}
+
+impl<T> From<Ready<T>> for LazyMut<T> {
+    fn from(ready: Ready<T>) -> LazyMut<T> {
+        LazyMut::Value(ready.value)
+    }
+}
@@ -0,0 +1,182 @@
+use core::ops::{Deref, DerefMut};
+use core::fmt::{self, Display};
+use AlreadyInitialized;
+
+/// A mutable lazy value with an `unsafe` initializer
+///
+/// Methods that may run the initializer are `unsafe` as well, so the caller must uphold the
+/// contract of the initializer wherever it could be run. For this reason, it is never initialized
+/// implicitly, and `DerefMut` panics on an uninitialized value just like `Deref`.
+///
+/// ```
+/// use lazy_mut::UnsafeLazyMut;
+///
+/// static mut COUNTER: u32 = 7;
+///
+/// /// Must not be called while another thread is accessing `COUNTER`
+/// unsafe fn read_counter() -> u32 {
+///     COUNTER
+/// }
+///
+/// let mut count = UnsafeLazyMut::Init(read_counter);
+///
+/// // This thread is the only one accessing `COUNTER`
+/// unsafe { count.init() };
+/// assert_eq!(*count, 7);
+/// ```
+#[derive(Clone, Debug)]
+pub enum UnsafeLazyMut<T> {
+    /// An initializer that will be run to obtain the first value
+    Init(unsafe fn() -> T),
+    /// The value from the initializer
+    Value(T),
+}
+
+impl<T> UnsafeLazyMut<T> {
+    /// Returns the wrapped value, initializing if needed
+    ///
+    /// # Safety
+    ///
+    /// If the value is uninitialized, the requirements of the initializer must be upheld.
+    pub unsafe fn unwrap(self) -> T {
+        use self::UnsafeLazyMut::*;
+        match self {
+            Init(init) => ::trace::init(|| init()),
+            Value(val) => val,
+        }
+    }
+
+    /// Initializes the wrapped value if it is uninitialized
+    ///
+    /// # Safety
+    ///
+    /// If the value is uninitialized, the requirements of the initializer must be upheld.
+    pub unsafe fn init(&mut self) -> &mut UnsafeLazyMut<T> {
+        use self::UnsafeLazyMut::*;
+        let new = match *self {
+            Init(init) => Value(::trace::init(|| init())),
+            _ => return self,
+        };
+        *self = new;
+        self
+    }
+
+    /// Initializes the wrapped value, returning an error if it was already initialized
+    ///
+    /// # Safety
+    ///
+    /// The requirements of the initializer must be upheld.
+    #[track_caller]
+    pub unsafe fn try_init_once(&mut self) -> Result<&mut T, AlreadyInitialized> {
+        use self::UnsafeLazyMut::*;
+        if let Init(init) = *self {
+            *self = Value(::trace::init(|| init()));
+        } else {
+            return Err(AlreadyInitialized { location: None });
+        }
+        match *self {
+            Init(_) => unreachable!(),
+            Value(ref mut val) => Ok(val),
+        }
+    }
+
+    /// Runs the initializer and returns its result, leaving the value uninitialized
+    ///
+    /// This panics if the value has already been initialized. See `LazyMut::peek_init`.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of the initializer must be upheld.
+    #[track_caller]
+    pub unsafe fn peek_init(&self) -> T {
+        match *self {
+            UnsafeLazyMut::Init(init) => init(),
+            UnsafeLazyMut::Value(_) => ::already_peeked(),
+        }
+    }
+
+    /// Initializes the wrapped value, panicking if it was already initialized
+    ///
+    /// # Safety
+    ///
+    /// The requirements of the initializer must be upheld.
+    #[track_caller]
+    pub unsafe fn init_once(&mut self) -> &mut UnsafeLazyMut<T> {
+        use self::UnsafeLazyMut::*;
+        let new = match *self {
+            Init(init) => Value(::trace::init(|| init())),
+            _ => ::already_initialized(None),
+        };
+        *self = new;
+        self
+    }
+
+    /// Tries to get a reference to the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`UnsafeLazyMut::get(&VAL)`)
+    #[inline]
+    pub fn get(this: &UnsafeLazyMut<T>) -> Option<&T> {
+        use self::UnsafeLazyMut::*;
+        match *this {
+            Init(_) => None,
+            Value(ref val) => Some(val),
+        }
+    }
+
+    /// Tries to get a mutable reference the value, returns `None` if the value is uninitialized
+    ///
+    /// Uses associated function syntax (`UnsafeLazyMut::get_mut(&mut VAL)`)
+    #[inline]
+    pub fn get_mut(this: &mut UnsafeLazyMut<T>) -> Option<&mut T> {
+        use self::UnsafeLazyMut::*;
+        match *this {
+            Init(_) => None,
+            Value(ref mut val) => Some(val),
+        }
+    }
+
+    /// Returns `true` if the wrapped value has been initialized
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        use self::UnsafeLazyMut::*;
+        match *self {
+            Init(_) => false,
+            Value(_) => true,
+        }
+    }
+}
+
+impl<T> Deref for UnsafeLazyMut<T> {
+    type Target = T;
+    #[inline]
+    #[track_caller]
+    fn deref(&self) -> &T {
+        use self::UnsafeLazyMut::*;
+        match *self {
+            Init(_) => ::uninitialized::<T>(None),
+            Value(ref val) => val,
+        }
+    }
+}
+
+impl<T> DerefMut for UnsafeLazyMut<T> {
+    #[inline]
+    #[track_caller]
+    fn deref_mut(&mut self) -> &mut T {
+        use self::UnsafeLazyMut::*;
+        match *self {
+            Init(_) => ::uninitialized::<T>(None),
+            Value(ref mut val) => val,
+        }
+    }
+}
+
+impl<T> Display for UnsafeLazyMut<T> where T: Display {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::UnsafeLazyMut::*;
+        match *self {
+            Init(_) => write!(f, "{{uninitialized}}"),
+            Value(ref val) => val.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use core::fmt::Debug;
+use Initializer;
+
+/// An initializer that checks the value it produces before it can be used
+///
+/// The validator runs right after the initializer. If it returns an error, the initializer panics
+/// with that error instead of returning the value, so a lazy value never serves data that failed
+/// validation. With `SyncLazy`, this poisons the value, so every later access panics as well:
+///
+/// ```
+/// use lazy_mut::{LazyState, LazyValue, SyncLazy, Validated};
+/// use std::panic;
+///
+/// struct Config {
+///     workers: usize,
+/// }
+///
+/// fn load_config() -> Config {
+///     Config { workers: 0 }
+/// }
+///
+/// fn check_config(config: &Config) -> Result<(), &'static str> {
+///     if config.workers == 0 { Err("at least one worker is required") } else { Ok(()) }
+/// }
+///
+/// type ConfigInit = Validated<fn() -> Config, fn(&Config) -> Result<(), &'static str>>;
+///
+/// static CONFIG: SyncLazy<Config, ConfigInit> =
+///     SyncLazy::new(Validated::new(load_config, check_config));
+///
+/// assert!(panic::catch_unwind(|| CONFIG.workers).is_err());
+/// assert_eq!(CONFIG.state(), LazyState::Poisoned);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Validated<F, V> {
+    init: F,
+    validate: V,
+}
+
+impl<F, V> Validated<F, V> {
+    /// Creates an initializer that runs `init` and then checks its result with `validate`
+    pub const fn new(init: F, validate: V) -> Validated<F, V> {
+        Validated { init, validate }
+    }
+}
+
+impl<T, E, F, V> Initializer<T> for Validated<F, V>
+    where F: Initializer<T>, V: FnOnce(&T) -> Result<(), E>, E: Debug
+{
+    #[track_caller]
+    fn run(self) -> T {
+        let val = self.init.run();
+        if let Err(err) = (self.validate)(&val) {
+            invalid(&err);
+        }
+        val
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn invalid(err: &dyn Debug) -> ! {
+    panic!("lazy value failed validation: {:?}", err)
+}
@@ -0,0 +1,215 @@
+#[cfg(feature = "std")]
+use core::any;
+#[cfg(feature = "std")]
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use {ContextLazy, GenLazy, HotSwap, LazyEnvVar, ProgressLazy, TwoPhaseLazy};
+#[cfg(feature = "std")]
+use regen::RegenLazy;
+#[cfg(feature = "persist")]
+use persist::PersistLazy;
+#[cfg(feature = "persist")]
+use serde::Serialize;
+#[cfg(feature = "persist")]
+use serde::de::DeserializeOwned;
+#[cfg(all(feature = "shared-mem", unix))]
+use shared_mem::{Pod, SharedMemLazy};
+
+/// How far a lazy value has been initialized, as reported by `LazyValue::state`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LazyState {
+    /// The initializer hasn't run yet
+    Uninitialized,
+    /// The initializer is currently running
+    Initializing,
+    /// The value has been initialized
+    Initialized,
+    /// The initializer panicked, so the value can't be used
+    Poisoned,
+}
+
+/// Operations shared by every lazy type that can be initialized through a shared reference
+///
+/// This trait is object safe, so lazy values of different types can be collected together and
+/// driven from one place, such as startup code that initializes a list of them in order:
+///
+/// ```
+/// use lazy_mut::{HotSwap, LazyState, LazyValue, SyncLazy};
+///
+/// static NAMES: SyncLazy<Vec<&str>> = SyncLazy::new(|| vec!["a", "b"]);
+/// static CONFIG: HotSwap<String> = HotSwap::new(|| "debug = true".to_string());
+///
+/// let lazies: Vec<&'static dyn LazyValue> = vec![&NAMES, &CONFIG];
+///
+/// for lazy in &lazies {
+///     assert_eq!(lazy.state(), LazyState::Uninitialized);
+///     lazy.init_dyn();
+/// }
+///
+/// assert!(lazies.iter().all(|lazy| lazy.is_initialized()));
+/// assert_eq!(lazies[0].name(), "alloc::vec::Vec<&str>");
+/// ```
+///
+/// Types that only report whether they are initialized, and not whether their initializer is
+/// running, never report `LazyState::Initializing` or `LazyState::Poisoned`.
+pub trait LazyValue {
+    /// Returns a name for the value, which is its own name if it has one and otherwise the name of
+    /// the type of the value
+    fn name(&self) -> &'static str;
+
+    /// Returns `true` if the value has been initialized
+    fn is_initialized(&self) -> bool {
+        self.state() == LazyState::Initialized
+    }
+
+    /// Initializes the value if it is uninitialized
+    ///
+    /// This panics in the same cases that accessing the value would.
+    fn init_dyn(&self);
+
+    /// Returns how far the value has been initialized
+    fn state(&self) -> LazyState;
+}
+
+/// Returns the state of a value that can only report whether it is initialized
+#[cfg(feature = "std")]
+fn ready(initialized: bool) -> LazyState {
+    if initialized { LazyState::Initialized } else { LazyState::Uninitialized }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> LazyValue for HotSwap<T, F> where F: Fn() -> T {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.load();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(HotSwap::is_initialized(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> LazyValue for GenLazy<T, F> where F: Fn() -> T {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.read();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(GenLazy::is_initialized(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> LazyValue for RegenLazy<T, F> where F: Fn() -> T {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.load();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(self.is_cached())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> LazyValue for LazyEnvVar<T> where T: FromStr, T::Err: Display {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        let _: &T = self;
+    }
+
+    fn state(&self) -> LazyState {
+        ready(LazyEnvVar::is_initialized(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> LazyValue for ProgressLazy<T> {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(ProgressLazy::is_initialized(self))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> LazyValue for TwoPhaseLazy<T> {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.complete();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(self.is_complete())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> LazyValue for ContextLazy<T> {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(ContextLazy::is_initialized(self))
+    }
+}
+
+#[cfg(feature = "persist")]
+impl<T> LazyValue for PersistLazy<T> where T: Serialize + DeserializeOwned {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(PersistLazy::is_initialized(self))
+    }
+}
+
+#[cfg(all(feature = "shared-mem", unix))]
+impl<T> LazyValue for SharedMemLazy<T> where T: Pod {
+    fn name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn init_dyn(&self) {
+        self.init();
+    }
+
+    fn state(&self) -> LazyState {
+        ready(SharedMemLazy::get(self).is_some())
+    }
+}
@@ -0,0 +1,193 @@
+//! Lazy values that are reloaded when a file changes
+//!
+//! With the `watch` feature enabled, a `WatchLazy<T>` reads a file with its initializer on first
+//! access like any other lazy value, and then watches the file with the `notify` crate. Whenever
+//! the file changes, the initializer runs again on a background thread and the value is replaced,
+//! so that a configuration file can be edited while the program is running:
+//!
+//! ```no_run
+//! use lazy_mut::watch::WatchLazy;
+//! use std::fs;
+//! use std::path::Path;
+//! use std::time::Duration;
+//!
+//! static CONFIG: WatchLazy<String> = WatchLazy::new("config.toml", read_config)
+//!     .debounce(Duration::from_millis(500));
+//!
+//! fn read_config(path: &Path) -> String {
+//!     fs::read_to_string(path).unwrap_or_default()
+//! }
+//!
+//! let config = CONFIG.load();
+//! println!("current config: {}", config);
+//! ```
+//!
+//! Editors often write a file more than once when saving it, so changes are debounced: the value
+//! is only reloaded once no more changes have been seen for the debounce duration (100
+//! milliseconds by default). The parent directory of the file is watched rather than the file
+//! itself, so changes are still seen when an editor replaces the file instead of writing to it.
+//!
+//! If the initializer panics while reloading, the previous value is kept. If the file can't be
+//! watched, the value is still initialized but never reloaded, and a warning is printed (or
+//! logged with the `log` feature).
+
+use core::fmt::{self, Debug};
+use std::ffi::OsStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::thread;
+use std::time::Duration;
+use notify::{self, Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// A thread-safe lazy value that is reloaded from a file whenever the file changes
+///
+/// See the module documentation for more information.
+pub struct WatchLazy<T> {
+    path: &'static str,
+    init: fn(&Path) -> T,
+    debounce: Duration,
+    value: RwLock<Option<Arc<T>>>,
+    loading: Mutex<()>,
+    watcher: OnceLock<Option<RecommendedWatcher>>,
+}
+
+impl<T> WatchLazy<T> where T: Send + Sync + 'static {
+    /// Creates a new uninitialized value that is read from a file with an initializer
+    pub const fn new(path: &'static str, init: fn(&Path) -> T) -> WatchLazy<T> {
+        WatchLazy {
+            path,
+            init,
+            debounce: Duration::from_millis(100),
+            value: RwLock::new(None),
+            loading: Mutex::new(()),
+            watcher: OnceLock::new(),
+        }
+    }
+
+    /// Sets how long to wait for more changes to the file before reloading the value
+    pub const fn debounce(mut self, debounce: Duration) -> WatchLazy<T> {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns the current value, initializing it and starting to watch the file if needed
+    ///
+    /// This only blocks while the value is being initialized for the first time.
+    #[track_caller]
+    pub fn load(&'static self) -> Arc<T> {
+        if let Some(val) = self.get() {
+            return val;
+        }
+        self.initialize()
+    }
+
+    /// Runs the initializer again and replaces the value with its result, returning the
+    /// previous value if there was one
+    ///
+    /// This is called automatically when the file changes, but can also be called directly.
+    #[track_caller]
+    pub fn reload(&self) -> Option<Arc<T>> {
+        let val = Arc::new(::trace::init(|| (self.init)(self.path())));
+        let mut guard = self.value.write().unwrap_or_else(PoisonError::into_inner);
+        guard.replace(val)
+    }
+
+    /// Returns `true` if the file is being watched for changes
+    pub fn is_watching(&self) -> bool {
+        matches!(self.watcher.get(), Some(Some(_)))
+    }
+
+    #[cold]
+    #[inline(never)]
+    #[track_caller]
+    fn initialize(&'static self) -> Arc<T> {
+        let _loading = self.loading.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(val) = self.get() {
+            return val;
+        }
+        let val = Arc::new(::trace::init(|| (self.init)(self.path())));
+        *self.value.write().unwrap_or_else(PoisonError::into_inner) = Some(val.clone());
+        self.watcher.get_or_init(|| match self.watch() {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                warn(self.path, &err);
+                None
+            }
+        });
+        val
+    }
+
+    fn watch(&'static self) -> notify::Result<RecommendedWatcher> {
+        let path = self.path();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let name = path.file_name().map(OsStr::to_os_string);
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                if event.paths.iter().any(|changed| changed.file_name() == name.as_deref()) {
+                    let _ = sender.send(());
+                }
+            }
+        })?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        thread::Builder::new()
+            .name("lazy_mut-watch".into())
+            .spawn(move || {
+                while receiver.recv().is_ok() {
+                    loop {
+                        match receiver.recv_timeout(self.debounce) {
+                            Ok(()) => continue,
+                            Err(RecvTimeoutError::Timeout) => break,
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| self.reload()));
+                }
+            })
+            .map_err(notify::Error::io)?;
+        Ok(watcher)
+    }
+}
+
+impl<T> WatchLazy<T> {
+    /// Returns the current value without initializing it, returns `None` if the value is
+    /// uninitialized
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.value.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Returns the path of the file
+    #[inline]
+    pub fn path(&self) -> &Path {
+        Path::new(self.path)
+    }
+
+    /// Returns `true` if the value has been initialized
+    pub fn is_initialized(&self) -> bool {
+        self.value.read().unwrap_or_else(PoisonError::into_inner).is_some()
+    }
+}
+
+impl<T> Debug for WatchLazy<T> where T: Debug {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.get() {
+            Some(val) => f.debug_tuple("WatchLazy").field(&val).finish(),
+            None => write!(f, "WatchLazy({{uninitialized}})"),
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+fn warn(path: &str, err: &notify::Error) {
+    ::log::warn!(target: "lazy_mut", "cannot watch `{}` for changes: {}", path, err);
+}
+
+#[cfg(not(feature = "log"))]
+fn warn(path: &str, err: &notify::Error) {
+    eprintln!("cannot watch `{}` for changes: {}", path, err);
+}
@@ -0,0 +1,91 @@
+//! Checks the code generated for accesses to initialized lazy values
+//!
+//! This builds `examples/codegen.rs` in release mode, emitting assembly, and checks each probe in
+//! it: taking the path for an initialized value must not call anything, and every call on the
+//! other paths must go to one of the out-of-line functions that initialize a value or panic. The
+//! path for an initialized value must also stay within a handful of instructions, so that
+//! accesses can be inlined. Only x86-64 assembly is checked, and not under Miri.
+
+#![cfg(all(target_arch = "x86_64", not(miri)))]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The most instructions that each probe may run before returning when the value is initialized
+const PROBES: &[(&str, usize)] = &[
+    ("probe_lazy_mut_deref", 4),
+    ("probe_lazy_mut_init", 12),
+    ("probe_sync_lazy_deref", 4),
+    ("probe_compact_lazy_deref_mut", 10),
+];
+
+/// Parts of the names of the functions that the probes may call, all of which are `#[cold]`
+const COLD: &[&str] = &["initialize", "uninitialized", "poisoned"];
+
+fn assembly() -> String {
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("codegen");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["rustc", "--quiet", "--release", "--example", "codegen", "--target-dir"])
+        .arg(&target_dir)
+        .args(["--", "--emit", "asm", "-C", "codegen-units=1"])
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "failed to build the codegen example");
+
+    let examples = target_dir.join("release").join("examples");
+    let mut files: Vec<_> = fs::read_dir(&examples)
+        .expect("failed to read the examples directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("codegen-") && name.ends_with(".s")
+        })
+        .collect();
+    files.sort_by_key(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok());
+    let file = files.pop().expect("no assembly was emitted for the codegen example");
+    fs::read_to_string(file).expect("failed to read the assembly")
+}
+
+/// Returns the instructions of a function, without directives or labels
+fn instructions<'a>(asm: &'a str, name: &str) -> Vec<&'a str> {
+    let start = format!("{}:", name);
+    asm.lines()
+        .skip_while(|line| *line != start)
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with(".cfi_endproc"))
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('.') && !line.ends_with(':'))
+        .collect()
+}
+
+#[test]
+fn initialized_accesses_stay_small() {
+    let asm = assembly();
+    for &(name, limit) in PROBES {
+        let body = instructions(&asm, name);
+        assert!(!body.is_empty(), "`{}` not found in the assembly", name);
+
+        let fast = body.iter().position(|line| line.starts_with("ret")).map(|ret| &body[..ret]);
+        let fast = fast.unwrap_or_else(|| panic!("`{}` never returns", name));
+        assert!(fast.iter().all(|line| !line.starts_with("call")),
+            "`{}` makes a call when the value is initialized:\n{}", name, body.join("\n"));
+        assert!(fast.len() <= limit,
+            "`{}` takes {} instructions when the value is initialized, more than {}:\n{}",
+            name, fast.len(), limit, body.join("\n"));
+
+        for line in &body {
+            let target = match line.split_whitespace().next() {
+                Some(op) if op.starts_with("call") || op.starts_with("jmp") => &line[op.len()..],
+                _ => continue,
+            };
+            let target = target.trim();
+            if target.starts_with(".L") {
+                continue;
+            }
+            assert!(COLD.iter().any(|cold| target.contains(cold)),
+                "`{}` calls `{}`, which isn't out of line:\n{}", name, target, body.join("\n"));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Uses every form of the `lazy_mut` and `const_lazy` macros the way the crate documentation
+//! recommends, so that the expansions can be checked for undefined behavior under Miri:
+//!
+//! ```text
+//! cargo +nightly miri test --test macros
+//! ```
+//!
+//! Each `static mut` is only reached through a raw pointer from `ptr::addr_of_mut!`, and each
+//! reference made from it ends before the next access starts.
+
+#![cfg(not(feature = "strict-statics"))]
+
+#[macro_use]
+extern crate lazy_mut;
+
+use lazy_mut::LazyMut;
+use std::ptr;
+use std::thread;
+
+mod config {
+    lazy_mut! {
+        pub static mut NAMES: Vec<&'static str> = vec!["a"];
+        pub(crate) static mut LIMIT: usize = 2;
+        pub static GREETING: String = "hello".to_string();
+        pub(crate) static ANSWER: u32 = 42;
+    }
+
+    const_lazy! {
+        pub static mut RETRIES: u32 = 3;
+        pub(crate) static mut BACKOFF: u64 = 100;
+        pub static PORT: u16 = 8080;
+        pub(crate) static HOST: &'static str = "localhost";
+    }
+}
+
+lazy_mut! {
+    static mut TOTAL: Vec<u64> = Vec::new();
+    static PRIMES: Vec<u32> = vec![2, 3, 5];
+}
+
+const_lazy! {
+    static mut STEP: u64 = 1;
+    static VERSION: u32 = 7;
+}
+
+#[test]
+fn local() {
+    lazy_mut! {
+        let mut local: Vec<u8> = Vec::new();
+    }
+    assert!(!local.is_initialized());
+    local.init();
+    local.push(1);
+    assert_eq!(*local, [1]);
+}
+
+#[test]
+fn static_mut() {
+    unsafe {
+        let names = ptr::addr_of_mut!(config::NAMES);
+        (*names).init();
+        (*names).push("b");
+        assert_eq!(**names, ["a", "b"]);
+
+        let limit = ptr::addr_of_mut!(config::LIMIT);
+        **limit += 1;
+        assert_eq!(**limit, 3);
+
+        let retries = ptr::addr_of_mut!(config::RETRIES);
+        let backoff = ptr::addr_of_mut!(config::BACKOFF);
+        **retries -= 1;
+        **backoff *= 2;
+        assert_eq!((**retries, **backoff), (2, 200));
+    }
+}
+
+#[test]
+fn static_mut_through_raw_pointers() {
+    unsafe {
+        let total = ptr::addr_of_mut!(TOTAL);
+        assert!(LazyMut::raw_get(total).is_null());
+
+        let values = LazyMut::raw_init(total);
+        (*values).push(5);
+
+        // A shared reference to the value ends before the value is written again
+        assert_eq!(LazyMut::get(&*total).map(|values| values.len()), Some(1));
+        (*LazyMut::raw_get_mut(total)).push(6);
+        assert_eq!(*LazyMut::raw_get(total), [5, 6]);
+
+        let step = ptr::addr_of_mut!(STEP);
+        *LazyMut::raw_get_mut(step) += 1;
+        assert_eq!(*LazyMut::raw_get(step), 2);
+    }
+}
+
+#[test]
+fn thread_safe_statics() {
+    let threads: Vec<_> = (0..4).map(|_| thread::spawn(|| {
+        (config::GREETING.len(), *config::ANSWER, PRIMES.iter().sum::<u32>())
+    })).collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), (5, 42, 10));
+    }
+    assert_eq!((*config::PORT, *config::HOST, *VERSION), (8080, "localhost", 7));
+}